@@ -17,6 +17,8 @@ pub struct AppState {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
     let internal_builder = ssl_builder();
     let external_builder = ssl_builder();
 
@@ -1,4 +1,4 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 
 use argonautica::Verifier;
 use sea_orm::{entity::*, QueryFilter};
@@ -10,6 +10,7 @@ use entity::auth;
 use crate::models::{Claim, LoginData, RequestType, Token};
 
 pub async fn jwt(
+    req: HttpRequest,
     form: web::Json<LoginData>,
     db: web::Data<crate::AppState>,
     path: web::Path<String>,
@@ -22,25 +23,52 @@ pub async fn jwt(
         .await
         .unwrap();
 
-    return match get_auth {
+    match get_auth {
         Some(auth) => {
+            let user = entity::users::Entity::find()
+                .filter(entity::users::Column::AuthId.eq(auth.id))
+                .one(&db.conn)
+                .await
+                .unwrap()
+                .unwrap();
+
             let valid = Verifier::default()
                 .with_hash(auth.user_password.clone())
                 .with_password(form.password.clone())
                 .with_secret_key(secret)
                 .verify()
-                .unwrap();
+                // Deleted accounts keep an empty hash nothing verifies against.
+                .unwrap_or(false);
+
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
 
             if valid {
+                // Deleted accounts only live on as the author of deleted buzzes.
+                if user.deleted_at.is_some() {
+                    return HttpResponse::NotFound().json("User not found");
+                }
+
+                // A suspension without an end lasts until a moderator lifts it.
+                let now = chrono::Utc::now();
+                if user.suspended_at.is_some()
+                    && user.suspended_until.is_none_or(|until| until > now)
+                {
+                    return HttpResponse::Forbidden().json("Account suspended");
+                }
+
                 let token: Token;
-                let user_id: i64 = entity::users::Entity::find()
-                    .filter(entity::users::Column::AuthId.eq(auth.id))
-                    .one(&db.conn)
-                    .await
-                    .unwrap()
-                    .unwrap()
-                    .id;
+                let user_id = user.id;
                 if path.as_str() == "login" {
+                    // Logging in during the grace period keeps the account.
+                    if user.deletion_requested_at.is_some() {
+                        let mut user: entity::users::ActiveModel = user.into();
+                        user.deletion_requested_at = Set(None);
+                        user.update(&db.conn).await.unwrap();
+                        record_security_event(&db, user_id, "deletion_cancelled", &ip_address)
+                            .await;
+                    }
+                    record_security_event(&db, user_id, "login", &ip_address).await;
+
                     token = Token {
                         jwt: generate_token(auth, user_id, RequestType::Login),
                         user_id,
@@ -55,11 +83,34 @@ pub async fn jwt(
                 }
                 HttpResponse::Ok().json(token)
             } else {
+                if user.deleted_at.is_none() {
+                    record_security_event(&db, user.id, "login_failed", &ip_address).await;
+                }
                 HttpResponse::Forbidden().json("Invalid password")
             }
         }
         None => HttpResponse::NotFound().json("User not found"),
+    }
+}
+
+/// Leaves a line in the user's `security_events`, which their data export
+/// includes. A failure to record does not stand in the way of logging in.
+async fn record_security_event(
+    db: &crate::AppState,
+    user_id: i64,
+    kind: &str,
+    ip_address: &Option<String>,
+) {
+    let event = entity::security_events::ActiveModel {
+        user_id: Set(user_id),
+        kind: Set(kind.to_string()),
+        ip_address: Set(ip_address.clone()),
+        created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+        ..Default::default()
     };
+    if let Err(e) = event.insert(&db.conn).await {
+        log::error!("recording security event failed: {}", e);
+    }
 }
 
 fn generate_token(auth: auth::Model, user_id: i64, request_type: RequestType) -> String {
@@ -19,7 +19,11 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub mentioned_users: Option<String>,
     pub ratings_id: Option<i64>,
+    pub quoted_buzz_id: Option<i64>,
     pub created_at: DateTimeWithTimeZone,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub hidden_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -40,8 +44,32 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Users,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::QuotedBuzzId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
     #[sea_orm(has_many = "super::reply::Entity")]
     Reply,
+    #[sea_orm(has_many = "super::buzz_revisions::Entity")]
+    BuzzRevisions,
+    #[sea_orm(has_many = "super::buzz_hashtags::Entity")]
+    BuzzHashtags,
+    #[sea_orm(has_many = "super::rebuzz::Entity")]
+    Rebuzz,
+    #[sea_orm(has_many = "super::bookmarks::Entity")]
+    Bookmarks,
+    #[sea_orm(has_many = "super::mentions::Entity")]
+    Mentions,
+    #[sea_orm(has_many = "super::notifications::Entity")]
+    Notifications,
+    #[sea_orm(has_many = "super::reports::Entity")]
+    Reports,
+    #[sea_orm(has_one = "super::polls::Entity")]
+    Polls,
 }
 
 impl Related<super::ratings::Entity> for Entity {
@@ -62,4 +90,52 @@ impl Related<super::reply::Entity> for Entity {
     }
 }
 
+impl Related<super::buzz_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuzzRevisions.def()
+    }
+}
+
+impl Related<super::rebuzz::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Rebuzz.def()
+    }
+}
+
+impl Related<super::bookmarks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bookmarks.def()
+    }
+}
+
+impl Related<super::buzz_hashtags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuzzHashtags.def()
+    }
+}
+
+impl Related<super::mentions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mentions.def()
+    }
+}
+
+impl Related<super::notifications::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Notifications.def()
+    }
+}
+
+impl Related<super::reports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reports.def()
+    }
+}
+
+impl Related<super::polls::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Polls.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -3,8 +3,32 @@
 pub mod prelude;
 
 pub mod auth;
+pub mod blocks;
+pub mod bookmarks;
 pub mod buzz;
+pub mod buzz_hashtags;
+pub mod buzz_revisions;
+pub mod conversation_members;
+pub mod conversations;
+pub mod data_exports;
+pub mod follow_requests;
+pub mod follows;
+pub mod hashtags;
+pub mod mentions;
+pub mod messages;
+pub mod moderation_actions;
+pub mod mutes;
+pub mod notifications;
+pub mod poll_options;
+pub mod poll_votes;
+pub mod polls;
+pub mod rate_limit_buckets;
 pub mod ratings;
+pub mod rebuzz;
 pub mod reply;
+pub mod reports;
+pub mod security_events;
 pub mod trending;
+pub mod upvotes;
 pub mod users;
+pub mod view_events;
@@ -1,8 +1,32 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
 pub use super::auth::Entity as Auth;
+pub use super::blocks::Entity as Blocks;
+pub use super::bookmarks::Entity as Bookmarks;
 pub use super::buzz::Entity as Buzz;
+pub use super::buzz_hashtags::Entity as BuzzHashtags;
+pub use super::buzz_revisions::Entity as BuzzRevisions;
+pub use super::conversation_members::Entity as ConversationMembers;
+pub use super::conversations::Entity as Conversations;
+pub use super::data_exports::Entity as DataExports;
+pub use super::follow_requests::Entity as FollowRequests;
+pub use super::follows::Entity as Follows;
+pub use super::hashtags::Entity as Hashtags;
+pub use super::mentions::Entity as Mentions;
+pub use super::messages::Entity as Messages;
+pub use super::moderation_actions::Entity as ModerationActions;
+pub use super::mutes::Entity as Mutes;
+pub use super::notifications::Entity as Notifications;
+pub use super::poll_options::Entity as PollOptions;
+pub use super::poll_votes::Entity as PollVotes;
+pub use super::polls::Entity as Polls;
+pub use super::rate_limit_buckets::Entity as RateLimitBuckets;
 pub use super::ratings::Entity as Ratings;
+pub use super::rebuzz::Entity as Rebuzz;
 pub use super::reply::Entity as Reply;
+pub use super::reports::Entity as Reports;
+pub use super::security_events::Entity as SecurityEvents;
 pub use super::trending::Entity as Trending;
+pub use super::upvotes::Entity as Upvotes;
 pub use super::users::Entity as Users;
+pub use super::view_events::Entity as ViewEvents;
@@ -9,8 +9,8 @@ pub struct Model {
     pub id: i64,
     pub upvotes: Option<i64>,
     pub views: Option<i64>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub upvoted_by: Option<String>,
+    pub rebuzzes: Option<i64>,
+    pub quotes: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -19,6 +19,10 @@ pub enum Relation {
     Buzz,
     #[sea_orm(has_many = "super::reply::Entity")]
     Reply,
+    #[sea_orm(has_many = "super::view_events::Entity")]
+    ViewEvents,
+    #[sea_orm(has_many = "super::upvotes::Entity")]
+    Upvotes,
 }
 
 impl Related<super::buzz::Entity> for Entity {
@@ -33,4 +37,16 @@ impl Related<super::reply::Entity> for Entity {
     }
 }
 
+impl Related<super::view_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ViewEvents.def()
+    }
+}
+
+impl Related<super::upvotes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Upvotes.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
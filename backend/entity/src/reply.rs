@@ -9,6 +9,7 @@ pub struct Model {
     pub id: i64,
     pub user_id: i64,
     pub buzz_id: i64,
+    pub parent_reply_id: Option<i64>,
     #[sea_orm(column_type = "Text")]
     pub reply_content: String,
     #[sea_orm(column_type = "Text", nullable)]
@@ -17,6 +18,9 @@ pub struct Model {
     pub mentioned_users: Option<String>,
     pub ratings_id: Option<i64>,
     pub created_at: DateTimeWithTimeZone,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+    pub hidden_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -45,6 +49,24 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Users,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentReplyId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::buzz_revisions::Entity")]
+    BuzzRevisions,
+    #[sea_orm(has_many = "super::buzz_hashtags::Entity")]
+    BuzzHashtags,
+    #[sea_orm(has_many = "super::mentions::Entity")]
+    Mentions,
+    #[sea_orm(has_many = "super::notifications::Entity")]
+    Notifications,
+    #[sea_orm(has_many = "super::reports::Entity")]
+    Reports,
 }
 
 impl Related<super::buzz::Entity> for Entity {
@@ -65,4 +87,34 @@ impl Related<super::users::Entity> for Entity {
     }
 }
 
+impl Related<super::buzz_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuzzRevisions.def()
+    }
+}
+
+impl Related<super::buzz_hashtags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuzzHashtags.def()
+    }
+}
+
+impl Related<super::mentions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mentions.def()
+    }
+}
+
+impl Related<super::notifications::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Notifications.def()
+    }
+}
+
+impl Related<super::reports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reports.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -12,14 +12,29 @@ pub struct Model {
     pub description: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub buzz_words: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub time_window: Option<String>,
+    pub buzz_count: Option<i64>,
+    pub rank: Option<i32>,
+    pub computed_at: Option<DateTimeWithTimeZone>,
+    pub hashtag_id: Option<i64>,
 }
 
-#[derive(Copy, Clone, Debug, EnumIter)]
-pub enum Relation {}
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::hashtags::Entity",
+        from = "Column::HashtagId",
+        to = "super::hashtags::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Hashtags,
+}
 
-impl RelationTrait for Relation {
-    fn def(&self) -> RelationDef {
-        panic!("No RelationDef")
+impl Related<super::hashtags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Hashtags.def()
     }
 }
 
@@ -16,13 +16,15 @@ pub struct Model {
     pub description: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub location_or_region: Option<String>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub following: Option<String>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub followers: Option<String>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub upvoted: Option<String>,
     pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "Text")]
+    pub dm_policy: String,
+    pub is_private: bool,
+    pub deletion_requested_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub is_moderator: bool,
+    pub suspended_at: Option<DateTimeWithTimeZone>,
+    pub suspended_until: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -39,6 +41,24 @@ pub enum Relation {
     Buzz,
     #[sea_orm(has_many = "super::reply::Entity")]
     Reply,
+    #[sea_orm(has_many = "super::rebuzz::Entity")]
+    Rebuzz,
+    #[sea_orm(has_many = "super::bookmarks::Entity")]
+    Bookmarks,
+    #[sea_orm(has_many = "super::mentions::Entity")]
+    Mentions,
+    #[sea_orm(has_many = "super::conversations::Entity")]
+    Conversations,
+    #[sea_orm(has_many = "super::conversation_members::Entity")]
+    ConversationMembers,
+    #[sea_orm(has_many = "super::messages::Entity")]
+    Messages,
+    #[sea_orm(has_many = "super::upvotes::Entity")]
+    Upvotes,
+    #[sea_orm(has_many = "super::security_events::Entity")]
+    SecurityEvents,
+    #[sea_orm(has_many = "super::data_exports::Entity")]
+    DataExports,
 }
 
 impl Related<super::auth::Entity> for Entity {
@@ -59,4 +79,58 @@ impl Related<super::reply::Entity> for Entity {
     }
 }
 
+impl Related<super::rebuzz::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Rebuzz.def()
+    }
+}
+
+impl Related<super::bookmarks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bookmarks.def()
+    }
+}
+
+impl Related<super::mentions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mentions.def()
+    }
+}
+
+impl Related<super::conversations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Conversations.def()
+    }
+}
+
+impl Related<super::conversation_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ConversationMembers.def()
+    }
+}
+
+impl Related<super::messages::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Messages.def()
+    }
+}
+
+impl Related<super::upvotes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Upvotes.def()
+    }
+}
+
+impl Related<super::security_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SecurityEvents.def()
+    }
+}
+
+impl Related<super::data_exports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DataExports.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
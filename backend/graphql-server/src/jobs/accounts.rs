@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr, TransactionTrait};
+
+use crate::lib::{
+    accounts::{delete_account, purge_deleted_accounts},
+    common::env_or,
+};
+
+/// Every `ACCOUNT_DELETION_INTERVAL_SECONDS` deletes the accounts whose
+/// deletion was asked for more than `ACCOUNT_DELETION_GRACE_DAYS` ago, and
+/// removes the anonymized ones nothing points at anymore.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("ACCOUNT_DELETION_INTERVAL_SECONDS", 3600));
+
+    loop {
+        if let Err(e) = delete_due(&connection).await {
+            log::error!("account deletion job failed: {}", e);
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn delete_due(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    let grace = chrono::Duration::days(env_or("ACCOUNT_DELETION_GRACE_DAYS", 30));
+    let requested_before: sea_orm::prelude::DateTimeWithTimeZone =
+        chrono::DateTime::from(chrono::Utc::now() - grace);
+
+    let due = entity::users::Entity::find()
+        .filter(entity::users::Column::DeletionRequestedAt.lt(requested_before))
+        .filter(entity::users::Column::DeletedAt.is_null())
+        .all(connection)
+        .await?;
+
+    // One transaction per account, a failing one does not hold up the rest.
+    for user_id in due.into_iter().map(|user| user.id) {
+        let txn = connection.begin().await?;
+
+        // The account may have cancelled its deletion since it was listed.
+        let user = entity::users::Entity::find_by_id(user_id)
+            .filter(entity::users::Column::DeletionRequestedAt.lt(requested_before))
+            .filter(entity::users::Column::DeletedAt.is_null())
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        let user = match user {
+            Some(user) => user,
+            None => {
+                txn.rollback().await?;
+                continue;
+            }
+        };
+
+        match delete_account(&txn, user).await {
+            Ok(()) => txn.commit().await?,
+            Err(e) => {
+                txn.rollback().await?;
+                log::error!("deleting account {} failed: {}", user_id, e);
+            }
+        }
+    }
+
+    let purged = purge_deleted_accounts(connection).await?;
+    if purged > 0 {
+        log::info!("removed {} deleted accounts", purged);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use sea_orm::{
+    entity::*, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement, TransactionTrait,
+};
+
+use crate::lib::{
+    common::env_or,
+    exports::{build_archive, DataExportStatus},
+};
+
+/// Every `DATA_EXPORT_INTERVAL_SECONDS` builds the archives users asked for,
+/// their links work for `DATA_EXPORT_LINK_HOURS`. Expired archives are
+/// dropped, the rows stay so users can see what they asked for.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("DATA_EXPORT_INTERVAL_SECONDS", 60));
+
+    loop {
+        if let Err(e) = build_pending(&connection).await {
+            log::error!("data export job failed: {}", e);
+        }
+        if let Err(e) = drop_expired(&connection).await {
+            log::error!("dropping expired data exports failed: {}", e);
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn build_pending(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    let link_lifetime = chrono::Duration::hours(env_or("DATA_EXPORT_LINK_HOURS", 48));
+
+    // Each export is claimed under a row lock, so servers running the job
+    // side by side never build the same one.
+    loop {
+        let txn = connection.begin().await?;
+        let pending = entity::data_exports::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT * FROM data_exports WHERE status = $1
+                ORDER BY id LIMIT 1 FOR UPDATE SKIP LOCKED"#,
+                vec![DataExportStatus::Pending.key().into()],
+            ))
+            .one(&txn)
+            .await?;
+        let data_export = match pending {
+            Some(data_export) => data_export,
+            None => return txn.commit().await,
+        };
+
+        let data_export_id = data_export.id;
+        let user_id = data_export.user_id;
+        let now = chrono::Utc::now();
+        let mut data_export: entity::data_exports::ActiveModel = data_export.into();
+        match build_archive(&txn, user_id).await {
+            Ok(archive) => {
+                data_export.status = Set(DataExportStatus::Ready.key().to_string());
+                data_export.archive = Set(Some(archive));
+                data_export.expires_at = Set(Some(chrono::DateTime::from(now + link_lifetime)));
+            }
+            Err(e) => {
+                log::error!("building data export {} failed: {:?}", data_export_id, e);
+                data_export.status = Set(DataExportStatus::Failed.key().to_string());
+            }
+        }
+        data_export.completed_at = Set(Some(chrono::DateTime::from(now)));
+        data_export.update(&txn).await?;
+        txn.commit().await?;
+    }
+}
+
+async fn drop_expired(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"UPDATE data_exports SET status = $1, archive = NULL
+            WHERE status = $2 AND expires_at < NOW()"#,
+            vec![
+                DataExportStatus::Expired.key().into(),
+                DataExportStatus::Ready.key().into(),
+            ],
+        ))
+        .await?;
+
+    Ok(())
+}
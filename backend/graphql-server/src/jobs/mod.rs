@@ -0,0 +1,18 @@
+use sea_orm::DatabaseConnection;
+
+pub mod accounts;
+pub mod exports;
+pub mod rate_limits;
+pub mod tombstones;
+pub mod trending;
+pub mod views;
+
+/// Background jobs running next to the server for as long as it is up.
+pub fn spawn_all(connection: &DatabaseConnection) {
+    actix_web::rt::spawn(trending::run(connection.clone()));
+    actix_web::rt::spawn(views::run(connection.clone()));
+    actix_web::rt::spawn(tombstones::run(connection.clone()));
+    actix_web::rt::spawn(accounts::run(connection.clone()));
+    actix_web::rt::spawn(exports::run(connection.clone()));
+    actix_web::rt::spawn(rate_limits::run(connection.clone()));
+}
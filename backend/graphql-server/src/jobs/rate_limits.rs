@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use sea_orm::{DatabaseConnection, DbErr};
+
+use crate::lib::{common::env_or, rate_limits::purge_idle_buckets};
+
+/// Every `RATE_LIMIT_PURGE_INTERVAL_SECONDS` drops buckets untouched for
+/// `RATE_LIMIT_IDLE_SECONDS`, so one row per caller does not pile up.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("RATE_LIMIT_PURGE_INTERVAL_SECONDS", 3600));
+
+    loop {
+        if let Err(e) = purge(&connection).await {
+            log::error!("rate limit job failed: {}", e);
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn purge(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    let purged = purge_idle_buckets(connection, env_or("RATE_LIMIT_IDLE_SECONDS", 86400)).await?;
+    if purged > 0 {
+        log::info!("purged {} idle rate limit buckets", purged);
+    }
+    Ok(())
+}
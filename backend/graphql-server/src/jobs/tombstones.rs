@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use sea_orm::{DatabaseConnection, DbErr};
+
+use crate::lib::{common::env_or, tombstones::purge_tombstones};
+
+/// Every `TOMBSTONES_INTERVAL_SECONDS` removes buzzes and replies deleted
+/// more than `BUZZ_TOMBSTONE_RETENTION_DAYS` ago that nobody replied to.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("TOMBSTONES_INTERVAL_SECONDS", 3600));
+
+    loop {
+        if let Err(e) = purge(&connection).await {
+            log::error!("tombstones job failed: {}", e);
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn purge(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    let retention = chrono::Duration::days(env_or("BUZZ_TOMBSTONE_RETENTION_DAYS", 30));
+    let deleted_before = chrono::DateTime::from(chrono::Utc::now() - retention);
+
+    let purged = purge_tombstones(connection, deleted_before).await?;
+    if purged > 0 {
+        log::info!("purged {} deleted buzzes and replies", purged);
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use sea_orm::{
+    entity::*, query::*, DatabaseConnection, DbBackend, DbErr, FromQueryResult, Statement,
+    TransactionTrait,
+};
+
+use crate::lib::common::env_or;
+use crate::schemas::trending::TrendingWindow;
+
+#[derive(FromQueryResult)]
+struct TagCount {
+    hashtag_id: i64,
+    buzz_count: i64,
+}
+
+#[derive(FromQueryResult)]
+struct Locked {
+    locked: bool,
+}
+
+/// Recomputes the top `TRENDING_LIMIT` hashtags of every window each
+/// `TRENDING_INTERVAL_SECONDS`, counting the buzzes and replies that used
+/// them within the window up to now. Each window is computed by one server
+/// at a time, the others skip it while it is locked.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("TRENDING_INTERVAL_SECONDS", 300));
+    let limit: i64 = env_or("TRENDING_LIMIT", 10);
+
+    loop {
+        for window in TrendingWindow::ALL {
+            if let Err(e) = compute(&connection, window, limit).await {
+                log::error!("trending job failed for window {}: {}", window.key(), e);
+            }
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn compute(
+    connection: &DatabaseConnection,
+    window: TrendingWindow,
+    limit: i64,
+) -> Result<(), DbErr> {
+    let now = chrono::Utc::now();
+    let since: chrono::DateTime<chrono::FixedOffset> =
+        chrono::DateTime::from(now - window.duration());
+
+    let txn = connection.begin().await?;
+
+    // Released when the transaction ends.
+    let locked = Locked::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_try_advisory_xact_lock(hashtext($1)) AS locked",
+        vec![format!("trending:{}", window.key()).into()],
+    ))
+    .one(&txn)
+    .await?
+    .is_some_and(|row| row.locked);
+    if !locked {
+        return txn.rollback().await;
+    }
+
+    let counts = TagCount::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"SELECT hashtags.id AS hashtag_id, COUNT(*) AS buzz_count
+            FROM buzz_hashtags JOIN hashtags ON hashtags.id = buzz_hashtags.hashtag_id
+            WHERE buzz_hashtags.created_at >= $1
+            GROUP BY hashtags.id, hashtags.tag
+            ORDER BY buzz_count DESC, hashtags.tag
+            LIMIT {}"#,
+            limit
+        ),
+        vec![since.into()],
+    ))
+    .all(&txn)
+    .await?;
+
+    entity::trending::Entity::delete_many()
+        .filter(entity::trending::Column::TimeWindow.eq(window.key()))
+        .exec(&txn)
+        .await?;
+
+    if !counts.is_empty() {
+        let computed_at: chrono::DateTime<chrono::FixedOffset> = chrono::DateTime::from(now);
+        entity::trending::Entity::insert_many(counts.into_iter().enumerate().map(
+            |(rank, count)| entity::trending::ActiveModel {
+                hashtag_id: Set(Some(count.hashtag_id)),
+                time_window: Set(Some(window.key().to_string())),
+                buzz_count: Set(Some(count.buzz_count)),
+                rank: Set(Some(rank as i32 + 1)),
+                computed_at: Set(Some(computed_at)),
+                ..Default::default()
+            },
+        ))
+        .exec(&txn)
+        .await?;
+    }
+
+    txn.commit().await
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+
+use crate::lib::{common::env_or, views::window_start};
+
+/// Every `VIEWS_INTERVAL_SECONDS` adds the views recorded since the last
+/// run to `ratings.views`, then forgets the views of past windows.
+pub async fn run(connection: DatabaseConnection) {
+    let interval = Duration::from_secs(env_or("VIEWS_INTERVAL_SECONDS", 60));
+
+    loop {
+        if let Err(e) = aggregate(&connection).await {
+            log::error!("views job failed: {}", e);
+        }
+        actix_web::rt::time::sleep(interval).await;
+    }
+}
+
+async fn aggregate(connection: &DatabaseConnection) -> Result<(), DbErr> {
+    // Marking and adding happen in one statement, a view is never counted
+    // twice even if two servers run the job at once.
+    connection
+        .execute(Statement::from_string(
+            DbBackend::Postgres,
+            r#"WITH counted AS (
+                UPDATE view_events SET counted = TRUE WHERE NOT counted
+                RETURNING ratings_id
+            ), totals AS (
+                SELECT ratings_id, COUNT(*) AS views FROM counted GROUP BY ratings_id
+            )
+            UPDATE ratings SET views = COALESCE(ratings.views, 0) + totals.views
+            FROM totals WHERE ratings.id = totals.ratings_id"#
+                .to_string(),
+        ))
+        .await?;
+
+    connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM view_events WHERE counted AND window_start < $1",
+            vec![window_start(chrono::Utc::now()).into()],
+        ))
+        .await?;
+
+    Ok(())
+}
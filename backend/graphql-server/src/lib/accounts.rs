@@ -0,0 +1,168 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbBackend, DbErr, Statement};
+
+use crate::lib::tombstones::{tombstone_buzz, tombstone_reply};
+
+/// Removes everything `user` owns. Their buzzes and replies become
+/// tombstones, so what others replied below them stays, and the ones nobody
+/// replied to are removed right away. The `users` and `auth` rows are removed
+/// once no tombstone points at them, until then they stay behind anonymized
+/// and marked `deleted_at` for `purge_deleted_accounts`. Running it again for
+/// the same user is harmless. Run it inside a transaction.
+pub async fn delete_account<C: ConnectionTrait>(
+    connection: &C,
+    user: entity::users::Model,
+) -> Result<(), DbErr> {
+    let user_id = user.id;
+
+    // Counters of what the user upvoted and rebuzzed drop before the rows go.
+    for sql in [
+        r#"UPDATE ratings SET upvotes = COALESCE(ratings.upvotes, 0) - 1
+        FROM upvotes WHERE upvotes.ratings_id = ratings.id AND upvotes.user_id = $1"#,
+        "DELETE FROM upvotes WHERE user_id = $1",
+        r#"UPDATE ratings SET rebuzzes = COALESCE(ratings.rebuzzes, 0) - 1
+        FROM rebuzz JOIN buzz ON buzz.id = rebuzz.buzz_id
+        WHERE buzz.ratings_id = ratings.id AND rebuzz.user_id = $1"#,
+        "DELETE FROM rebuzz WHERE user_id = $1",
+    ] {
+        connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                vec![user_id.into()],
+            ))
+            .await?;
+    }
+
+    for reply in entity::reply::Entity::find()
+        .filter(entity::reply::Column::UserId.eq(user_id))
+        .filter(entity::reply::Column::DeletedAt.is_null())
+        .all(connection)
+        .await?
+    {
+        tombstone_reply(connection, reply).await?;
+    }
+    // Leaf first, a reply goes once nothing hangs below it anymore.
+    loop {
+        let result = connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM reply WHERE reply.user_id = $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM reply AS child WHERE child.parent_reply_id = reply.id
+                )"#,
+                vec![user_id.into()],
+            ))
+            .await?;
+        if result.rows_affected() == 0 {
+            break;
+        }
+    }
+
+    for buzz in entity::buzz::Entity::find()
+        .filter(entity::buzz::Column::UserId.eq(user_id))
+        .filter(entity::buzz::Column::DeletedAt.is_null())
+        .all(connection)
+        .await?
+    {
+        tombstone_buzz(connection, buzz).await?;
+    }
+    connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"DELETE FROM buzz WHERE buzz.user_id = $1
+            AND NOT EXISTS (SELECT 1 FROM reply WHERE reply.buzz_id = buzz.id)"#,
+            vec![user_id.into()],
+        ))
+        .await?;
+
+    let remaining = entity::buzz::Entity::find()
+        .filter(entity::buzz::Column::UserId.eq(user_id))
+        .count(connection)
+        .await?
+        + entity::reply::Entity::find()
+            .filter(entity::reply::Column::UserId.eq(user_id))
+            .count(connection)
+            .await?;
+    if remaining == 0 {
+        // Everything else that points at the user goes with the row.
+        let auth_id = user.auth_id;
+        user.delete(connection).await?;
+        entity::auth::Entity::delete_by_id(auth_id)
+            .exec(connection)
+            .await?;
+        return Ok(());
+    }
+
+    for sql in [
+        "DELETE FROM follows WHERE follower_id = $1 OR followee_id = $1",
+        "DELETE FROM follow_requests WHERE requester_id = $1 OR target_id = $1",
+        "DELETE FROM blocks WHERE blocker_id = $1 OR blocked_id = $1",
+        "DELETE FROM mutes WHERE muter_id = $1 OR muted_id = $1",
+        "DELETE FROM bookmarks WHERE user_id = $1",
+        "DELETE FROM poll_votes WHERE user_id = $1",
+        "DELETE FROM mentions WHERE user_id = $1",
+        "DELETE FROM notifications WHERE user_id = $1 OR actor_id = $1",
+        "DELETE FROM messages WHERE sender_id = $1",
+        "DELETE FROM conversation_members WHERE user_id = $1",
+        "DELETE FROM security_events WHERE user_id = $1",
+        "DELETE FROM data_exports WHERE user_id = $1",
+        "DELETE FROM reports WHERE reporter_id = $1",
+    ] {
+        connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                vec![user_id.into()],
+            ))
+            .await?;
+    }
+
+    if user.deleted_at.is_none() {
+        let auth_id = user.auth_id;
+
+        let mut user_table: entity::users::ActiveModel = user.into();
+        user_table.full_name = Set("Deleted user".to_string());
+        user_table.profile_picture = Set(None);
+        user_table.description = Set(None);
+        user_table.location_or_region = Set(None);
+        user_table.is_private = Set(false);
+        user_table.deleted_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+        user_table.update(connection).await?;
+
+        // A new username also ends every session of the account.
+        if let Some(auth) = entity::auth::Entity::find_by_id(auth_id)
+            .one(connection)
+            .await?
+        {
+            let mut auth_table: entity::auth::ActiveModel = auth.into();
+            auth_table.username = Set(format!("deleted-{}", user_id));
+            auth_table.email = Set(format!("deleted-{}", user_id));
+            auth_table.contact_number = Set(None);
+            auth_table.user_password = Set(String::new());
+            auth_table.update(connection).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the `users` and `auth` rows of anonymized accounts once the last
+/// of their tombstones was purged. Returns how many accounts went.
+pub async fn purge_deleted_accounts<C: ConnectionTrait>(connection: &C) -> Result<u64, DbErr> {
+    let result = connection
+        .execute(Statement::from_string(
+            DbBackend::Postgres,
+            r#"WITH removed AS (
+                DELETE FROM users
+                WHERE users.deleted_at IS NOT NULL
+                AND NOT EXISTS (SELECT 1 FROM buzz WHERE buzz.user_id = users.id)
+                AND NOT EXISTS (SELECT 1 FROM reply WHERE reply.user_id = users.id)
+                RETURNING users.auth_id
+            )
+            DELETE FROM auth WHERE auth.id IN (SELECT auth_id FROM removed)"#
+                .to_string(),
+        ))
+        .await?;
+
+    Ok(result.rows_affected())
+}
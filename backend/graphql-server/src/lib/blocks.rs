@@ -0,0 +1,107 @@
+use sea_orm::{
+    entity::*,
+    query::*,
+    sea_query::{Expr, SimpleExpr},
+    ConnectionTrait, DbErr,
+};
+
+use crate::lib::errors::{AppError, AppResult};
+
+/// Whether either of the two users blocked the other.
+pub async fn is_blocked_between<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    other_id: i64,
+) -> Result<bool, DbErr> {
+    let block = entity::blocks::Entity::find()
+        .filter(
+            Condition::any()
+                .add(
+                    Condition::all()
+                        .add(entity::blocks::Column::BlockerId.eq(user_id))
+                        .add(entity::blocks::Column::BlockedId.eq(other_id)),
+                )
+                .add(
+                    Condition::all()
+                        .add(entity::blocks::Column::BlockerId.eq(other_id))
+                        .add(entity::blocks::Column::BlockedId.eq(user_id)),
+                ),
+        )
+        .one(connection)
+        .await?;
+
+    Ok(block.is_some())
+}
+
+/// Condition on `author_column` that leaves out users who blocked
+/// `viewer_id` or were blocked by them. Anonymous viewers see everyone.
+pub fn not_blocked(author_column: &str, viewer_id: Option<i64>) -> SimpleExpr {
+    Expr::cust_with_values(&not_blocked_sql(author_column, "$1"), vec![viewer_id])
+}
+
+/// `not_blocked` for hand written statements, `viewer` is the placeholder
+/// bound to the viewer id, or to NULL for anonymous viewers.
+pub fn not_blocked_sql(author_column: &str, viewer: &str) -> String {
+    format!(
+        r#"NOT EXISTS (
+            SELECT 1 FROM blocks
+            WHERE (blocks.blocker_id = {1} AND blocks.blocked_id = {0})
+            OR (blocks.blocker_id = {0} AND blocks.blocked_id = {1})
+        )"#,
+        author_column, viewer
+    )
+}
+
+/// Fails when either of the two users blocked the other.
+pub async fn require_not_blocked<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    other_id: i64,
+    message: &'static str,
+) -> AppResult<()> {
+    if is_blocked_between(connection, user_id, other_id).await? {
+        return Err(AppError::Forbidden(message));
+    }
+    Ok(())
+}
+
+/// Users whose content `user_id` should not see: everyone they blocked or
+/// were blocked by, plus the accounts they muted when `include_muted`.
+pub async fn hidden_user_ids<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    include_muted: bool,
+) -> Result<Vec<i64>, DbErr> {
+    let mut hidden = Vec::new();
+
+    for block in entity::blocks::Entity::find()
+        .filter(
+            Condition::any()
+                .add(entity::blocks::Column::BlockerId.eq(user_id))
+                .add(entity::blocks::Column::BlockedId.eq(user_id)),
+        )
+        .all(connection)
+        .await?
+    {
+        hidden.push(if block.blocker_id == user_id {
+            block.blocked_id
+        } else {
+            block.blocker_id
+        });
+    }
+
+    if include_muted {
+        hidden.extend(
+            entity::mutes::Entity::find()
+                .filter(entity::mutes::Column::MuterId.eq(user_id))
+                .all(connection)
+                .await?
+                .into_iter()
+                .map(|mute| mute.muted_id),
+        );
+    }
+
+    hidden.sort_unstable();
+    hidden.dedup();
+    Ok(hidden)
+}
@@ -0,0 +1,124 @@
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::HttpRequest;
+
+/// Reverse proxies whose `X-Forwarded-For` entries are believed. Anyone
+/// else could claim any address with them.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies {
+    proxies: Vec<IpAddr>,
+}
+
+impl TrustedProxies {
+    /// `TRUSTED_PROXIES` takes comma separated addresses such as
+    /// `10.0.0.2,10.0.0.3`, none are trusted by default.
+    pub fn from_env() -> Self {
+        let proxies = match std::env::var("TRUSTED_PROXIES") {
+            Ok(proxies) => proxies
+                .split(',')
+                .filter_map(|proxy| proxy.trim().parse::<IpAddr>().ok())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        TrustedProxies { proxies }
+    }
+
+    /// Address of whoever sent `req`. `X-Forwarded-For` is walked from the
+    /// right, as every proxy appends the address it got the request from,
+    /// and the first hop that is not a trusted proxy is the client. Entries
+    /// left of it are whatever the client put there.
+    pub fn client_ip(&self, req: &HttpRequest) -> Option<String> {
+        let peer = req.peer_addr()?.ip();
+        if !self.proxies.contains(&peer) {
+            return Some(peer.to_string());
+        }
+
+        let hops: Vec<&str> = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            let hop = hop.trim();
+            match hop
+                .parse::<IpAddr>()
+                .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+            {
+                Ok(addr) => client = addr,
+                Err(_) => break,
+            }
+            if !self.proxies.contains(&client) {
+                break;
+            }
+        }
+        Some(client.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn trusting(proxies: &[&str]) -> TrustedProxies {
+        TrustedProxies {
+            proxies: proxies.iter().map(|proxy| proxy.parse().unwrap()).collect(),
+        }
+    }
+
+    fn request(peer: &str, forwarded_for: Option<&str>) -> HttpRequest {
+        let mut request = TestRequest::default().peer_addr(peer.parse().unwrap());
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.insert_header(("x-forwarded-for", forwarded_for));
+        }
+        request.to_http_request()
+    }
+
+    #[test]
+    fn uses_the_peer_without_trusted_proxies() {
+        let req = request("203.0.113.7:5000", Some("198.51.100.1"));
+        assert_eq!(
+            TrustedProxies::default().client_ip(&req).as_deref(),
+            Some("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn believes_trusted_proxies() {
+        let proxies = trusting(&["10.0.0.2"]);
+        let req = request("10.0.0.2:5000", Some("198.51.100.1, 10.0.0.9"));
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("10.0.0.9"));
+
+        let proxies = trusting(&["10.0.0.2", "10.0.0.9"]);
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("198.51.100.1"));
+    }
+
+    #[test]
+    fn ignores_addresses_the_client_put_in_front() {
+        let proxies = trusting(&["10.0.0.2"]);
+        let req = request("10.0.0.2:5000", Some("10.0.0.2, 192.0.2.66, 198.51.100.1"));
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("198.51.100.1"));
+    }
+
+    #[test]
+    fn ignores_headers_from_other_peers() {
+        let proxies = trusting(&["10.0.0.2"]);
+        let req = request("10.0.0.3:5000", Some("198.51.100.1"));
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("10.0.0.3"));
+    }
+
+    #[test]
+    fn falls_back_to_the_proxy_without_a_header() {
+        let proxies = trusting(&["10.0.0.2"]);
+        let req = request("10.0.0.2:5000", None);
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("10.0.0.2"));
+
+        let req = request("10.0.0.2:5000", Some("not an address"));
+        assert_eq!(proxies.client_ip(&req).as_deref(), Some("10.0.0.2"));
+    }
+}
@@ -1,12 +1,83 @@
-use std::collections::HashSet;
+/// Ids joined the way `getUserFollows` and `getUserUpvotes` return them.
+pub fn to_id_list(ids: Vec<i64>) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value.parse::<T>().unwrap_or(default),
+        Err(_) => default,
+    }
+}
+
+/// The buzz or reply something was written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Tagged {
+    Buzz(i64),
+    Reply(i64),
+}
+
+/// Words written right after `prefix`, such as `#tag` or `@username`. A
+/// prefix inside a word, as in `c#` or `me@example.com`, is ignored.
+pub fn prefixed_words(text: &str, prefix: char, is_word_char: fn(char) -> bool) -> Vec<String> {
+    let mut words = Vec::new();
 
-pub fn convert_string_to_set(string: String) -> HashSet<String> {
-    string
-        .split(", ")
-        .map(|s| s.to_string())
-        .collect::<HashSet<String>>()
+    let mut previous: Option<char> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == prefix && !previous.is_some_and(is_word_char) {
+            let mut word = String::new();
+            while let Some(&next) = chars.peek() {
+                if !is_word_char(next) {
+                    break;
+                }
+                word.push(next);
+                chars.next();
+            }
+            previous = word.chars().last().or(Some(c));
+            words.push(word);
+            continue;
+        }
+        previous = Some(c);
+    }
+
+    words
 }
 
-pub fn convert_set_to_string(set: HashSet<String>) -> String {
-    set.into_iter().collect::<Vec<String>>().join(", ")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    #[test]
+    fn finds_words_after_the_prefix() {
+        assert_eq!(
+            prefixed_words("#one two #three_3, (#four)", '#', is_word_char),
+            vec!["one", "three_3", "four"]
+        );
+    }
+
+    #[test]
+    fn ignores_prefixes_inside_words() {
+        assert!(prefixed_words("c# and me@example.com", '#', is_word_char).is_empty());
+        assert!(prefixed_words("me@example.com", '@', is_word_char).is_empty());
+    }
+
+    #[test]
+    fn keeps_empty_words_for_a_lone_prefix() {
+        assert_eq!(prefixed_words("# #tag", '#', is_word_char), vec!["", "tag"]);
+        assert_eq!(prefixed_words("##tag", '#', is_word_char), vec!["", "tag"]);
+    }
+
+    #[test]
+    fn joins_id_lists() {
+        assert_eq!(to_id_list(vec![]), "");
+        assert_eq!(to_id_list(vec![1, 22, 3]), "1, 22, 3");
+    }
 }
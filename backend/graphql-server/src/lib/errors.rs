@@ -0,0 +1,158 @@
+use juniper::{FieldError, IntoFieldError, ScalarValue, ID};
+use sea_orm::DbErr;
+
+/// Errors returned by resolvers, surfaced to clients as
+/// `{ message, extensions: { code } }` so they can branch on the code
+/// instead of the message.
+#[derive(Debug)]
+pub enum AppError {
+    InvalidId(String),
+    BadRequest(String),
+    NotFound(&'static str),
+    Forbidden(&'static str),
+    Unauthenticated,
+    /// Seconds until the caller may try again.
+    RateLimited(i32),
+    Database(DbErr),
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        AppError::Database(e)
+    }
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::InvalidId(_) | AppError::BadRequest(_) => "BAD_USER_INPUT",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::Unauthenticated => "UNAUTHENTICATED",
+            AppError::RateLimited(_) => "RATE_LIMITED",
+            AppError::Database(_) | AppError::Internal(_) => "INTERNAL_SERVER_ERROR",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::InvalidId(id) => format!("Invalid id: {}", id),
+            AppError::BadRequest(message) => message.clone(),
+            AppError::NotFound(what) => format!("{} not found", what),
+            AppError::Forbidden(message) => message.to_string(),
+            AppError::Unauthenticated => "Authentication Failed".to_string(),
+            AppError::RateLimited(retry_after) => {
+                format!("Too many requests, try again in {} seconds", retry_after)
+            }
+            // Database and internal details stay in the server log.
+            AppError::Database(_) | AppError::Internal(_) => "Internal server error".to_string(),
+        }
+    }
+
+    /// Postgres aborted the transaction because of a concurrent one, running
+    /// it again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::Database(e) => matches!(
+                sqlstate(e),
+                Some(SERIALIZATION_FAILURE) | Some(DEADLOCK_DETECTED)
+            ),
+            _ => false,
+        }
+    }
+}
+
+const SERIALIZATION_FAILURE: &str = "40001";
+const DEADLOCK_DETECTED: &str = "40P01";
+
+/// SQLSTATE of the Postgres errors transactions are retried on. sea-orm 0.9
+/// only keeps the text of database errors, so the code is told apart by the
+/// fixed message the server sends with it.
+fn sqlstate(e: &DbErr) -> Option<&'static str> {
+    let message = match e {
+        DbErr::Exec(message) | DbErr::Query(message) => message,
+        _ => return None,
+    };
+    let message = message
+        .strip_prefix("error returned from database: ")
+        .unwrap_or(message);
+
+    if message.starts_with("could not serialize access") {
+        Some(SERIALIZATION_FAILURE)
+    } else if message.starts_with("deadlock detected") {
+        Some(DEADLOCK_DETECTED)
+    } else {
+        None
+    }
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for AppError {
+    fn into_field_error(self) -> FieldError<S> {
+        match &self {
+            AppError::Database(e) => log::error!("database error: {}", e),
+            AppError::Internal(e) => log::error!("internal error: {}", e),
+            _ => {}
+        }
+
+        let code = self.code();
+        match self {
+            AppError::RateLimited(retry_after) => FieldError::new(
+                self.message(),
+                graphql_value!({ "code": code, "retryAfter": retry_after }),
+            ),
+            _ => FieldError::new(self.message(), graphql_value!({ "code": code })),
+        }
+    }
+}
+
+pub fn parse_id(id: &ID) -> AppResult<i64> {
+    id.parse::<i64>()
+        .map_err(|_| AppError::InvalidId(id.to_string()))
+}
+
+pub fn check_page(page_size: i32, page_number: i32) -> AppResult<()> {
+    if page_size < 1 || page_number < 1 {
+        return Err(AppError::BadRequest(
+            "page_size and page_number must be positive".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database_error(message: &str) -> AppError {
+        AppError::Database(DbErr::Query(format!(
+            "error returned from database: {}",
+            message
+        )))
+    }
+
+    #[test]
+    fn retries_serialization_failures_and_deadlocks() {
+        assert!(database_error(
+            "could not serialize access due to read/write dependencies among transactions"
+        )
+        .is_retryable());
+        assert!(
+            database_error("could not serialize access due to concurrent update").is_retryable()
+        );
+        assert!(database_error("deadlock detected").is_retryable());
+    }
+
+    #[test]
+    fn does_not_retry_other_errors() {
+        assert!(
+            !database_error("duplicate key value violates unique constraint \"follows_pkey\"")
+                .is_retryable()
+        );
+        assert!(!database_error("relation \"x\" mentions deadlock detected").is_retryable());
+        assert!(!AppError::Database(DbErr::Conn("deadlock detected".to_string())).is_retryable());
+        assert!(!AppError::BadRequest("deadlock detected".to_string()).is_retryable());
+    }
+}
@@ -0,0 +1,409 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, ConnectionTrait, DbBackend, DbErr,
+    FromQueryResult, Statement,
+};
+use serde_json::{json, Value};
+
+use crate::lib::errors::{AppError, AppResult};
+
+/// Directory every file of an export archive sits in.
+const ARCHIVE_ROOT: &str = "buzz-export";
+
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataExportStatus {
+    Pending,
+    Ready,
+    Failed,
+    Expired,
+}
+
+impl DataExportStatus {
+    /// Stored in `data_exports.status`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            DataExportStatus::Pending => "pending",
+            DataExportStatus::Ready => "ready",
+            DataExportStatus::Failed => "failed",
+            DataExportStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "pending" => Some(DataExportStatus::Pending),
+            "ready" => Some(DataExportStatus::Ready),
+            "failed" => Some(DataExportStatus::Failed),
+            "expired" => Some(DataExportStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// The secret part of a download link, whoever has it can fetch the archive.
+pub fn new_download_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect()
+}
+
+/// The export behind `token` with its archive, as long as the link has not
+/// expired.
+pub async fn downloadable_export<C: ConnectionTrait>(
+    connection: &C,
+    token: &str,
+) -> Result<Option<entity::data_exports::Model>, DbErr> {
+    let now: DateTimeWithTimeZone = chrono::DateTime::from(chrono::Utc::now());
+
+    entity::data_exports::Entity::find()
+        .filter(entity::data_exports::Column::Token.eq(token))
+        .filter(entity::data_exports::Column::Status.eq(DataExportStatus::Ready.key()))
+        .filter(entity::data_exports::Column::ExpiresAt.gt(now))
+        .filter(entity::data_exports::Column::Archive.is_not_null())
+        .one(connection)
+        .await
+}
+
+#[derive(FromQueryResult)]
+struct UpvoteRow {
+    ratings_id: i64,
+    buzz_id: Option<i64>,
+    reply_id: Option<i64>,
+    created_at: DateTimeWithTimeZone,
+}
+
+#[derive(FromQueryResult)]
+struct PollVoteRow {
+    buzz_id: i64,
+    option: String,
+    created_at: DateTimeWithTimeZone,
+}
+
+fn timestamp(at: &DateTimeWithTimeZone) -> String {
+    at.to_rfc3339()
+}
+
+fn optional_timestamp(at: &Option<DateTimeWithTimeZone>) -> Option<String> {
+    at.as_ref().map(timestamp)
+}
+
+/// Everything kept about `user_id` as a gzipped tar of JSON files: the
+/// account (without the password), profile, buzzes, replies, upvotes, follow
+/// lists and security events. Images and videos are listed in `media.json`
+/// by their links rather than copied.
+pub async fn build_archive<C: ConnectionTrait>(connection: &C, user_id: i64) -> AppResult<Vec<u8>> {
+    let user = entity::users::Entity::find_by_id(user_id)
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("User"))?;
+    let auth = entity::auth::Entity::find_by_id(user.auth_id)
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("User"))?;
+
+    let buzzes = entity::buzz::Entity::find()
+        .filter(entity::buzz::Column::UserId.eq(user_id))
+        .filter(entity::buzz::Column::DeletedAt.is_null())
+        .order_by(entity::buzz::Column::Id, Order::Asc)
+        .all(connection)
+        .await?;
+    let replies = entity::reply::Entity::find()
+        .filter(entity::reply::Column::UserId.eq(user_id))
+        .filter(entity::reply::Column::DeletedAt.is_null())
+        .order_by(entity::reply::Column::Id, Order::Asc)
+        .all(connection)
+        .await?;
+    let upvotes = UpvoteRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT upvotes.ratings_id, buzz.id AS buzz_id, reply.id AS reply_id, upvotes.created_at
+        FROM upvotes
+        LEFT JOIN buzz ON buzz.ratings_id = upvotes.ratings_id
+        LEFT JOIN reply ON reply.ratings_id = upvotes.ratings_id
+        WHERE upvotes.user_id = $1
+        ORDER BY upvotes.id"#,
+        vec![user_id.into()],
+    ))
+    .all(connection)
+    .await?;
+    let poll_votes = PollVoteRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT polls.buzz_id, poll_options.label AS option, poll_votes.created_at
+        FROM poll_votes
+        JOIN polls ON polls.id = poll_votes.poll_id
+        JOIN poll_options ON poll_options.id = poll_votes.option_id
+        WHERE poll_votes.user_id = $1
+        ORDER BY poll_votes.id"#,
+        vec![user_id.into()],
+    ))
+    .all(connection)
+    .await?;
+    let following = entity::follows::Entity::find()
+        .filter(entity::follows::Column::FollowerId.eq(user_id))
+        .order_by(entity::follows::Column::Id, Order::Asc)
+        .all(connection)
+        .await?;
+    let followers = entity::follows::Entity::find()
+        .filter(entity::follows::Column::FolloweeId.eq(user_id))
+        .order_by(entity::follows::Column::Id, Order::Asc)
+        .all(connection)
+        .await?;
+    let security_events = entity::security_events::Entity::find()
+        .filter(entity::security_events::Column::UserId.eq(user_id))
+        .order_by(entity::security_events::Column::Id, Order::Asc)
+        .all(connection)
+        .await?;
+
+    let mut media = Vec::new();
+    if let Some(profile_picture) = &user.profile_picture {
+        media.push(json!({ "kind": "profile_picture", "link": profile_picture }));
+    }
+    for buzz in &buzzes {
+        if let Some(image_link) = &buzz.image_link {
+            media.push(json!({ "kind": "image", "buzz_id": buzz.id, "link": image_link }));
+        }
+        if let Some(video_link) = &buzz.video_link {
+            media.push(json!({ "kind": "video", "buzz_id": buzz.id, "link": video_link }));
+        }
+    }
+
+    let files: Vec<(&str, Value)> = vec![
+        (
+            "auth.json",
+            json!({
+                "id": auth.id,
+                "username": auth.username,
+                "email": auth.email,
+                "contact_number": auth.contact_number,
+            }),
+        ),
+        (
+            "profile.json",
+            json!({
+                "id": user.id,
+                "full_name": user.full_name,
+                "profile_picture": user.profile_picture,
+                "description": user.description,
+                "location_or_region": user.location_or_region,
+                "created_at": timestamp(&user.created_at),
+                "dm_policy": user.dm_policy,
+                "is_private": user.is_private,
+                "deletion_requested_at": optional_timestamp(&user.deletion_requested_at),
+            }),
+        ),
+        (
+            "buzzes.json",
+            buzzes
+                .iter()
+                .map(|buzz| {
+                    json!({
+                        "id": buzz.id,
+                        "description": buzz.description,
+                        "image_link": buzz.image_link,
+                        "video_link": buzz.video_link,
+                        "buzz_words": buzz.buzz_words,
+                        "mentioned_users": buzz.mentioned_users,
+                        "quoted_buzz_id": buzz.quoted_buzz_id,
+                        "created_at": timestamp(&buzz.created_at),
+                        "edited_at": optional_timestamp(&buzz.edited_at),
+                    })
+                })
+                .collect(),
+        ),
+        (
+            "replies.json",
+            replies
+                .iter()
+                .map(|reply| {
+                    json!({
+                        "id": reply.id,
+                        "buzz_id": reply.buzz_id,
+                        "parent_reply_id": reply.parent_reply_id,
+                        "reply_content": reply.reply_content,
+                        "buzz_words": reply.buzz_words,
+                        "mentioned_users": reply.mentioned_users,
+                        "created_at": timestamp(&reply.created_at),
+                        "edited_at": optional_timestamp(&reply.edited_at),
+                    })
+                })
+                .collect(),
+        ),
+        (
+            "upvotes.json",
+            upvotes
+                .iter()
+                .map(|upvote| {
+                    json!({
+                        "ratings_id": upvote.ratings_id,
+                        "buzz_id": upvote.buzz_id,
+                        "reply_id": upvote.reply_id,
+                        "created_at": timestamp(&upvote.created_at),
+                    })
+                })
+                .collect(),
+        ),
+        (
+            "poll_votes.json",
+            poll_votes
+                .iter()
+                .map(|vote| {
+                    json!({
+                        "buzz_id": vote.buzz_id,
+                        "option": vote.option,
+                        "created_at": timestamp(&vote.created_at),
+                    })
+                })
+                .collect(),
+        ),
+        (
+            "follows.json",
+            json!({
+                "following": following
+                    .iter()
+                    .map(|follow| json!({
+                        "user_id": follow.followee_id,
+                        "created_at": timestamp(&follow.created_at),
+                    }))
+                    .collect::<Vec<Value>>(),
+                "followers": followers
+                    .iter()
+                    .map(|follow| json!({
+                        "user_id": follow.follower_id,
+                        "created_at": timestamp(&follow.created_at),
+                    }))
+                    .collect::<Vec<Value>>(),
+            }),
+        ),
+        (
+            "security_events.json",
+            security_events
+                .iter()
+                .map(|event| {
+                    json!({
+                        "kind": event.kind,
+                        "ip_address": event.ip_address,
+                        "created_at": timestamp(&event.created_at),
+                    })
+                })
+                .collect(),
+        ),
+        ("media.json", Value::Array(media)),
+    ];
+
+    let mut entries = Vec::new();
+    for (name, value) in files {
+        let contents = serde_json::to_vec_pretty(&value)
+            .map_err(|e| AppError::Internal(format!("serializing {}: {}", name, e)))?;
+        entries.push((format!("{}/{}", ARCHIVE_ROOT, name), contents));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&tar(&entries, chrono::Utc::now().timestamp()))
+        .and_then(|_| encoder.finish())
+        .map_err(|e| AppError::Internal(format!("compressing data export: {}", e)))
+}
+
+/// A ustar archive of regular files, `entries` being path and contents.
+/// Paths have to fit the 100 bytes of the name field.
+fn tar(entries: &[(String, Vec<u8>)], modified_at: i64) -> Vec<u8> {
+    let mut archive = Vec::new();
+
+    for (path, contents) in entries {
+        let mut header = [0u8; 512];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[108..116].copy_from_slice(b"0000000\0");
+        header[116..124].copy_from_slice(b"0000000\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+        header[136..148].copy_from_slice(format!("{:011o}\0", modified_at).as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        // The checksum is taken with its own field filled with spaces.
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(contents);
+        archive.resize(archive.len() + (512 - contents.len() % 512) % 512, 0);
+    }
+
+    // Two empty blocks end the archive.
+    archive.resize(archive.len() + 1024, 0);
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn octal(field: &[u8]) -> u64 {
+        let digits = std::str::from_utf8(field).unwrap();
+        u64::from_str_radix(digits.trim_matches(|c| c == '\0' || c == ' '), 8).unwrap()
+    }
+
+    /// Reads `archive` back the way tar does, checking every header.
+    fn untar(archive: &[u8]) -> Vec<(String, Vec<u8>, u64)> {
+        assert_eq!(archive.len() % 512, 0);
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        loop {
+            let header = &archive[offset..offset + 512];
+            if header.iter().all(|byte| *byte == 0) {
+                assert!(archive[offset..].iter().all(|byte| *byte == 0));
+                assert_eq!(archive.len() - offset, 1024);
+                return entries;
+            }
+
+            assert_eq!(&header[257..263], b"ustar\0");
+            assert_eq!(header[156], b'0');
+            let mut blanked = header.to_vec();
+            blanked[148..156].copy_from_slice(b"        ");
+            let checksum: u64 = blanked.iter().map(|byte| *byte as u64).sum();
+            assert_eq!(octal(&header[148..156]), checksum);
+
+            let name_length = header[..100].iter().position(|byte| *byte == 0).unwrap();
+            let name = String::from_utf8(header[..name_length].to_vec()).unwrap();
+            let size = octal(&header[124..136]) as usize;
+            let modified_at = octal(&header[136..148]);
+
+            offset += 512;
+            entries.push((name, archive[offset..offset + size].to_vec(), modified_at));
+            offset += size.div_ceil(512) * 512;
+        }
+    }
+
+    #[test]
+    fn tar_reads_back() {
+        let entries = vec![
+            (
+                "export/auth.json".to_string(),
+                b"{\"username\": \"bob\"}".to_vec(),
+            ),
+            ("export/empty.json".to_string(), Vec::new()),
+            ("export/block.json".to_string(), vec![b'x'; 512]),
+            ("export/long.json".to_string(), vec![b'y'; 1300]),
+        ];
+
+        let read = untar(&tar(&entries, 1_664_000_000));
+
+        assert_eq!(read.len(), entries.len());
+        for ((path, contents), (name, read_contents, modified_at)) in entries.iter().zip(read) {
+            assert_eq!(&name, path);
+            assert_eq!(&read_contents, contents);
+            assert_eq!(modified_at, 1_664_000_000);
+        }
+    }
+
+    #[test]
+    fn empty_tar_is_two_blocks() {
+        assert_eq!(tar(&[], 0), vec![0; 1024]);
+    }
+}
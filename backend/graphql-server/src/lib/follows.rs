@@ -0,0 +1,98 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbBackend, DbErr, Statement};
+
+/// Whether `follower_id` follows `followee_id`.
+pub async fn is_following<C: ConnectionTrait>(
+    connection: &C,
+    follower_id: i64,
+    followee_id: i64,
+) -> Result<bool, DbErr> {
+    let follow = entity::follows::Entity::find()
+        .filter(entity::follows::Column::FollowerId.eq(follower_id))
+        .filter(entity::follows::Column::FolloweeId.eq(followee_id))
+        .one(connection)
+        .await?;
+
+    Ok(follow.is_some())
+}
+
+/// Users `user_id` follows, oldest follow first.
+pub async fn following_ids<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+) -> Result<Vec<i64>, DbErr> {
+    Ok(entity::follows::Entity::find()
+        .filter(entity::follows::Column::FollowerId.eq(user_id))
+        .order_by(entity::follows::Column::Id, Order::Asc)
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|follow| follow.followee_id)
+        .collect())
+}
+
+/// Users following `user_id`, oldest follow first.
+pub async fn follower_ids<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+) -> Result<Vec<i64>, DbErr> {
+    Ok(entity::follows::Entity::find()
+        .filter(entity::follows::Column::FolloweeId.eq(user_id))
+        .order_by(entity::follows::Column::Id, Order::Asc)
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|follow| follow.follower_id)
+        .collect())
+}
+
+/// Makes `follower_id` follow `followee_id`. Returns false when they already
+/// did, the unique constraint settles concurrent follows.
+pub async fn follow<C: ConnectionTrait>(
+    connection: &C,
+    follower_id: i64,
+    followee_id: i64,
+) -> Result<bool, DbErr> {
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO follows (follower_id, followee_id, created_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (follower_id, followee_id) DO NOTHING"#,
+            vec![follower_id.into(), followee_id.into()],
+        ))
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Makes `follower_id` stop following `followee_id`. Returns false when they
+/// did not follow.
+pub async fn unfollow<C: ConnectionTrait>(
+    connection: &C,
+    follower_id: i64,
+    followee_id: i64,
+) -> Result<bool, DbErr> {
+    let result = entity::follows::Entity::delete_many()
+        .filter(entity::follows::Column::FollowerId.eq(follower_id))
+        .filter(entity::follows::Column::FolloweeId.eq(followee_id))
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected > 0)
+}
+
+/// Ends following between the two users, either way.
+pub async fn remove_follows_between<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    other_id: i64,
+) -> Result<(), DbErr> {
+    let pair = [user_id, other_id];
+    entity::follows::Entity::delete_many()
+        .filter(entity::follows::Column::FollowerId.is_in(pair))
+        .filter(entity::follows::Column::FolloweeId.is_in(pair))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
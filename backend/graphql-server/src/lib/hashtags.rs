@@ -0,0 +1,153 @@
+use std::collections::BTreeSet;
+
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, ConnectionTrait, DbBackend, DbErr,
+    Statement,
+};
+
+use crate::lib::common::{prefixed_words, Tagged};
+
+const MAX_TAG_LENGTH: usize = 64;
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn insert_tag(tags: &mut BTreeSet<String>, tag: &str) {
+    if !tag.is_empty() && tag.chars().count() <= MAX_TAG_LENGTH && tag.chars().all(is_tag_char) {
+        tags.insert(tag.to_lowercase());
+    }
+}
+
+/// `#tags` written in `text` plus the entries of the client supplied
+/// `buzz_words`, lower cased and without duplicates.
+pub fn parse_hashtags(text: &str, buzz_words: Option<&str>) -> Vec<String> {
+    let mut tags = BTreeSet::new();
+
+    for tag in prefixed_words(text, '#', is_tag_char) {
+        insert_tag(&mut tags, &tag);
+    }
+    for word in buzz_words.unwrap_or_default().split(',') {
+        insert_tag(&mut tags, word.trim().trim_start_matches('#'));
+    }
+
+    tags.into_iter().collect()
+}
+
+/// The value stored in the `buzz_words` column for `tags`.
+pub fn to_buzz_words(tags: &[String]) -> Option<String> {
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join(", "))
+    }
+}
+
+/// Replaces the hashtags linked to `tagged`.
+pub async fn save_hashtags<C: ConnectionTrait>(
+    connection: &C,
+    tagged: Tagged,
+    tags: &[String],
+    created_at: DateTimeWithTimeZone,
+) -> Result<(), DbErr> {
+    let (buzz_id, reply_id) = match tagged {
+        Tagged::Buzz(buzz_id) => (Some(buzz_id), None),
+        Tagged::Reply(reply_id) => (None, Some(reply_id)),
+    };
+
+    let existing = match tagged {
+        Tagged::Buzz(buzz_id) => entity::buzz_hashtags::Column::BuzzId.eq(buzz_id),
+        Tagged::Reply(reply_id) => entity::buzz_hashtags::Column::ReplyId.eq(reply_id),
+    };
+    entity::buzz_hashtags::Entity::delete_many()
+        .filter(existing)
+        .exec(connection)
+        .await?;
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let placeholders = (1..=tags.len())
+        .map(|i| format!("(${}, now())", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                "INSERT INTO hashtags (tag, created_at) VALUES {} ON CONFLICT (tag) DO NOTHING",
+                placeholders
+            ),
+            tags.iter().map(|tag| tag.as_str().into()),
+        ))
+        .await?;
+
+    let hashtags = entity::hashtags::Entity::find()
+        .filter(entity::hashtags::Column::Tag.is_in(tags.to_vec()))
+        .all(connection)
+        .await?;
+
+    entity::buzz_hashtags::Entity::insert_many(hashtags.into_iter().map(|hashtag| {
+        entity::buzz_hashtags::ActiveModel {
+            hashtag_id: Set(hashtag.id),
+            buzz_id: Set(buzz_id),
+            reply_id: Set(reply_id),
+            created_at: Set(created_at),
+            ..Default::default()
+        }
+    }))
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_tags_in_text() {
+        assert_eq!(
+            parse_hashtags("Loving #Rust and #rust, #async_await! #café", None),
+            vec!["async_await", "café", "rust"]
+        );
+    }
+
+    #[test]
+    fn ignores_hashes_inside_words() {
+        assert_eq!(
+            parse_hashtags("c# and f#, but #dotnet", None),
+            vec!["dotnet"]
+        );
+        assert!(parse_hashtags("# alone", None).is_empty());
+    }
+
+    #[test]
+    fn merges_buzz_words() {
+        assert_eq!(
+            parse_hashtags("#one", Some("#Two, three , not-a-tag,,ONE")),
+            vec!["one", "three", "two"]
+        );
+    }
+
+    #[test]
+    fn drops_tags_over_the_length_limit() {
+        let longest = "a".repeat(MAX_TAG_LENGTH);
+        let too_long = "b".repeat(MAX_TAG_LENGTH + 1);
+        assert_eq!(
+            parse_hashtags(&format!("#{} #{}", longest, too_long), None),
+            vec![longest]
+        );
+    }
+
+    #[test]
+    fn stores_tags_as_buzz_words() {
+        assert_eq!(to_buzz_words(&[]), None);
+        assert_eq!(
+            to_buzz_words(&["one".to_string(), "two".to_string()]),
+            Some("one, two".to_string())
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use dataloader::{cached::Loader, BatchFn};
+use sea_orm::{
+    entity::*, query::*, sea_query::Expr, DatabaseConnection, DbBackend, DbErr, FromQueryResult,
+    Statement,
+};
+
+use crate::lib::{
+    blocks::{not_blocked, not_blocked_sql},
+    common::Tagged,
+    privacy::{not_locked, not_locked_sql},
+};
+
+pub type LoadResult<T> = Result<Option<T>, DbErr>;
+
+/// Per-request loaders, every `load` issued while resolving one level of the
+/// query is collected into a single `WHERE .. IN (..)` lookup.
+#[derive(Clone)]
+pub struct Loaders {
+    pub users: Loader<i64, LoadResult<entity::users::Model>, UsersBatcher>,
+    pub buzzes: Loader<i64, LoadResult<entity::buzz::Model>, BuzzesBatcher>,
+    pub ratings: Loader<i64, LoadResult<entity::ratings::Model>, RatingsBatcher>,
+    pub reply_counts: Loader<ReplyCountKey, Result<i64, DbErr>, ReplyCountBatcher>,
+    pub replies: Loader<ReplyPageKey, Result<Vec<entity::reply::Model>, DbErr>, RepliesBatcher>,
+    pub mentions: Loader<Tagged, Result<Vec<i64>, DbErr>, MentionsBatcher>,
+    pub bookmarked: Loader<BookmarkKey, Result<bool, DbErr>, BookmarkedBatcher>,
+    pub revisions:
+        Loader<RevisionOwner, Result<Vec<entity::buzz_revisions::Model>, DbErr>, RevisionsBatcher>,
+    pub polls: Loader<i64, LoadResult<entity::polls::Model>, PollsBatcher>,
+    pub poll_tallies: Loader<i64, Result<PollTally, DbErr>, PollTalliesBatcher>,
+    pub poll_choices: Loader<PollVoteKey, Result<Vec<i64>, DbErr>, PollChoicesBatcher>,
+}
+
+impl Loaders {
+    pub fn new(connection: &DatabaseConnection) -> Self {
+        Loaders {
+            users: Loader::new(UsersBatcher {
+                connection: connection.clone(),
+            }),
+            buzzes: Loader::new(BuzzesBatcher {
+                connection: connection.clone(),
+            }),
+            ratings: Loader::new(RatingsBatcher {
+                connection: connection.clone(),
+            }),
+            reply_counts: Loader::new(ReplyCountBatcher {
+                connection: connection.clone(),
+            }),
+            replies: Loader::new(RepliesBatcher {
+                connection: connection.clone(),
+            }),
+            mentions: Loader::new(MentionsBatcher {
+                connection: connection.clone(),
+            }),
+            bookmarked: Loader::new(BookmarkedBatcher {
+                connection: connection.clone(),
+            }),
+            revisions: Loader::new(RevisionsBatcher {
+                connection: connection.clone(),
+            }),
+            polls: Loader::new(PollsBatcher {
+                connection: connection.clone(),
+            }),
+            poll_tallies: Loader::new(PollTalliesBatcher {
+                connection: connection.clone(),
+            }),
+            poll_choices: Loader::new(PollChoicesBatcher {
+                connection: connection.clone(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for Loaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Loaders").finish_non_exhaustive()
+    }
+}
+
+fn fill_with_error<K: Clone + Eq + std::hash::Hash, V>(
+    keys: &[K],
+    e: DbErr,
+) -> HashMap<K, Result<V, DbErr>> {
+    keys.iter()
+        .map(|key| (key.clone(), Err(e.clone())))
+        .collect()
+}
+
+pub struct UsersBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<i64, LoadResult<entity::users::Model>> for UsersBatcher {
+    async fn load(&mut self, keys: &[i64]) -> HashMap<i64, LoadResult<entity::users::Model>> {
+        let users = entity::users::Entity::find()
+            .filter(entity::users::Column::Id.is_in(keys.to_vec()))
+            .all(&self.connection)
+            .await;
+
+        match users {
+            Ok(users) => {
+                let mut found: HashMap<i64, entity::users::Model> =
+                    users.into_iter().map(|user| (user.id, user)).collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(found.remove(key))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+pub struct BuzzesBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<i64, LoadResult<entity::buzz::Model>> for BuzzesBatcher {
+    async fn load(&mut self, keys: &[i64]) -> HashMap<i64, LoadResult<entity::buzz::Model>> {
+        let buzzes = entity::buzz::Entity::find()
+            .filter(entity::buzz::Column::Id.is_in(keys.to_vec()))
+            .all(&self.connection)
+            .await;
+
+        match buzzes {
+            Ok(buzzes) => {
+                let mut found: HashMap<i64, entity::buzz::Model> =
+                    buzzes.into_iter().map(|buzz| (buzz.id, buzz)).collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(found.remove(key))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+pub struct RatingsBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<i64, LoadResult<entity::ratings::Model>> for RatingsBatcher {
+    async fn load(&mut self, keys: &[i64]) -> HashMap<i64, LoadResult<entity::ratings::Model>> {
+        let ratings = entity::ratings::Entity::find()
+            .filter(entity::ratings::Column::Id.is_in(keys.to_vec()))
+            .all(&self.connection)
+            .await;
+
+        match ratings {
+            Ok(ratings) => {
+                let mut found: HashMap<i64, entity::ratings::Model> = ratings
+                    .into_iter()
+                    .map(|rating| (rating.id, rating))
+                    .collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(found.remove(key))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+#[derive(FromQueryResult)]
+struct ReplyCount {
+    buzz_id: i64,
+    count: i64,
+}
+
+/// Replies to a buzz the viewer may see, the ones of users blocked either
+/// way left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReplyCountKey {
+    pub buzz_id: i64,
+    pub viewer_id: Option<i64>,
+}
+
+pub struct ReplyCountBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<ReplyCountKey, Result<i64, DbErr>> for ReplyCountBatcher {
+    async fn load(&mut self, keys: &[ReplyCountKey]) -> HashMap<ReplyCountKey, Result<i64, DbErr>> {
+        // All keys of a request share the same viewer.
+        let viewer_id = keys.first().and_then(|key| key.viewer_id);
+        let counts = entity::reply::Entity::find()
+            .select_only()
+            .column(entity::reply::Column::BuzzId)
+            .column_as(entity::reply::Column::Id.count(), "count")
+            .filter(
+                entity::reply::Column::BuzzId
+                    .is_in(keys.iter().map(|key| key.buzz_id).collect::<Vec<i64>>()),
+            )
+            .filter(not_locked("reply.user_id", viewer_id))
+            .filter(not_blocked("reply.user_id", viewer_id))
+            .group_by(entity::reply::Column::BuzzId)
+            .into_model::<ReplyCount>()
+            .all(&self.connection)
+            .await;
+
+        match counts {
+            Ok(counts) => {
+                let found: HashMap<i64, i64> = counts
+                    .into_iter()
+                    .map(|count| (count.buzz_id, count.count))
+                    .collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(*found.get(&key.buzz_id).unwrap_or(&0))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+/// One page of the replies of a buzz the viewer may see, newest first. Like
+/// `ReplyCountKey` it leaves out users blocked either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReplyPageKey {
+    pub buzz_id: i64,
+    pub page_size: i64,
+    pub page_number: i64,
+    pub viewer_id: Option<i64>,
+}
+
+pub struct RepliesBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<ReplyPageKey, Result<Vec<entity::reply::Model>, DbErr>> for RepliesBatcher {
+    async fn load(
+        &mut self,
+        keys: &[ReplyPageKey],
+    ) -> HashMap<ReplyPageKey, Result<Vec<entity::reply::Model>, DbErr>> {
+        // Buzzes in one list are normally asked for the same page, so this is
+        // one query per distinct (page_size, page_number) pair. All keys of a
+        // request share the same viewer.
+        let viewer_id = keys.first().and_then(|key| key.viewer_id);
+        let mut pages: HashMap<(i64, i64), Vec<i64>> = HashMap::new();
+        for key in keys {
+            pages
+                .entry((key.page_size, key.page_number))
+                .or_default()
+                .push(key.buzz_id);
+        }
+
+        let mut result = HashMap::new();
+        for ((page_size, page_number), buzz_ids) in pages {
+            let placeholders = (1..=buzz_ids.len())
+                .map(|i| format!("${}", i))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let offset = (page_number - 1) * page_size;
+
+            let sql = format!(
+                r#"SELECT * FROM (
+                    SELECT reply.*, ROW_NUMBER() OVER (
+                        PARTITION BY reply.buzz_id ORDER BY reply.created_at DESC, reply.id DESC
+                    ) AS row_number
+                    FROM reply WHERE reply.buzz_id IN ({}) AND {} AND {}
+                ) AS ranked
+                WHERE ranked.row_number > {} AND ranked.row_number <= {}
+                ORDER BY ranked.buzz_id, ranked.row_number"#,
+                placeholders,
+                not_locked_sql("reply.user_id", &format!("${}", buzz_ids.len() + 1)),
+                not_blocked_sql("reply.user_id", &format!("${}", buzz_ids.len() + 1)),
+                offset,
+                offset + page_size
+            );
+
+            let replies = entity::reply::Entity::find()
+                .from_raw_sql(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    &sql,
+                    buzz_ids
+                        .iter()
+                        .map(|id| (*id).into())
+                        .chain([viewer_id.into()]),
+                ))
+                .all(&self.connection)
+                .await;
+
+            match replies {
+                Ok(replies) => {
+                    let mut grouped: HashMap<i64, Vec<entity::reply::Model>> = HashMap::new();
+                    for reply in replies {
+                        grouped.entry(reply.buzz_id).or_default().push(reply);
+                    }
+                    for buzz_id in buzz_ids {
+                        result.insert(
+                            ReplyPageKey {
+                                buzz_id,
+                                page_size,
+                                page_number,
+                                viewer_id,
+                            },
+                            Ok(grouped.remove(&buzz_id).unwrap_or_default()),
+                        );
+                    }
+                }
+                Err(e) => {
+                    for buzz_id in buzz_ids {
+                        result.insert(
+                            ReplyPageKey {
+                                buzz_id,
+                                page_size,
+                                page_number,
+                                viewer_id,
+                            },
+                            Err(e.clone()),
+                        );
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// The buzz or reply a revision belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RevisionOwner {
+    Buzz(i64),
+    Reply(i64),
+}
+
+pub struct RevisionsBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<RevisionOwner, Result<Vec<entity::buzz_revisions::Model>, DbErr>>
+    for RevisionsBatcher
+{
+    async fn load(
+        &mut self,
+        keys: &[RevisionOwner],
+    ) -> HashMap<RevisionOwner, Result<Vec<entity::buzz_revisions::Model>, DbErr>> {
+        let mut buzz_ids = Vec::new();
+        let mut reply_ids = Vec::new();
+        for key in keys {
+            match key {
+                RevisionOwner::Buzz(id) => buzz_ids.push(*id),
+                RevisionOwner::Reply(id) => reply_ids.push(*id),
+            }
+        }
+
+        // Newest revision first.
+        let revisions = entity::buzz_revisions::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(entity::buzz_revisions::Column::BuzzId.is_in(buzz_ids))
+                    .add(entity::buzz_revisions::Column::ReplyId.is_in(reply_ids)),
+            )
+            .order_by_desc(entity::buzz_revisions::Column::RevisedAt)
+            .order_by_desc(entity::buzz_revisions::Column::Id)
+            .all(&self.connection)
+            .await;
+
+        match revisions {
+            Ok(revisions) => {
+                let mut grouped: HashMap<RevisionOwner, Vec<entity::buzz_revisions::Model>> =
+                    HashMap::new();
+                for revision in revisions {
+                    let owner = match (revision.buzz_id, revision.reply_id) {
+                        (Some(buzz_id), _) => RevisionOwner::Buzz(buzz_id),
+                        (None, Some(reply_id)) => RevisionOwner::Reply(reply_id),
+                        (None, None) => continue,
+                    };
+                    grouped.entry(owner).or_default().push(revision);
+                }
+                keys.iter()
+                    .map(|key| (*key, Ok(grouped.remove(key).unwrap_or_default())))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+/// Whether `user_id` bookmarked `buzz_id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BookmarkKey {
+    pub user_id: i64,
+    pub buzz_id: i64,
+}
+
+pub struct BookmarkedBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<BookmarkKey, Result<bool, DbErr>> for BookmarkedBatcher {
+    async fn load(&mut self, keys: &[BookmarkKey]) -> HashMap<BookmarkKey, Result<bool, DbErr>> {
+        // All keys of a request share the same user.
+        let bookmarks = entity::bookmarks::Entity::find()
+            .filter(
+                entity::bookmarks::Column::UserId
+                    .is_in(keys.iter().map(|key| key.user_id).collect::<Vec<i64>>()),
+            )
+            .filter(
+                entity::bookmarks::Column::BuzzId
+                    .is_in(keys.iter().map(|key| key.buzz_id).collect::<Vec<i64>>()),
+            )
+            .all(&self.connection)
+            .await;
+
+        match bookmarks {
+            Ok(bookmarks) => {
+                let found: std::collections::HashSet<BookmarkKey> = bookmarks
+                    .into_iter()
+                    .map(|bookmark| BookmarkKey {
+                        user_id: bookmark.user_id,
+                        buzz_id: bookmark.buzz_id,
+                    })
+                    .collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(found.contains(key))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+pub struct MentionsBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<Tagged, Result<Vec<i64>, DbErr>> for MentionsBatcher {
+    async fn load(&mut self, keys: &[Tagged]) -> HashMap<Tagged, Result<Vec<i64>, DbErr>> {
+        let mut buzz_ids = Vec::new();
+        let mut reply_ids = Vec::new();
+        for key in keys {
+            match key {
+                Tagged::Buzz(id) => buzz_ids.push(*id),
+                Tagged::Reply(id) => reply_ids.push(*id),
+            }
+        }
+
+        let mentions = entity::mentions::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(entity::mentions::Column::BuzzId.is_in(buzz_ids))
+                    .add(entity::mentions::Column::ReplyId.is_in(reply_ids)),
+            )
+            .order_by_asc(entity::mentions::Column::Id)
+            .all(&self.connection)
+            .await;
+
+        match mentions {
+            Ok(mentions) => {
+                let mut grouped: HashMap<Tagged, Vec<i64>> = HashMap::new();
+                for mention in mentions {
+                    let tagged = match (mention.buzz_id, mention.reply_id) {
+                        (Some(buzz_id), _) => Tagged::Buzz(buzz_id),
+                        (None, Some(reply_id)) => Tagged::Reply(reply_id),
+                        (None, None) => continue,
+                    };
+                    grouped.entry(tagged).or_default().push(mention.user_id);
+                }
+                keys.iter()
+                    .map(|key| (*key, Ok(grouped.remove(key).unwrap_or_default())))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+/// Polls keyed by the buzz they belong to.
+pub struct PollsBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<i64, LoadResult<entity::polls::Model>> for PollsBatcher {
+    async fn load(&mut self, keys: &[i64]) -> HashMap<i64, LoadResult<entity::polls::Model>> {
+        let polls = entity::polls::Entity::find()
+            .filter(entity::polls::Column::BuzzId.is_in(keys.to_vec()))
+            .all(&self.connection)
+            .await;
+
+        match polls {
+            Ok(polls) => {
+                let mut found: HashMap<i64, entity::polls::Model> =
+                    polls.into_iter().map(|poll| (poll.buzz_id, poll)).collect();
+                keys.iter()
+                    .map(|key| (*key, Ok(found.remove(key))))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+/// The options of a poll in order with their votes, and how many users voted.
+#[derive(Clone, Debug, Default)]
+pub struct PollTally {
+    pub options: Vec<(entity::poll_options::Model, i64)>,
+    pub voters: i64,
+}
+
+#[derive(FromQueryResult)]
+struct OptionVotes {
+    option_id: i64,
+    votes: i64,
+}
+
+#[derive(FromQueryResult)]
+struct PollVoters {
+    poll_id: i64,
+    voters: i64,
+}
+
+pub struct PollTalliesBatcher {
+    connection: DatabaseConnection,
+}
+
+impl PollTalliesBatcher {
+    async fn tallies(&self, poll_ids: &[i64]) -> Result<HashMap<i64, PollTally>, DbErr> {
+        let options = entity::poll_options::Entity::find()
+            .filter(entity::poll_options::Column::PollId.is_in(poll_ids.to_vec()))
+            .order_by_asc(entity::poll_options::Column::Position)
+            .all(&self.connection)
+            .await?;
+
+        let votes: HashMap<i64, i64> = entity::poll_votes::Entity::find()
+            .select_only()
+            .column(entity::poll_votes::Column::OptionId)
+            .column_as(entity::poll_votes::Column::Id.count(), "votes")
+            .filter(entity::poll_votes::Column::PollId.is_in(poll_ids.to_vec()))
+            .group_by(entity::poll_votes::Column::OptionId)
+            .into_model::<OptionVotes>()
+            .all(&self.connection)
+            .await?
+            .into_iter()
+            .map(|row| (row.option_id, row.votes))
+            .collect();
+
+        // A voter in a multiple choice poll counts once however many options
+        // they picked.
+        let voters = entity::poll_votes::Entity::find()
+            .select_only()
+            .column(entity::poll_votes::Column::PollId)
+            .column_as(Expr::cust("COUNT(DISTINCT user_id)"), "voters")
+            .filter(entity::poll_votes::Column::PollId.is_in(poll_ids.to_vec()))
+            .group_by(entity::poll_votes::Column::PollId)
+            .into_model::<PollVoters>()
+            .all(&self.connection)
+            .await?;
+
+        let mut tallies: HashMap<i64, PollTally> = HashMap::new();
+        for option in options {
+            let option_votes = *votes.get(&option.id).unwrap_or(&0);
+            tallies
+                .entry(option.poll_id)
+                .or_default()
+                .options
+                .push((option, option_votes));
+        }
+        for row in voters {
+            tallies.entry(row.poll_id).or_default().voters = row.voters;
+        }
+        Ok(tallies)
+    }
+}
+
+#[async_trait]
+impl BatchFn<i64, Result<PollTally, DbErr>> for PollTalliesBatcher {
+    async fn load(&mut self, keys: &[i64]) -> HashMap<i64, Result<PollTally, DbErr>> {
+        match self.tallies(keys).await {
+            Ok(mut tallies) => keys
+                .iter()
+                .map(|key| (*key, Ok(tallies.remove(key).unwrap_or_default())))
+                .collect(),
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
+
+/// The options `user_id` voted for in `poll_id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PollVoteKey {
+    pub user_id: i64,
+    pub poll_id: i64,
+}
+
+pub struct PollChoicesBatcher {
+    connection: DatabaseConnection,
+}
+
+#[async_trait]
+impl BatchFn<PollVoteKey, Result<Vec<i64>, DbErr>> for PollChoicesBatcher {
+    async fn load(
+        &mut self,
+        keys: &[PollVoteKey],
+    ) -> HashMap<PollVoteKey, Result<Vec<i64>, DbErr>> {
+        // All keys of a request share the same user.
+        let votes = entity::poll_votes::Entity::find()
+            .filter(
+                entity::poll_votes::Column::UserId
+                    .is_in(keys.iter().map(|key| key.user_id).collect::<Vec<i64>>()),
+            )
+            .filter(
+                entity::poll_votes::Column::PollId
+                    .is_in(keys.iter().map(|key| key.poll_id).collect::<Vec<i64>>()),
+            )
+            .all(&self.connection)
+            .await;
+
+        match votes {
+            Ok(votes) => {
+                let mut grouped: HashMap<PollVoteKey, Vec<i64>> = HashMap::new();
+                for vote in votes {
+                    grouped
+                        .entry(PollVoteKey {
+                            user_id: vote.user_id,
+                            poll_id: vote.poll_id,
+                        })
+                        .or_default()
+                        .push(vote.option_id);
+                }
+                keys.iter()
+                    .map(|key| (*key, Ok(grouped.remove(key).unwrap_or_default())))
+                    .collect()
+            }
+            Err(e) => fill_with_error(keys, e),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::{BTreeSet, HashSet};
+
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, ConnectionTrait, DbBackend, DbErr,
+    FromQueryResult, Statement,
+};
+
+use crate::lib::common::{env_or, prefixed_words, Tagged};
+
+#[derive(FromQueryResult)]
+pub struct Mention {
+    pub user_id: i64,
+    pub username: String,
+}
+
+fn is_username_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// `@usernames` written in `text`, in the order they first appear and at
+/// most `MAX_MENTIONS_PER_POST` of them. Trailing punctuation, as in
+/// `thanks @bob.`, is not part of the name.
+pub fn parse_mentions(text: &str) -> Vec<String> {
+    let max_mentions: usize = env_or("MAX_MENTIONS_PER_POST", 10);
+
+    let mut seen = HashSet::new();
+    prefixed_words(text, '@', is_username_char)
+        .into_iter()
+        .map(|username| username.trim_end_matches(['.', '-']).to_lowercase())
+        .filter(|username| !username.is_empty() && seen.insert(username.clone()))
+        .take(max_mentions)
+        .collect()
+}
+
+/// Looks up the users behind `usernames`, names nobody has are dropped and
+/// so are users blocked by or blocking `author_id`.
+pub async fn resolve_mentions<C: ConnectionTrait>(
+    connection: &C,
+    author_id: i64,
+    usernames: &[String],
+) -> Result<Vec<Mention>, DbErr> {
+    if usernames.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = (2..usernames.len() + 2)
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    Mention::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"SELECT users.id AS user_id, auth.username
+            FROM auth JOIN users ON users.auth_id = auth.id
+            WHERE lower(auth.username) IN ({})
+            AND NOT EXISTS (
+                SELECT 1 FROM blocks
+                WHERE (blocks.blocker_id = users.id AND blocks.blocked_id = $1)
+                    OR (blocks.blocker_id = $1 AND blocks.blocked_id = users.id)
+            )
+            ORDER BY auth.username"#,
+            placeholders
+        ),
+        std::iter::once(author_id.into())
+            .chain(usernames.iter().map(|username| username.as_str().into())),
+    ))
+    .all(connection)
+    .await
+}
+
+/// The value stored in the `mentioned_users` column for `mentions`.
+pub fn to_mentioned_users(mentions: &[Mention]) -> Option<String> {
+    if mentions.is_empty() {
+        None
+    } else {
+        Some(
+            mentions
+                .iter()
+                .map(|mention| mention.username.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+        )
+    }
+}
+
+/// Replaces the mentions written in `tagged` and returns the users that were
+/// not mentioned there before.
+pub async fn save_mentions<C: ConnectionTrait>(
+    connection: &C,
+    tagged: Tagged,
+    mentions: &[Mention],
+    created_at: DateTimeWithTimeZone,
+) -> Result<Vec<i64>, DbErr> {
+    let (buzz_id, reply_id, existing) = match tagged {
+        Tagged::Buzz(buzz_id) => (
+            Some(buzz_id),
+            None,
+            entity::mentions::Column::BuzzId.eq(buzz_id),
+        ),
+        Tagged::Reply(reply_id) => (
+            None,
+            Some(reply_id),
+            entity::mentions::Column::ReplyId.eq(reply_id),
+        ),
+    };
+
+    let previous = entity::mentions::Entity::find()
+        .filter(existing.clone())
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|mention| mention.user_id)
+        .collect::<BTreeSet<i64>>();
+
+    entity::mentions::Entity::delete_many()
+        .filter(existing)
+        .exec(connection)
+        .await?;
+
+    if mentions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    entity::mentions::Entity::insert_many(mentions.iter().map(|mention| {
+        entity::mentions::ActiveModel {
+            user_id: Set(mention.user_id),
+            buzz_id: Set(buzz_id),
+            reply_id: Set(reply_id),
+            created_at: Set(created_at),
+            ..Default::default()
+        }
+    }))
+    .exec(connection)
+    .await?;
+
+    Ok(mentions
+        .iter()
+        .map(|mention| mention.user_id)
+        .filter(|user_id| !previous.contains(user_id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_usernames_in_text() {
+        assert_eq!(
+            parse_mentions("thanks @Bob. and @alice_b, cc @bob"),
+            vec!["bob", "alice_b"]
+        );
+    }
+
+    #[test]
+    fn keeps_dots_and_dashes_inside_names() {
+        assert_eq!(
+            parse_mentions("ping @jane.doe-smith- now"),
+            vec!["jane.doe-smith"]
+        );
+    }
+
+    #[test]
+    fn ignores_email_addresses() {
+        assert!(parse_mentions("write to me@example.com").is_empty());
+    }
+
+    #[test]
+    fn keeps_the_first_mentions_up_to_the_limit() {
+        let text = (1..=12)
+            .map(|i| format!("@user{}", i))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let mentions = parse_mentions(&text);
+        assert_eq!(mentions.len(), 10);
+        assert_eq!(mentions.first().map(String::as_str), Some("user1"));
+        assert_eq!(mentions.last().map(String::as_str), Some("user10"));
+    }
+}
@@ -1,2 +1,23 @@
+pub mod accounts;
+pub mod blocks;
+pub mod client_ip;
 pub mod common;
+pub mod errors;
+pub mod exports;
+pub mod follows;
+pub mod hashtags;
+pub mod loaders;
+pub mod mentions;
+pub mod moderation;
+pub mod notifications;
+pub mod persisted_queries;
+pub mod polls;
+pub mod privacy;
+pub mod query_limits;
+pub mod rate_limits;
+pub mod rebuzzes;
+pub mod security_events;
 pub mod server_auth;
+pub mod tombstones;
+pub mod upvotes;
+pub mod views;
@@ -0,0 +1,291 @@
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, sea_query::Expr, ConnectionTrait, DbErr,
+};
+
+use crate::lib::errors::{AppError, AppResult};
+
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    HateSpeech,
+    Violence,
+    SexualContent,
+    SelfHarm,
+    Misinformation,
+    Impersonation,
+    Other,
+}
+
+impl ReportReason {
+    /// Stored in `reports.reason`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Harassment => "harassment",
+            ReportReason::HateSpeech => "hate_speech",
+            ReportReason::Violence => "violence",
+            ReportReason::SexualContent => "sexual_content",
+            ReportReason::SelfHarm => "self_harm",
+            ReportReason::Misinformation => "misinformation",
+            ReportReason::Impersonation => "impersonation",
+            ReportReason::Other => "other",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "spam" => Some(ReportReason::Spam),
+            "harassment" => Some(ReportReason::Harassment),
+            "hate_speech" => Some(ReportReason::HateSpeech),
+            "violence" => Some(ReportReason::Violence),
+            "sexual_content" => Some(ReportReason::SexualContent),
+            "self_harm" => Some(ReportReason::SelfHarm),
+            "misinformation" => Some(ReportReason::Misinformation),
+            "impersonation" => Some(ReportReason::Impersonation),
+            "other" => Some(ReportReason::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+impl ReportStatus {
+    /// Stored in `reports.status`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "open" => Some(ReportStatus::Open),
+            "resolved" => Some(ReportStatus::Resolved),
+            "dismissed" => Some(ReportStatus::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+/// What a moderator does to a buzz or reply. Hidden content stays in place
+/// for its replies and can be shown again, removed content is gone for good.
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentAction {
+    Hide,
+    Unhide,
+    Remove,
+}
+
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModerationActionKind {
+    HideBuzz,
+    UnhideBuzz,
+    RemoveBuzz,
+    HideReply,
+    UnhideReply,
+    RemoveReply,
+    SuspendUser,
+    UnsuspendUser,
+    DismissReport,
+}
+
+impl ModerationActionKind {
+    /// Stored in `moderation_actions.action`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ModerationActionKind::HideBuzz => "hide_buzz",
+            ModerationActionKind::UnhideBuzz => "unhide_buzz",
+            ModerationActionKind::RemoveBuzz => "remove_buzz",
+            ModerationActionKind::HideReply => "hide_reply",
+            ModerationActionKind::UnhideReply => "unhide_reply",
+            ModerationActionKind::RemoveReply => "remove_reply",
+            ModerationActionKind::SuspendUser => "suspend_user",
+            ModerationActionKind::UnsuspendUser => "unsuspend_user",
+            ModerationActionKind::DismissReport => "dismiss_report",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "hide_buzz" => Some(ModerationActionKind::HideBuzz),
+            "unhide_buzz" => Some(ModerationActionKind::UnhideBuzz),
+            "remove_buzz" => Some(ModerationActionKind::RemoveBuzz),
+            "hide_reply" => Some(ModerationActionKind::HideReply),
+            "unhide_reply" => Some(ModerationActionKind::UnhideReply),
+            "remove_reply" => Some(ModerationActionKind::RemoveReply),
+            "suspend_user" => Some(ModerationActionKind::SuspendUser),
+            "unsuspend_user" => Some(ModerationActionKind::UnsuspendUser),
+            "dismiss_report" => Some(ModerationActionKind::DismissReport),
+            _ => None,
+        }
+    }
+}
+
+/// What a report or moderation action is about. `user_id` is the author for
+/// buzzes and replies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModerationTarget {
+    Buzz { buzz_id: i64, user_id: i64 },
+    Reply { reply_id: i64, user_id: i64 },
+    User { user_id: i64 },
+}
+
+impl ModerationTarget {
+    fn user_id(&self) -> i64 {
+        match self {
+            ModerationTarget::Buzz { user_id, .. }
+            | ModerationTarget::Reply { user_id, .. }
+            | ModerationTarget::User { user_id } => *user_id,
+        }
+    }
+
+    fn buzz_id(&self) -> Option<i64> {
+        match self {
+            ModerationTarget::Buzz { buzz_id, .. } => Some(*buzz_id),
+            _ => None,
+        }
+    }
+
+    fn reply_id(&self) -> Option<i64> {
+        match self {
+            ModerationTarget::Reply { reply_id, .. } => Some(*reply_id),
+            _ => None,
+        }
+    }
+
+    /// Reports about exactly this target, a report on a buzz is not one
+    /// about its author.
+    fn reports(&self) -> Condition {
+        let reports = Condition::all()
+            .add(entity::reports::Column::ReportedUserId.eq(self.user_id()))
+            .add(match self.buzz_id() {
+                Some(buzz_id) => entity::reports::Column::BuzzId.eq(buzz_id),
+                None => entity::reports::Column::BuzzId.is_null(),
+            });
+        match self.reply_id() {
+            Some(reply_id) => reports.add(entity::reports::Column::ReplyId.eq(reply_id)),
+            None => reports.add(entity::reports::Column::ReplyId.is_null()),
+        }
+    }
+}
+
+/// Only moderators get past this.
+pub async fn require_moderator<C: ConnectionTrait>(connection: &C, user_id: i64) -> AppResult<()> {
+    let user = entity::users::Entity::find_by_id(user_id)
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("User"))?;
+
+    if !user.is_moderator {
+        return Err(AppError::Forbidden("Only moderators can do this"));
+    }
+    Ok(())
+}
+
+/// Moderators have to say why they act, the reason goes into the log.
+pub fn check_reason(reason: &str) -> AppResult<String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("A reason is required".to_string()));
+    }
+    Ok(reason.to_string())
+}
+
+/// Files a report by `reporter_id`. Reporting the same thing again while the
+/// first report is open returns that report.
+pub async fn file_report<C: ConnectionTrait>(
+    connection: &C,
+    reporter_id: i64,
+    target: ModerationTarget,
+    reason: ReportReason,
+    details: Option<String>,
+    content_snapshot: Option<String>,
+) -> Result<entity::reports::Model, DbErr> {
+    if let Some(report) = entity::reports::Entity::find()
+        .filter(entity::reports::Column::ReporterId.eq(reporter_id))
+        .filter(entity::reports::Column::Status.eq(ReportStatus::Open.key()))
+        .filter(target.reports())
+        .one(connection)
+        .await?
+    {
+        return Ok(report);
+    }
+
+    entity::reports::ActiveModel {
+        reporter_id: Set(reporter_id),
+        reported_user_id: Set(target.user_id()),
+        buzz_id: Set(target.buzz_id()),
+        reply_id: Set(target.reply_id()),
+        reason: Set(reason.key().to_string()),
+        details: Set(details
+            .map(|details| details.trim().to_string())
+            .filter(|details| !details.is_empty())),
+        content_snapshot: Set(content_snapshot),
+        status: Set(ReportStatus::Open.key().to_string()),
+        created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+}
+
+/// Closes the open reports about `target` as handled by `moderator_id`.
+pub async fn resolve_reports<C: ConnectionTrait>(
+    connection: &C,
+    moderator_id: i64,
+    target: ModerationTarget,
+) -> Result<(), DbErr> {
+    let now: DateTimeWithTimeZone = chrono::DateTime::from(chrono::Utc::now());
+
+    entity::reports::Entity::update_many()
+        .col_expr(
+            entity::reports::Column::Status,
+            Expr::value(ReportStatus::Resolved.key()),
+        )
+        .col_expr(
+            entity::reports::Column::ResolvedBy,
+            Expr::value(moderator_id),
+        )
+        .col_expr(entity::reports::Column::ResolvedAt, Expr::value(now))
+        .filter(entity::reports::Column::Status.eq(ReportStatus::Open.key()))
+        .filter(target.reports())
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Adds a line to the moderation log.
+pub async fn record_moderation_action<C: ConnectionTrait>(
+    connection: &C,
+    moderator_id: i64,
+    action: ModerationActionKind,
+    target: ModerationTarget,
+    report_id: Option<i64>,
+    reason: String,
+) -> Result<(), DbErr> {
+    entity::moderation_actions::ActiveModel {
+        moderator_id: Set(Some(moderator_id)),
+        action: Set(action.key().to_string()),
+        user_id: Set(Some(target.user_id())),
+        buzz_id: Set(target.buzz_id()),
+        reply_id: Set(target.reply_id()),
+        report_id: Set(report_id),
+        reason: Set(reason),
+        created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+
+use crate::lib::common::Tagged;
+
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    Follow,
+    FollowRequest,
+    Upvote,
+    Reply,
+    Mention,
+}
+
+impl NotificationKind {
+    /// Stored in `notifications.kind`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotificationKind::Follow => "follow",
+            NotificationKind::FollowRequest => "follow_request",
+            NotificationKind::Upvote => "upvote",
+            NotificationKind::Reply => "reply",
+            NotificationKind::Mention => "mention",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "follow" => Some(NotificationKind::Follow),
+            "follow_request" => Some(NotificationKind::FollowRequest),
+            "upvote" => Some(NotificationKind::Upvote),
+            "reply" => Some(NotificationKind::Reply),
+            "mention" => Some(NotificationKind::Mention),
+            _ => None,
+        }
+    }
+}
+
+fn target_columns(target: Option<Tagged>) -> (Option<i64>, Option<i64>) {
+    match target {
+        Some(Tagged::Buzz(buzz_id)) => (Some(buzz_id), None),
+        Some(Tagged::Reply(reply_id)) => (None, Some(reply_id)),
+        None => (None, None),
+    }
+}
+
+/// Tells each of `user_ids` that `actor_id` did `kind` on `target`. Nobody is
+/// notified about their own actions.
+pub async fn notify<C: ConnectionTrait>(
+    connection: &C,
+    kind: NotificationKind,
+    user_ids: &[i64],
+    actor_id: i64,
+    target: Option<Tagged>,
+) -> Result<(), DbErr> {
+    let mut user_ids = user_ids
+        .iter()
+        .copied()
+        .filter(|user_id| *user_id != actor_id)
+        .collect::<Vec<i64>>();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+
+    let (buzz_id, reply_id) = target_columns(target);
+    let created_at: chrono::DateTime<chrono::FixedOffset> =
+        chrono::DateTime::from(chrono::Utc::now());
+
+    entity::notifications::Entity::insert_many(user_ids.into_iter().map(|user_id| {
+        entity::notifications::ActiveModel {
+            user_id: Set(user_id),
+            actor_id: Set(actor_id),
+            kind: Set(kind.key().to_string()),
+            buzz_id: Set(buzz_id),
+            reply_id: Set(reply_id),
+            created_at: Set(created_at),
+            ..Default::default()
+        }
+    }))
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes back what `notify` wrote once the action is undone, e.g. an unfollow.
+pub async fn retract<C: ConnectionTrait>(
+    connection: &C,
+    kind: NotificationKind,
+    user_id: i64,
+    actor_id: i64,
+    target: Option<Tagged>,
+) -> Result<(), DbErr> {
+    let (buzz_id, reply_id) = target_columns(target);
+
+    entity::notifications::Entity::delete_many()
+        .filter(entity::notifications::Column::UserId.eq(user_id))
+        .filter(entity::notifications::Column::ActorId.eq(actor_id))
+        .filter(entity::notifications::Column::Kind.eq(kind.key()))
+        .filter(match buzz_id {
+            Some(buzz_id) => entity::notifications::Column::BuzzId.eq(buzz_id),
+            None => entity::notifications::Column::BuzzId.is_null(),
+        })
+        .filter(match reply_id {
+            Some(reply_id) => entity::notifications::Column::ReplyId.eq(reply_id),
+            None => entity::notifications::Column::ReplyId.is_null(),
+        })
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// The buzz or reply a ratings row belongs to, with its author.
+pub async fn ratings_owner<C: ConnectionTrait>(
+    connection: &C,
+    ratings_id: i64,
+) -> Result<Option<(i64, Tagged)>, DbErr> {
+    if let Some(buzz) = entity::buzz::Entity::find()
+        .filter(entity::buzz::Column::RatingsId.eq(ratings_id))
+        .one(connection)
+        .await?
+    {
+        return Ok(Some((buzz.user_id, Tagged::Buzz(buzz.id))));
+    }
+
+    Ok(entity::reply::Entity::find()
+        .filter(entity::reply::Column::RatingsId.eq(ratings_id))
+        .one(connection)
+        .await?
+        .map(|reply| (reply.user_id, Tagged::Reply(reply.id))))
+}
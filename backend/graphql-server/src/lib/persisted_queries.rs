@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use juniper::FieldError;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::lib::common::env_or;
+
+#[derive(Deserialize)]
+pub struct RequestExtensions {
+    #[serde(rename = "persistedQuery")]
+    pub persisted_query: Option<PersistedQueryExtension>,
+}
+
+#[derive(Deserialize)]
+pub struct PersistedQueryExtension {
+    pub version: i32,
+    #[serde(rename = "sha256Hash")]
+    pub sha256_hash: String,
+}
+
+/// Automatic persisted queries keyed by the sha256 of the query document.
+///
+/// Clients first send only the hash; on `PERSISTED_QUERY_NOT_FOUND` they
+/// retry with the full document, which is then registered. In strict mode
+/// only the documents from the allowlist file are executed and nothing new
+/// is registered.
+pub struct PersistedQueries {
+    allowlist: HashMap<String, String>,
+    registered: Mutex<RegisteredQueries>,
+    strict: bool,
+    max_entries: usize,
+}
+
+/// Queries registered by clients, the least recently used one makes room
+/// once `max_entries` is reached.
+#[derive(Default)]
+struct RegisteredQueries {
+    queries: HashMap<String, (String, u64)>,
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl RegisteredQueries {
+    fn get(&mut self, hash: &str) -> Option<String> {
+        self.clock += 1;
+        let (query, used) = self.queries.get_mut(hash)?;
+        self.recency.remove(used);
+        self.recency.insert(self.clock, hash.to_string());
+        *used = self.clock;
+        Some(query.clone())
+    }
+
+    fn insert(&mut self, hash: String, query: String, max_entries: usize) {
+        if max_entries == 0 || self.get(&hash).is_some() {
+            return;
+        }
+        while self.queries.len() >= max_entries {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => self.queries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.recency.insert(self.clock, hash.clone());
+        self.queries.insert(hash, (query, self.clock));
+    }
+}
+
+pub fn sha256_hex(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+impl PersistedQueries {
+    /// `GRAPHQL_OPERATION_ALLOWLIST` points to a JSON object of
+    /// `{ "<sha256>": "<query>" }`, `GRAPHQL_STRICT_OPERATIONS=true` rejects
+    /// everything else.
+    pub fn from_env() -> Self {
+        let mut allowlist = HashMap::new();
+
+        if let Ok(path) = std::env::var("GRAPHQL_OPERATION_ALLOWLIST") {
+            let file = std::fs::read_to_string(&path).expect("Unable to read operation allowlist");
+            let entries: HashMap<String, String> =
+                serde_json::from_str(&file).expect("Operation allowlist must be a JSON object");
+
+            for (hash, query) in entries {
+                if sha256_hex(&query) != hash {
+                    panic!("Operation allowlist hash {} does not match its query", hash);
+                }
+                allowlist.insert(hash, query);
+            }
+        }
+
+        let strict = env_or("GRAPHQL_STRICT_OPERATIONS", false);
+        if strict && allowlist.is_empty() {
+            panic!("GRAPHQL_STRICT_OPERATIONS requires GRAPHQL_OPERATION_ALLOWLIST");
+        }
+
+        PersistedQueries::new(allowlist, strict, env_or("GRAPHQL_APQ_MAX_ENTRIES", 10_000))
+    }
+
+    /// Allowlisted queries are kept for good, at most `max_entries` others
+    /// are registered next to them.
+    fn new(allowlist: HashMap<String, String>, strict: bool, max_entries: usize) -> Self {
+        PersistedQueries {
+            allowlist,
+            registered: Mutex::new(RegisteredQueries::default()),
+            strict,
+            max_entries,
+        }
+    }
+
+    fn lookup(&self, hash: &str) -> Option<String> {
+        if let Some(query) = self.allowlist.get(hash) {
+            return Some(query.clone());
+        }
+        if self.strict {
+            return None;
+        }
+        self.registered.lock().unwrap().get(hash)
+    }
+
+    /// Returns the query document to execute for this request.
+    pub fn resolve(
+        &self,
+        query: Option<String>,
+        extensions: Option<&RequestExtensions>,
+    ) -> Result<String, FieldError> {
+        let persisted = extensions.and_then(|extensions| extensions.persisted_query.as_ref());
+
+        match (query, persisted) {
+            (Some(query), None) => {
+                if self.strict && !self.allowlist.contains_key(&sha256_hex(&query)) {
+                    return Err(Self::not_allowed());
+                }
+                Ok(query)
+            }
+
+            (None, Some(persisted)) => {
+                Self::check_version(persisted)?;
+                match self.lookup(&persisted.sha256_hash) {
+                    Some(query) => Ok(query),
+                    None if self.strict => Err(Self::not_allowed()),
+                    None => Err(FieldError::new(
+                        "PersistedQueryNotFound",
+                        graphql_value!({ "code": "PERSISTED_QUERY_NOT_FOUND" }),
+                    )),
+                }
+            }
+
+            (Some(query), Some(persisted)) => {
+                Self::check_version(persisted)?;
+                if sha256_hex(&query) != persisted.sha256_hash {
+                    return Err(FieldError::new(
+                        "provided sha does not match query",
+                        graphql_value!({ "code": "PERSISTED_QUERY_HASH_MISMATCH" }),
+                    ));
+                }
+
+                if !self.allowlist.contains_key(&persisted.sha256_hash) {
+                    if self.strict {
+                        return Err(Self::not_allowed());
+                    }
+                    self.registered.lock().unwrap().insert(
+                        persisted.sha256_hash.clone(),
+                        query.clone(),
+                        self.max_entries,
+                    );
+                }
+                Ok(query)
+            }
+
+            (None, None) => Err(FieldError::new(
+                "Must provide query string",
+                graphql_value!({ "code": "BAD_REQUEST" }),
+            )),
+        }
+    }
+
+    fn check_version(persisted: &PersistedQueryExtension) -> Result<(), FieldError> {
+        if persisted.version != 1 {
+            return Err(FieldError::new(
+                "Unsupported persisted query version",
+                graphql_value!({ "code": "PERSISTED_QUERY_NOT_SUPPORTED" }),
+            ));
+        }
+        Ok(())
+    }
+
+    fn not_allowed() -> FieldError {
+        FieldError::new(
+            "Operation is not in the allowlist",
+            graphql_value!({ "code": "OPERATION_NOT_ALLOWED" }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(query: &str) -> RequestExtensions {
+        RequestExtensions {
+            persisted_query: Some(PersistedQueryExtension {
+                version: 1,
+                sha256_hash: sha256_hex(query),
+            }),
+        }
+    }
+
+    fn register(queries: &PersistedQueries, query: &str) {
+        queries
+            .resolve(Some(query.to_string()), Some(&extensions(query)))
+            .unwrap();
+    }
+
+    fn is_registered(queries: &PersistedQueries, query: &str) -> bool {
+        queries.resolve(None, Some(&extensions(query))).is_ok()
+    }
+
+    #[test]
+    fn registers_queries_sent_with_their_hash() {
+        let queries = PersistedQueries::new(HashMap::new(), false, 10);
+        assert!(!is_registered(&queries, "{ a }"));
+        register(&queries, "{ a }");
+        assert_eq!(
+            queries.resolve(None, Some(&extensions("{ a }"))).unwrap(),
+            "{ a }"
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_hashes() {
+        let queries = PersistedQueries::new(HashMap::new(), false, 10);
+        let error = queries
+            .resolve(Some("{ b }".to_string()), Some(&extensions("{ a }")))
+            .unwrap_err();
+        assert_eq!(error.message(), "provided sha does not match query");
+        assert!(!is_registered(&queries, "{ a }"));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_query_when_full() {
+        let queries = PersistedQueries::new(HashMap::new(), false, 2);
+        register(&queries, "{ a }");
+        register(&queries, "{ b }");
+        assert!(is_registered(&queries, "{ a }"));
+
+        register(&queries, "{ c }");
+        assert!(is_registered(&queries, "{ a }"));
+        assert!(!is_registered(&queries, "{ b }"));
+        assert!(is_registered(&queries, "{ c }"));
+    }
+
+    #[test]
+    fn keeps_the_allowlist_when_full() {
+        let allowlist = HashMap::from([(sha256_hex("{ a }"), "{ a }".to_string())]);
+        let queries = PersistedQueries::new(allowlist, false, 1);
+        register(&queries, "{ b }");
+        register(&queries, "{ c }");
+        assert!(is_registered(&queries, "{ a }"));
+        assert!(!is_registered(&queries, "{ b }"));
+        assert!(is_registered(&queries, "{ c }"));
+    }
+
+    #[test]
+    fn strict_mode_only_runs_the_allowlist() {
+        let allowlist = HashMap::from([(sha256_hex("{ a }"), "{ a }".to_string())]);
+        let queries = PersistedQueries::new(allowlist, true, 10);
+        assert!(queries.resolve(Some("{ a }".to_string()), None).is_ok());
+        assert!(queries.resolve(Some("{ b }".to_string()), None).is_err());
+        assert!(queries
+            .resolve(Some("{ b }".to_string()), Some(&extensions("{ b }")))
+            .is_err());
+        assert!(!is_registered(&queries, "{ b }"));
+    }
+}
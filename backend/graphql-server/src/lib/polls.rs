@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, ConnectionTrait, DbBackend, DbErr,
+    Statement,
+};
+
+use crate::lib::{
+    common::{env_or, to_id_list},
+    errors::{AppError, AppResult},
+};
+
+const MIN_POLL_OPTIONS: usize = 2;
+const MAX_POLL_OPTIONS: usize = 4;
+const MAX_OPTION_LENGTH: usize = 50;
+
+/// A poll that passed `check_poll`, saved together with its buzz.
+#[derive(Clone, Debug)]
+pub struct NewPoll {
+    pub options: Vec<String>,
+    pub duration: chrono::Duration,
+    pub multiple_choice: bool,
+    pub hide_results: bool,
+}
+
+/// Polls have 2 to 4 distinct options and stay open between
+/// `POLL_MIN_DURATION_MINUTES` and `POLL_MAX_DURATION_MINUTES`.
+pub fn check_poll(
+    options: Vec<String>,
+    duration_minutes: i32,
+    multiple_choice: bool,
+    hide_results: bool,
+) -> AppResult<NewPoll> {
+    if options.len() < MIN_POLL_OPTIONS || options.len() > MAX_POLL_OPTIONS {
+        return Err(AppError::BadRequest(format!(
+            "A poll needs {} to {} options",
+            MIN_POLL_OPTIONS, MAX_POLL_OPTIONS
+        )));
+    }
+
+    let mut seen = HashSet::new();
+    let mut labels = Vec::with_capacity(options.len());
+    for option in options {
+        let label = option.trim().to_string();
+        if label.is_empty() || label.chars().count() > MAX_OPTION_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "Poll options must be 1 to {} characters",
+                MAX_OPTION_LENGTH
+            )));
+        }
+        if !seen.insert(label.to_lowercase()) {
+            return Err(AppError::BadRequest(
+                "Poll options must be different".to_string(),
+            ));
+        }
+        labels.push(label);
+    }
+
+    let min_minutes: i32 = env_or("POLL_MIN_DURATION_MINUTES", 5);
+    let max_minutes: i32 = env_or("POLL_MAX_DURATION_MINUTES", 7 * 24 * 60);
+    if duration_minutes < min_minutes || duration_minutes > max_minutes {
+        return Err(AppError::BadRequest(format!(
+            "A poll runs for {} to {} minutes",
+            min_minutes, max_minutes
+        )));
+    }
+
+    Ok(NewPoll {
+        options: labels,
+        duration: chrono::Duration::minutes(duration_minutes as i64),
+        multiple_choice,
+        hide_results,
+    })
+}
+
+/// Saves `poll` for `buzz_id`, it closes `poll.duration` after `created_at`.
+pub async fn create_poll<C: ConnectionTrait>(
+    connection: &C,
+    buzz_id: i64,
+    poll: NewPoll,
+    created_at: DateTimeWithTimeZone,
+) -> Result<entity::polls::Model, DbErr> {
+    let saved = entity::polls::ActiveModel {
+        buzz_id: Set(buzz_id),
+        multiple_choice: Set(poll.multiple_choice),
+        hide_results: Set(poll.hide_results),
+        closes_at: Set(created_at + poll.duration),
+        created_at: Set(created_at),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    entity::poll_options::Entity::insert_many(poll.options.into_iter().enumerate().map(
+        |(position, label)| entity::poll_options::ActiveModel {
+            poll_id: Set(saved.id),
+            position: Set(position as i32),
+            label: Set(label),
+            ..Default::default()
+        },
+    ))
+    .exec(connection)
+    .await?;
+
+    Ok(saved)
+}
+
+/// Whether `poll` stopped taking votes.
+pub fn is_closed(poll: &entity::polls::Model) -> bool {
+    poll.closes_at <= chrono::Utc::now()
+}
+
+/// Casts the one vote `user_id` gets in `poll_id`, for one option or, in
+/// multiple choice polls, several. The votes only go in when the user has
+/// none in the poll yet, run it inside a serializable transaction so two
+/// votes of the same user cannot both pass.
+pub async fn vote_poll<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    poll_id: i64,
+    option_ids: &[i64],
+) -> AppResult<entity::polls::Model> {
+    let poll = entity::polls::Entity::find_by_id(poll_id)
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("Poll"))?;
+
+    if is_closed(&poll) {
+        return Err(AppError::BadRequest("This poll is closed".to_string()));
+    }
+
+    let mut chosen: Vec<i64> = option_ids.to_vec();
+    chosen.sort_unstable();
+    chosen.dedup();
+    if chosen.is_empty() {
+        return Err(AppError::BadRequest("Choose an option".to_string()));
+    }
+    if !poll.multiple_choice && chosen.len() > 1 {
+        return Err(AppError::BadRequest(
+            "Only one option can be chosen in this poll".to_string(),
+        ));
+    }
+
+    let options = entity::poll_options::Entity::find()
+        .filter(entity::poll_options::Column::PollId.eq(poll.id))
+        .filter(entity::poll_options::Column::Id.is_in(chosen.clone()))
+        .count(connection)
+        .await?;
+    if options != chosen.len() {
+        return Err(AppError::NotFound("Poll option"));
+    }
+
+    let voted = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                r#"INSERT INTO poll_votes (poll_id, option_id, user_id, created_at)
+                SELECT $1, chosen.option_id, $2, NOW()
+                FROM UNNEST(ARRAY[{}]::BIGINT[]) AS chosen(option_id)
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM poll_votes WHERE poll_id = $1 AND user_id = $2
+                )
+                ON CONFLICT (poll_id, user_id, option_id) DO NOTHING"#,
+                to_id_list(chosen)
+            ),
+            vec![poll.id.into(), user_id.into()],
+        ))
+        .await?
+        .rows_affected();
+    if voted == 0 {
+        return Err(AppError::BadRequest(
+            "You already voted in this poll".to_string(),
+        ));
+    }
+
+    Ok(poll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|label| label.to_string()).collect()
+    }
+
+    #[test]
+    fn accepts_a_poll() {
+        let poll = check_poll(options(&[" yes ", "no"]), 60, true, false).unwrap();
+        assert_eq!(poll.options, vec!["yes", "no"]);
+        assert_eq!(poll.duration, chrono::Duration::minutes(60));
+        assert!(poll.multiple_choice);
+        assert!(!poll.hide_results);
+    }
+
+    #[test]
+    fn rejects_too_few_or_too_many_options() {
+        assert!(check_poll(options(&["yes"]), 60, false, false).is_err());
+        assert!(check_poll(options(&["a", "b", "c", "d", "e"]), 60, false, false).is_err());
+        assert!(check_poll(options(&["a", "b", "c", "d"]), 60, false, false).is_ok());
+    }
+
+    #[test]
+    fn rejects_blank_long_or_repeated_options() {
+        assert!(check_poll(options(&["yes", "  "]), 60, false, false).is_err());
+        assert!(check_poll(options(&["yes", &"n".repeat(51)]), 60, false, false).is_err());
+        assert!(check_poll(options(&["yes", &"n".repeat(50)]), 60, false, false).is_ok());
+        assert!(check_poll(options(&["Yes", "yes "]), 60, false, false).is_err());
+    }
+
+    #[test]
+    fn rejects_durations_out_of_range() {
+        assert!(check_poll(options(&["yes", "no"]), 4, false, false).is_err());
+        assert!(check_poll(options(&["yes", "no"]), 5, false, false).is_ok());
+        assert!(check_poll(options(&["yes", "no"]), 7 * 24 * 60, false, false).is_ok());
+        assert!(check_poll(options(&["yes", "no"]), 7 * 24 * 60 + 1, false, false).is_err());
+    }
+}
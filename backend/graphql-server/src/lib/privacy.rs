@@ -0,0 +1,52 @@
+use sea_orm::{
+    entity::*,
+    sea_query::{Expr, SimpleExpr},
+    ConnectionTrait, DbErr,
+};
+
+use crate::lib::follows::is_following;
+
+/// Condition on `author_column` that leaves out private accounts whose
+/// content `viewer_id` may not see: all of them for anonymous viewers,
+/// otherwise those the viewer neither is nor follows.
+pub fn not_locked(author_column: &str, viewer_id: Option<i64>) -> SimpleExpr {
+    Expr::cust_with_values(&not_locked_sql(author_column, "$1"), vec![viewer_id])
+}
+
+/// `not_locked` for hand written statements, `viewer` is the placeholder
+/// bound to the viewer id, or to NULL for anonymous viewers.
+pub fn not_locked_sql(author_column: &str, viewer: &str) -> String {
+    format!(
+        r#"NOT EXISTS (
+            SELECT 1 FROM users AS locked
+            WHERE locked.id = {0} AND locked.is_private
+            AND locked.id IS DISTINCT FROM {1}
+            AND NOT EXISTS (
+                SELECT 1 FROM follows
+                WHERE follows.follower_id = {1} AND follows.followee_id = locked.id
+            )
+        )"#,
+        author_column, viewer
+    )
+}
+
+/// Whether `user_id` is a private account `viewer_id` may not see.
+pub async fn is_locked<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    viewer_id: Option<i64>,
+) -> Result<bool, DbErr> {
+    if viewer_id == Some(user_id) {
+        return Ok(false);
+    }
+
+    let is_private = entity::users::Entity::find_by_id(user_id)
+        .one(connection)
+        .await?
+        .is_some_and(|user| user.is_private);
+
+    match viewer_id {
+        Some(viewer_id) if is_private => Ok(!is_following(connection, viewer_id, user_id).await?),
+        _ => Ok(is_private),
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use juniper::{parser::parse_document_source, Definition, FieldError, InputValue, Selection};
+
+use crate::lib::common::env_or;
+use crate::schemas::root::Schema;
+
+/// Limits checked against the query document before it is executed.
+///
+/// Each field costs `default_field_cost` (or its entry in `field_costs`) and
+/// everything selected below a paginated field is multiplied by the
+/// requested `pageSize`, so `getBuzzes(pageSize: 50) { author { .. } }`
+/// costs 50 authors.
+#[derive(Debug, Clone)]
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_aliases: usize,
+    pub max_cost: i64,
+    pub default_field_cost: i64,
+    pub field_costs: HashMap<String, i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct QueryCost {
+    pub depth: usize,
+    pub aliases: usize,
+    pub cost: i64,
+}
+
+impl QueryLimits {
+    /// `GRAPHQL_FIELD_COSTS` takes comma separated overrides such as
+    /// `getBuzzes=5,replies=3`.
+    pub fn from_env() -> Self {
+        let field_costs = match std::env::var("GRAPHQL_FIELD_COSTS") {
+            Ok(costs) => costs
+                .split(',')
+                .filter_map(|cost| {
+                    let (field, cost) = cost.split_once('=')?;
+                    Some((field.trim().to_string(), cost.trim().parse::<i64>().ok()?))
+                })
+                .collect(),
+            Err(_) => HashMap::new(),
+        };
+
+        QueryLimits {
+            max_depth: env_or("GRAPHQL_MAX_DEPTH", 10),
+            max_aliases: env_or("GRAPHQL_MAX_ALIASES", 15),
+            max_cost: env_or("GRAPHQL_MAX_COST", 10_000),
+            default_field_cost: env_or("GRAPHQL_DEFAULT_FIELD_COST", 1),
+            field_costs,
+        }
+    }
+
+    /// Documents that fail to parse are rejected, their cost cannot be
+    /// measured.
+    pub fn check(
+        &self,
+        schema: &Schema,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: Option<&InputValue>,
+    ) -> Result<QueryCost, FieldError> {
+        let document = match parse_document_source(query, &schema.schema) {
+            Ok(document) => document,
+            Err(error) => {
+                return Err(FieldError::new(
+                    format!("Query does not parse: {}", error.item),
+                    graphql_value!({ "code": "GRAPHQL_PARSE_FAILED" }),
+                ))
+            }
+        };
+
+        let mut fragments = HashMap::new();
+        for definition in document.iter() {
+            if let Definition::Fragment(fragment) = definition {
+                fragments.insert(fragment.item.name.item, &fragment.item.selection_set);
+            }
+        }
+
+        let mut walker = Walker {
+            limits: self,
+            fragments,
+            variables,
+            defaults: HashMap::new(),
+            fragment_path: Vec::new(),
+            cost: QueryCost::default(),
+        };
+
+        for definition in document.iter() {
+            if let Definition::Operation(operation) = definition {
+                let name = operation.item.name.as_ref().map(|name| name.item);
+                if operation_name.is_none() || operation_name == name {
+                    walker.defaults = operation
+                        .item
+                        .variable_definitions
+                        .iter()
+                        .flat_map(|definitions| definitions.item.items.iter())
+                        .filter_map(|(name, definition)| {
+                            Some((name.item, &definition.default_value.as_ref()?.item))
+                        })
+                        .collect();
+                    walker.walk(&operation.item.selection_set, 1, 1);
+                }
+            }
+        }
+
+        let cost = walker.cost;
+        if cost.depth > self.max_depth {
+            return Err(FieldError::new(
+                format!(
+                    "Query depth {} exceeds the maximum of {}",
+                    cost.depth, self.max_depth
+                ),
+                graphql_value!({ "code": "QUERY_TOO_DEEP" }),
+            ));
+        }
+        if cost.aliases > self.max_aliases {
+            return Err(FieldError::new(
+                format!(
+                    "Query uses {} aliases, the maximum is {}",
+                    cost.aliases, self.max_aliases
+                ),
+                graphql_value!({ "code": "TOO_MANY_ALIASES" }),
+            ));
+        }
+        if cost.cost > self.max_cost {
+            return Err(FieldError::new(
+                format!(
+                    "Query cost {} exceeds the maximum of {}",
+                    cost.cost, self.max_cost
+                ),
+                graphql_value!({ "code": "QUERY_TOO_COMPLEX" }),
+            ));
+        }
+
+        Ok(cost)
+    }
+
+    fn field_cost(&self, field: &str) -> i64 {
+        *self
+            .field_costs
+            .get(field)
+            .unwrap_or(&self.default_field_cost)
+    }
+}
+
+struct Walker<'a> {
+    limits: &'a QueryLimits,
+    fragments: HashMap<&'a str, &'a Vec<Selection<'a>>>,
+    variables: Option<&'a InputValue>,
+    /// Defaults of the operation being walked, used for the variables the
+    /// request leaves out.
+    defaults: HashMap<&'a str, &'a InputValue>,
+    fragment_path: Vec<&'a str>,
+    cost: QueryCost,
+}
+
+impl<'a> Walker<'a> {
+    fn walk(&mut self, selection_set: &'a [Selection<'a>], multiplier: i64, depth: usize) {
+        for selection in selection_set {
+            match selection {
+                Selection::Field(field) => {
+                    let field = &field.item;
+                    // Introspection is generated by tooling and is not
+                    // backed by the database.
+                    if field.name.item.starts_with("__") {
+                        continue;
+                    }
+
+                    if field.alias.is_some() {
+                        self.cost.aliases += 1;
+                    }
+                    self.cost.depth = self.cost.depth.max(depth);
+                    self.cost.cost = self.cost.cost.saturating_add(
+                        multiplier.saturating_mul(self.limits.field_cost(field.name.item)),
+                    );
+
+                    if let Some(selection_set) = &field.selection_set {
+                        let page_size = field
+                            .arguments
+                            .as_ref()
+                            .and_then(|arguments| {
+                                arguments.item.items.iter().find_map(|(name, value)| {
+                                    if name.item == "pageSize" {
+                                        self.resolve_int(&value.item)
+                                    } else {
+                                        self.page_size(&value.item)
+                                    }
+                                })
+                            })
+                            .unwrap_or(1);
+                        self.walk(
+                            selection_set,
+                            multiplier.saturating_mul(page_size),
+                            depth + 1,
+                        );
+                    }
+                }
+
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.item.name.item;
+                    if self.fragment_path.contains(&name) {
+                        continue;
+                    }
+                    if let Some(selection_set) = self.fragments.get(name).copied() {
+                        self.fragment_path.push(name);
+                        self.walk(selection_set, multiplier, depth);
+                        self.fragment_path.pop();
+                    }
+                }
+
+                Selection::InlineFragment(fragment) => {
+                    self.walk(&fragment.item.selection_set, multiplier, depth);
+                }
+            }
+        }
+    }
+
+    /// Finds `pageSize` nested in an input object such as `pageDetails`.
+    fn page_size(&self, value: &InputValue) -> Option<i64> {
+        match value {
+            InputValue::Object(fields) => fields.iter().find_map(|(name, value)| {
+                if name.item == "pageSize" {
+                    self.resolve_int(&value.item)
+                } else {
+                    self.page_size(&value.item)
+                }
+            }),
+            InputValue::Variable(name) => {
+                self.variable(name).and_then(|value| self.page_size(value))
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve_int(&self, value: &InputValue) -> Option<i64> {
+        match value {
+            InputValue::Variable(name) => self
+                .variable(name)
+                .and_then(|value| self.resolve_int(value)),
+            value => value.as_int_value().map(|size| (size as i64).max(1)),
+        }
+    }
+
+    fn variable(&self, name: &str) -> Option<&'a InputValue> {
+        let provided = match self.variables {
+            Some(InputValue::Object(fields)) => fields
+                .iter()
+                .find(|(variable, _)| variable.item == name)
+                .map(|(_, value)| &value.item),
+            _ => None,
+        };
+        provided.or_else(|| self.defaults.get(name).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::root::create_schema;
+
+    fn limits() -> QueryLimits {
+        QueryLimits {
+            max_depth: 5,
+            max_aliases: 2,
+            max_cost: 1_000,
+            default_field_cost: 1,
+            field_costs: HashMap::new(),
+        }
+    }
+
+    fn check(query: &str, variables: Option<&InputValue>) -> Result<QueryCost, FieldError> {
+        limits().check(&create_schema(), query, None, variables)
+    }
+
+    fn input(json: &str) -> InputValue {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn code(error: FieldError) -> String {
+        error
+            .extensions()
+            .as_object_value()
+            .and_then(|extensions| extensions.get_field_value("code"))
+            .and_then(|code| code.as_string_value())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    #[test]
+    fn multiplies_below_paginated_fields() {
+        let cost = check(
+            "{ getBuzzes(pageDetails: { pageSize: 50, pageNumber: 1 }) { buzzes { id } } }",
+            None,
+        )
+        .unwrap();
+        assert_eq!(cost.depth, 3);
+        assert_eq!(cost.cost, 1 + 50 + 50);
+    }
+
+    #[test]
+    fn counts_fragments_and_aliases() {
+        let cost = check(
+            "{ first: getUser(id: \"1\") { ...user } second: getUser(id: \"2\") { ...user } }
+            fragment user on UserDetails { id username }",
+            None,
+        )
+        .unwrap();
+        assert_eq!(cost.aliases, 2);
+        assert_eq!(cost.depth, 2);
+        assert_eq!(cost.cost, 6);
+    }
+
+    #[test]
+    fn stops_at_recursive_fragments() {
+        let cost = check(
+            "{ getUser(id: \"1\") { ...user } } fragment user on UserDetails { id ...user }",
+            None,
+        )
+        .unwrap();
+        assert_eq!(cost.cost, 2);
+    }
+
+    #[test]
+    fn skips_introspection() {
+        let cost = check("{ __schema { types { name } } }", None).unwrap();
+        assert_eq!(cost.cost, 0);
+    }
+
+    #[test]
+    fn rejects_deep_queries() {
+        let error = check("{ a { b { c { d { e { f } } } } } }", None).unwrap_err();
+        assert_eq!(code(error), "QUERY_TOO_DEEP");
+    }
+
+    #[test]
+    fn rejects_too_many_aliases() {
+        let error = check("{ a: id b: id c: id }", None).unwrap_err();
+        assert_eq!(code(error), "TOO_MANY_ALIASES");
+    }
+
+    #[test]
+    fn reads_page_size_from_variables() {
+        let query = "query($size: Int) { searchBuzzes(query: \"x\", pageSize: $size, pageNumber: 1) { buzzes { id } } }";
+        let variables = input(r#"{ "size": 10000 }"#);
+        let error = check(query, Some(&variables)).unwrap_err();
+        assert_eq!(code(error), "QUERY_TOO_COMPLEX");
+
+        let variables = input(r#"{ "size": 10 }"#);
+        assert_eq!(check(query, Some(&variables)).unwrap().cost, 21);
+    }
+
+    #[test]
+    fn reads_page_size_from_variable_defaults() {
+        let query = "query($size: Int = 10000) { searchBuzzes(query: \"x\", pageSize: $size, pageNumber: 1) { buzzes { id } } }";
+        let error = check(query, None).unwrap_err();
+        assert_eq!(code(error), "QUERY_TOO_COMPLEX");
+
+        let variables = input(r#"{ "size": 10 }"#);
+        assert_eq!(check(query, Some(&variables)).unwrap().cost, 21);
+    }
+
+    #[test]
+    fn rejects_unparseable_documents() {
+        let error = check("{ getBuzzes(", None).unwrap_err();
+        assert_eq!(code(error), "GRAPHQL_PARSE_FAILED");
+    }
+}
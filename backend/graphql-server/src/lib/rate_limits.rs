@@ -0,0 +1,184 @@
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+
+use crate::lib::{
+    common::env_or,
+    errors::{AppError, AppResult},
+};
+
+/// Write mutations limited together, each has its own buckets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MutationClass {
+    CreateBuzz,
+    CreateReply,
+    Upvote,
+    ChangeFollowUser,
+}
+
+impl MutationClass {
+    /// Part of the bucket keys and of the environment variables.
+    pub fn key(&self) -> &'static str {
+        match self {
+            MutationClass::CreateBuzz => "create_buzz",
+            MutationClass::CreateReply => "create_reply",
+            MutationClass::Upvote => "upvote",
+            MutationClass::ChangeFollowUser => "change_follow_user",
+        }
+    }
+
+    /// Burst and refill per minute.
+    fn default_limit(&self) -> (f64, f64) {
+        match self {
+            MutationClass::CreateBuzz => (10.0, 10.0),
+            MutationClass::CreateReply => (20.0, 30.0),
+            MutationClass::Upvote => (60.0, 120.0),
+            MutationClass::ChangeFollowUser => (30.0, 30.0),
+        }
+    }
+
+    /// `RATE_LIMIT_<CLASS>_BURST` calls at once, refilled at
+    /// `RATE_LIMIT_<CLASS>_PER_MINUTE`. A burst of 0 turns the limit off.
+    fn limit(&self) -> TokenBucket {
+        let (burst, per_minute) = self.default_limit();
+        let name = self.key().to_uppercase();
+        TokenBucket {
+            capacity: env_or(&format!("RATE_LIMIT_{}_BURST", name), burst),
+            refill_per_second: env_or(&format!("RATE_LIMIT_{}_PER_MINUTE", name), per_minute)
+                / 60.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+}
+
+#[derive(FromQueryResult)]
+struct RetryAfter {
+    retry_after: i64,
+}
+
+/// Takes a token from the buckets of `user_id` and of `client_ip` for
+/// `class`, or fails with the seconds until one is back. Many users can share
+/// an address, so its buckets hold `RATE_LIMIT_IP_FACTOR` times as much.
+/// A call turned away by either bucket costs nothing.
+pub async fn check_rate_limit<C: ConnectionTrait>(
+    connection: &C,
+    class: MutationClass,
+    user_id: i64,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let limit = class.limit();
+    if limit.capacity <= 0.0 || limit.refill_per_second <= 0.0 {
+        return Ok(());
+    }
+
+    let user_key = format!("{}:user:{}", class.key(), user_id);
+    take_token(connection, &user_key, limit).await?;
+
+    if let Some(client_ip) = client_ip {
+        let factor: f64 = env_or("RATE_LIMIT_IP_FACTOR", 5.0);
+        let ip_limit = TokenBucket {
+            capacity: limit.capacity * factor,
+            refill_per_second: limit.refill_per_second * factor,
+        };
+        let taken = take_token(
+            connection,
+            &format!("{}:ip:{}", class.key(), client_ip),
+            ip_limit,
+        )
+        .await;
+        if taken.is_err() {
+            refund_token(connection, &user_key, limit).await?;
+        }
+        taken?;
+    }
+
+    Ok(())
+}
+
+/// Refilling and taking happen in one statement on the shared row, so
+/// servers running side by side see the same bucket.
+async fn take_token<C: ConnectionTrait>(
+    connection: &C,
+    bucket_key: &str,
+    limit: TokenBucket,
+) -> AppResult<()> {
+    let taken = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO rate_limit_buckets (bucket_key, tokens, updated_at)
+            VALUES ($1, $2 - 1, NOW())
+            ON CONFLICT (bucket_key) DO UPDATE SET
+                tokens = LEAST($2, rate_limit_buckets.tokens
+                    + EXTRACT(EPOCH FROM NOW() - rate_limit_buckets.updated_at)::DOUBLE PRECISION
+                    * $3) - 1,
+                updated_at = NOW()
+            WHERE LEAST($2, rate_limit_buckets.tokens
+                + EXTRACT(EPOCH FROM NOW() - rate_limit_buckets.updated_at)::DOUBLE PRECISION
+                * $3) >= 1"#,
+            vec![
+                bucket_key.into(),
+                limit.capacity.into(),
+                limit.refill_per_second.into(),
+            ],
+        ))
+        .await?
+        .rows_affected();
+    if taken > 0 {
+        return Ok(());
+    }
+
+    let retry_after = RetryAfter::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT CEIL((1 - LEAST($2, tokens
+            + EXTRACT(EPOCH FROM NOW() - updated_at)::DOUBLE PRECISION * $3)) / $3)::BIGINT
+            AS retry_after
+        FROM rate_limit_buckets WHERE bucket_key = $1"#,
+        vec![
+            bucket_key.into(),
+            limit.capacity.into(),
+            limit.refill_per_second.into(),
+        ],
+    ))
+    .one(connection)
+    .await?
+    .map_or(1, |row| row.retry_after.max(1));
+
+    Err(AppError::RateLimited(retry_after as i32))
+}
+
+/// Puts back a token `take_token` took.
+async fn refund_token<C: ConnectionTrait>(
+    connection: &C,
+    bucket_key: &str,
+    limit: TokenBucket,
+) -> Result<(), DbErr> {
+    connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "UPDATE rate_limit_buckets SET tokens = LEAST($2, tokens + 1) WHERE bucket_key = $1",
+            vec![bucket_key.into(), limit.capacity.into()],
+        ))
+        .await?;
+
+    Ok(())
+}
+
+/// Forgets buckets nobody drew from since `idle_seconds` ago. Once refilled
+/// they are no different from a missing one.
+pub async fn purge_idle_buckets<C: ConnectionTrait>(
+    connection: &C,
+    idle_seconds: i64,
+) -> Result<u64, DbErr> {
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - $1 * INTERVAL '1 second'",
+            vec![idle_seconds.into()],
+        ))
+        .await?;
+
+    Ok(result.rows_affected())
+}
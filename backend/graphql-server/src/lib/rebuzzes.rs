@@ -0,0 +1,52 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbBackend, DbErr, Statement};
+
+use crate::schemas::ratings::adjust_ratings_counter;
+
+/// Rebuzzes `buzz` for `user_id`, or takes the rebuzz back when there is
+/// one. Returns whether the user rebuzzes it now. Like `toggle_upvote`,
+/// `ratings.rebuzzes` only moves when a row was really added or removed; run
+/// it inside a transaction to keep both in step.
+pub async fn toggle_rebuzz<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    buzz: &entity::buzz::Model,
+) -> Result<bool, DbErr> {
+    let removed = entity::rebuzz::Entity::delete_many()
+        .filter(entity::rebuzz::Column::UserId.eq(user_id))
+        .filter(entity::rebuzz::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?
+        .rows_affected;
+    if removed > 0 {
+        adjust_ratings_counter(
+            connection,
+            buzz.ratings_id,
+            entity::ratings::Column::Rebuzzes,
+            -(removed as i64),
+        )
+        .await?;
+        return Ok(false);
+    }
+
+    let added = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO rebuzz (user_id, buzz_id, created_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (user_id, buzz_id) DO NOTHING"#,
+            vec![user_id.into(), buzz.id.into()],
+        ))
+        .await?
+        .rows_affected();
+    if added > 0 {
+        adjust_ratings_counter(
+            connection,
+            buzz.ratings_id,
+            entity::ratings::Column::Rebuzzes,
+            added as i64,
+        )
+        .await?;
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,49 @@
+use sea_orm::{entity::*, ConnectionTrait, DbErr};
+
+/// Account changes users can look back on in their data export. Logins are
+/// recorded by the auth server as `login` and `login_failed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityEventKind {
+    UsernameChanged,
+    PasswordChanged,
+    EmailChanged,
+    ContactNumberChanged,
+    LoggedOutEverywhere,
+    DeletionRequested,
+    DataExportRequested,
+}
+
+impl SecurityEventKind {
+    /// Stored in `security_events.kind`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            SecurityEventKind::UsernameChanged => "username_changed",
+            SecurityEventKind::PasswordChanged => "password_changed",
+            SecurityEventKind::EmailChanged => "email_changed",
+            SecurityEventKind::ContactNumberChanged => "contact_number_changed",
+            SecurityEventKind::LoggedOutEverywhere => "logged_out_everywhere",
+            SecurityEventKind::DeletionRequested => "deletion_requested",
+            SecurityEventKind::DataExportRequested => "data_export_requested",
+        }
+    }
+}
+
+/// Records `kind` for `user_id`, `ip_address` is where the request came from.
+pub async fn record_security_event<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    kind: SecurityEventKind,
+    ip_address: Option<&str>,
+) -> Result<(), DbErr> {
+    entity::security_events::ActiveModel {
+        user_id: Set(user_id),
+        kind: Set(kind.key().to_string()),
+        ip_address: Set(ip_address.map(|ip_address| ip_address.to_string())),
+        created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    Ok(())
+}
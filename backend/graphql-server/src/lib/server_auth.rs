@@ -1,9 +1,12 @@
 use reqwest;
 use std::collections::HashMap;
 
+use crate::lib::errors::{AppError, AppResult};
+
 pub struct Authenticated {
     pub auth_id: i64,
     pub user_id: i64,
+    #[allow(dead_code)]
     pub username: String,
     pub is_one_time_jwt: bool,
 }
@@ -17,10 +20,13 @@ pub async fn authenticate(jwt: String) -> AuthenticationStatus {
     let mut json_jwt = HashMap::new();
     json_jwt.insert("jwt", jwt);
 
-    let client = reqwest::Client::builder()
+    let client = match reqwest::Client::builder()
         .danger_accept_invalid_certs(true)
         .build()
-        .unwrap();
+    {
+        Ok(client) => client,
+        Err(_) => return AuthenticationStatus::Unauthenticated,
+    };
     let res = client
         .post("https://localhost:9004/authenticate")
         .json(&json_jwt)
@@ -31,16 +37,24 @@ pub async fn authenticate(jwt: String) -> AuthenticationStatus {
         Ok(res) => {
             let status = res.status();
             if status == 200 {
-                let json: serde_json::Value = res.json().await.unwrap();
-                if json["is_authenticated"].as_bool().unwrap() {
-                    AuthenticationStatus::Authenticated(Authenticated {
-                        auth_id: json["auth_id"].as_i64().unwrap(),
-                        user_id: json["user_id"].as_i64().unwrap(),
-                        username: json["username"].as_str().unwrap().to_string(),
-                        is_one_time_jwt: json["is_one_time_jwt"].as_bool().unwrap(),
+                let json: serde_json::Value = match res.json().await {
+                    Ok(json) => json,
+                    Err(_) => return AuthenticationStatus::Unauthenticated,
+                };
+                let authenticated = (|| {
+                    if !json["is_authenticated"].as_bool()? {
+                        return None;
+                    }
+                    Some(Authenticated {
+                        auth_id: json["auth_id"].as_i64()?,
+                        user_id: json["user_id"].as_i64()?,
+                        username: json["username"].as_str()?.to_string(),
+                        is_one_time_jwt: json["is_one_time_jwt"].as_bool()?,
                     })
-                } else {
-                    AuthenticationStatus::Unauthenticated
+                })();
+                match authenticated {
+                    Some(authenticated) => AuthenticationStatus::Authenticated(authenticated),
+                    None => AuthenticationStatus::Unauthenticated,
                 }
             } else {
                 AuthenticationStatus::Unauthenticated
@@ -49,3 +63,20 @@ pub async fn authenticate(jwt: String) -> AuthenticationStatus {
         Err(_) => AuthenticationStatus::Unauthenticated,
     }
 }
+
+pub async fn require_auth(jwt: String) -> AppResult<Authenticated> {
+    match authenticate(jwt).await {
+        AuthenticationStatus::Authenticated(authenticated) => Ok(authenticated),
+        AuthenticationStatus::Unauthenticated => Err(AppError::Unauthenticated),
+    }
+}
+
+/// Sensitive account changes need the short lived one-time JWT.
+pub async fn require_one_time_auth(jwt: String) -> AppResult<Authenticated> {
+    let authenticated = require_auth(jwt).await?;
+    if authenticated.is_one_time_jwt {
+        Ok(authenticated)
+    } else {
+        Err(AppError::Unauthenticated)
+    }
+}
@@ -0,0 +1,165 @@
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, ConnectionTrait, DbBackend, DbErr,
+    Statement,
+};
+
+use crate::schemas::ratings::adjust_ratings_counter;
+
+/// Turns `buzz` into a tombstone: its content, ratings, hashtags, mentions,
+/// revisions, poll, rebuzzes, bookmarks and notifications go away while the
+/// row stays, so replies below it keep their thread. Run it inside a
+/// transaction.
+pub async fn tombstone_buzz<C: ConnectionTrait>(
+    connection: &C,
+    buzz: entity::buzz::Model,
+) -> Result<(), DbErr> {
+    if let Some(quoted_buzz_id) = buzz.quoted_buzz_id {
+        if let Some(quoted_buzz) = entity::buzz::Entity::find_by_id(quoted_buzz_id)
+            .one(connection)
+            .await?
+        {
+            adjust_ratings_counter(
+                connection,
+                quoted_buzz.ratings_id,
+                entity::ratings::Column::Quotes,
+                -1,
+            )
+            .await?;
+        }
+    }
+
+    entity::buzz_hashtags::Entity::delete_many()
+        .filter(entity::buzz_hashtags::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    entity::mentions::Entity::delete_many()
+        .filter(entity::mentions::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    entity::buzz_revisions::Entity::delete_many()
+        .filter(entity::buzz_revisions::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    // Options and votes go with the poll.
+    entity::polls::Entity::delete_many()
+        .filter(entity::polls::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    entity::rebuzz::Entity::delete_many()
+        .filter(entity::rebuzz::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    entity::bookmarks::Entity::delete_many()
+        .filter(entity::bookmarks::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+    entity::notifications::Entity::delete_many()
+        .filter(entity::notifications::Column::BuzzId.eq(buzz.id))
+        .exec(connection)
+        .await?;
+
+    let ratings_id = buzz.ratings_id;
+
+    let mut buzz_table: entity::buzz::ActiveModel = buzz.into();
+    buzz_table.description = Set(String::new());
+    buzz_table.image_link = Set(None);
+    buzz_table.video_link = Set(None);
+    buzz_table.buzz_words = Set(None);
+    buzz_table.mentioned_users = Set(None);
+    buzz_table.ratings_id = Set(None);
+    buzz_table.deleted_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+    buzz_table.update(connection).await?;
+
+    // Upvotes and views go with the ratings row.
+    if let Some(ratings_id) = ratings_id {
+        entity::ratings::Entity::delete_by_id(ratings_id)
+            .exec(connection)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Turns `reply` into a tombstone: its content, ratings, hashtags, mentions,
+/// revisions and notifications go away while the row stays, so replies below
+/// it keep their thread. Run it inside a transaction.
+pub async fn tombstone_reply<C: ConnectionTrait>(
+    connection: &C,
+    reply: entity::reply::Model,
+) -> Result<(), DbErr> {
+    entity::buzz_hashtags::Entity::delete_many()
+        .filter(entity::buzz_hashtags::Column::ReplyId.eq(reply.id))
+        .exec(connection)
+        .await?;
+    entity::mentions::Entity::delete_many()
+        .filter(entity::mentions::Column::ReplyId.eq(reply.id))
+        .exec(connection)
+        .await?;
+    entity::buzz_revisions::Entity::delete_many()
+        .filter(entity::buzz_revisions::Column::ReplyId.eq(reply.id))
+        .exec(connection)
+        .await?;
+    entity::notifications::Entity::delete_many()
+        .filter(entity::notifications::Column::ReplyId.eq(reply.id))
+        .exec(connection)
+        .await?;
+
+    let ratings_id = reply.ratings_id;
+
+    let mut reply_table: entity::reply::ActiveModel = reply.into();
+    reply_table.reply_content = Set(String::new());
+    reply_table.buzz_words = Set(None);
+    reply_table.mentioned_users = Set(None);
+    reply_table.ratings_id = Set(None);
+    reply_table.deleted_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+    reply_table.update(connection).await?;
+
+    // Upvotes and views go with the ratings row.
+    if let Some(ratings_id) = ratings_id {
+        entity::ratings::Entity::delete_by_id(ratings_id)
+            .exec(connection)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Removes tombstones deleted before `deleted_before`. A tombstone stays as
+/// long as replies hang below it, reply tombstones go leaf first so whole
+/// deleted branches are removed in one go. Returns how many were removed.
+pub async fn purge_tombstones<C: ConnectionTrait>(
+    connection: &C,
+    deleted_before: DateTimeWithTimeZone,
+) -> Result<u64, DbErr> {
+    let mut purged = 0;
+
+    loop {
+        let result = connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM reply
+                WHERE reply.deleted_at < $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM reply AS child WHERE child.parent_reply_id = reply.id
+                )"#,
+                vec![deleted_before.into()],
+            ))
+            .await?;
+        if result.rows_affected() == 0 {
+            break;
+        }
+        purged += result.rows_affected();
+    }
+
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"DELETE FROM buzz
+            WHERE buzz.deleted_at < $1
+            AND NOT EXISTS (SELECT 1 FROM reply WHERE reply.buzz_id = buzz.id)"#,
+            vec![deleted_before.into()],
+        ))
+        .await?;
+
+    Ok(purged + result.rows_affected())
+}
@@ -0,0 +1,67 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbBackend, DbErr, Statement};
+
+use crate::schemas::ratings::adjust_ratings_counter;
+
+/// Upvotes `ratings_id` for `user_id`, or takes the upvote back when there is
+/// one. Returns whether the user upvotes it now. `ratings.upvotes` only moves
+/// when a row was really added or removed, so concurrent toggles cannot make
+/// it drift; run it inside a transaction to keep both in step.
+pub async fn toggle_upvote<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+    ratings_id: i64,
+) -> Result<bool, DbErr> {
+    let removed = entity::upvotes::Entity::delete_many()
+        .filter(entity::upvotes::Column::UserId.eq(user_id))
+        .filter(entity::upvotes::Column::RatingsId.eq(ratings_id))
+        .exec(connection)
+        .await?
+        .rows_affected;
+    if removed > 0 {
+        adjust_ratings_counter(
+            connection,
+            Some(ratings_id),
+            entity::ratings::Column::Upvotes,
+            -(removed as i64),
+        )
+        .await?;
+        return Ok(false);
+    }
+
+    let added = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO upvotes (user_id, ratings_id, created_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (user_id, ratings_id) DO NOTHING"#,
+            vec![user_id.into(), ratings_id.into()],
+        ))
+        .await?
+        .rows_affected();
+    if added > 0 {
+        adjust_ratings_counter(
+            connection,
+            Some(ratings_id),
+            entity::ratings::Column::Upvotes,
+            added as i64,
+        )
+        .await?;
+    }
+
+    Ok(true)
+}
+
+/// Ratings `user_id` upvoted, oldest upvote first.
+pub async fn upvoted_ratings_ids<C: ConnectionTrait>(
+    connection: &C,
+    user_id: i64,
+) -> Result<Vec<i64>, DbErr> {
+    Ok(entity::upvotes::Entity::find()
+        .filter(entity::upvotes::Column::UserId.eq(user_id))
+        .order_by(entity::upvotes::Column::Id, Order::Asc)
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|upvote| upvote.ratings_id)
+        .collect())
+}
@@ -0,0 +1,49 @@
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement};
+
+use crate::lib::common::env_or;
+
+/// Start of the `VIEW_WINDOW_MINUTES` long window `now` falls in. A viewer
+/// seeing the same buzz or reply again within a window is one view.
+pub fn window_start(now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::FixedOffset> {
+    let window = env_or::<i64>("VIEW_WINDOW_MINUTES", 60).max(1) * 60;
+    let start = now.timestamp() - now.timestamp().rem_euclid(window);
+    chrono::DateTime::from(chrono::DateTime::<chrono::Utc>::from_utc(
+        chrono::NaiveDateTime::from_timestamp(start, 0),
+        chrono::Utc,
+    ))
+}
+
+/// Stores a view of each of `ratings_ids` by `viewer_key` and returns how
+/// many were new in the current window. Unknown ids are skipped, the
+/// counters are only bumped later by the views job.
+pub async fn record_views<C: ConnectionTrait>(
+    connection: &C,
+    viewer_key: &str,
+    ratings_ids: &[i64],
+) -> Result<u64, DbErr> {
+    if ratings_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let placeholders = (3..ratings_ids.len() + 3)
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut values = vec![viewer_key.into(), window_start(chrono::Utc::now()).into()];
+    values.extend(ratings_ids.iter().map(|id| (*id).into()));
+
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                r#"INSERT INTO view_events (ratings_id, viewer_key, window_start, counted)
+                SELECT ratings.id, $1, $2, FALSE FROM ratings WHERE ratings.id IN ({})
+                ON CONFLICT (ratings_id, viewer_key, window_start) DO NOTHING"#,
+                placeholders
+            ),
+            values,
+        ))
+        .await?;
+
+    Ok(result.rows_affected())
+}
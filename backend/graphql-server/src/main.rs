@@ -1,29 +1,53 @@
+#![allow(special_module_name)]
+
 #[macro_use]
 extern crate juniper;
 
 use actix_web::{
-    http::Error, middleware::Logger, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+    http::{header, Error},
+    middleware::Logger,
+    web, App, HttpRequest, HttpResponse, HttpServer, Responder,
 };
 use actix_web_lab::respond::Html;
-use juniper::http::{graphiql::graphiql_source, GraphQLRequest};
+use juniper::{
+    http::{graphiql::graphiql_source, GraphQLRequest, GraphQLResponse},
+    InputValue,
+};
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
 
+use lib::{
+    client_ip::TrustedProxies,
+    persisted_queries::{PersistedQueries, RequestExtensions},
+    query_limits::QueryLimits,
+};
 use migration::{Migrator, MigratorTrait};
 use schemas::root::Context;
 // use entity::*;
 
 // use sea_orm::{entity::*, query::*, DatabaseConnection};
-// use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
+mod jobs;
 mod lib;
 mod schemas;
 
+#[derive(Deserialize)]
+struct GraphQLPayload {
+    query: Option<String>,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<InputValue>,
+    extensions: Option<RequestExtensions>,
+}
+
 async fn index(_req: HttpRequest) -> impl Responder {
     HttpResponse::Ok().body("Hello world!")
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
     let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
     builder
         .set_private_key_file("192.168.0.108+3-key.pem", SslFiletype::PEM)
@@ -42,14 +66,21 @@ async fn main() -> std::io::Result<()> {
     let connection = sea_orm::Database::connect(&db_url).await.unwrap();
 
     Migrator::up(&connection, None).await.unwrap();
-    let state = Context { connection };
+    jobs::spawn_all(&connection);
+    let state = Context::new(connection);
     schemas::root::export_schema(&state);
+    let query_limits = QueryLimits::from_env();
+    let persisted_queries = web::Data::new(PersistedQueries::from_env());
+    let trusted_proxies = TrustedProxies::from_env();
 
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .app_data(web::Data::new(state.clone()))
             .app_data(web::Data::new(schemas::root::create_schema()))
+            .app_data(web::Data::new(query_limits.clone()))
+            .app_data(persisted_queries.clone())
+            .app_data(web::Data::new(trusted_proxies.clone()))
             .service(web::resource("/").name("home").route(web::get().to(index)))
             .service(
                 web::resource("/graphql")
@@ -57,6 +88,7 @@ async fn main() -> std::io::Result<()> {
                     .route(web::get().to(graphql)),
             )
             .service(web::resource("/graphiql").route(web::get().to(graphql_playground)))
+            .service(web::resource("/exports/{token}").route(web::get().to(download_data_export)))
     })
         .bind_openssl(host_address, builder)?
         .run()
@@ -64,19 +96,74 @@ async fn main() -> std::io::Result<()> {
 }
 
 async fn graphql(
+    req: HttpRequest,
     pool: web::Data<Context>,
     schema: web::Data<schemas::root::Schema>,
-    data: web::Json<GraphQLRequest>,
+    query_limits: web::Data<QueryLimits>,
+    persisted_queries: web::Data<PersistedQueries>,
+    trusted_proxies: web::Data<TrustedProxies>,
+    data: web::Json<GraphQLPayload>,
 ) -> Result<HttpResponse, Error> {
-    let ctx = Context {
-        connection: pool.connection.to_owned(),
+    let client_ip = trusted_proxies.client_ip(&req);
+    let ctx = Context::with_client_ip(pool.connection.to_owned(), client_ip);
+    let data = data.into_inner();
+
+    let query = match persisted_queries.resolve(data.query, data.extensions.as_ref()) {
+        Ok(query) => query,
+        Err(e) => return Ok(HttpResponse::Ok().json(GraphQLResponse::error(e))),
     };
 
-    let res = data.execute(&schema, &ctx).await;
+    match query_limits.check(
+        &schema,
+        &query,
+        data.operation_name.as_deref(),
+        data.variables.as_ref(),
+    ) {
+        Ok(cost) => log::info!(
+            "graphql operation {:?}: cost {}, depth {}, aliases {}",
+            data.operation_name,
+            cost.cost,
+            cost.depth,
+            cost.aliases
+        ),
+        Err(e) => {
+            log::warn!(
+                "graphql operation {:?} rejected: {}",
+                data.operation_name,
+                e.message()
+            );
+            return Ok(HttpResponse::Ok().json(GraphQLResponse::error(e)));
+        }
+    }
+
+    let request = GraphQLRequest::new(query, data.operation_name, data.variables);
+    let res = request.execute(&schema, &ctx).await;
 
     Ok(HttpResponse::Ok().json(res))
 }
 
+/// Serves the archive of a data export, the token in the link is all it
+/// takes.
+async fn download_data_export(pool: web::Data<Context>, token: web::Path<String>) -> HttpResponse {
+    match lib::exports::downloadable_export(&pool.connection, &token).await {
+        Ok(Some(data_export)) => HttpResponse::Ok()
+            .content_type("application/gzip")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"buzz-export-{}.tar.gz\"",
+                    data_export.id
+                ),
+            ))
+            .body(data_export.archive.unwrap_or_default()),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            log::error!("serving data export failed: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 async fn graphql_playground() -> impl Responder {
     Html(graphiql_source("/graphql", None))
 }
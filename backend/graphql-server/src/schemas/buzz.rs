@@ -1,13 +1,49 @@
+use juniper::ID;
 use sea_orm::prelude::DateTimeWithTimeZone;
 
+use crate::lib::{
+    common::Tagged,
+    errors::{check_page, AppResult},
+    loaders::{BookmarkKey, ReplyCountKey, ReplyPageKey, RevisionOwner},
+};
+use crate::schemas::{
+    polls::{PollDetails, PollInput},
+    ratings::{load_ratings_details, RatingsDetails},
+    reply::{AllReplyResult, ReplyResult},
+    revisions::{load_revisions, RevisionDetails},
+    root::Context,
+    users::{load_mentioned_users, load_user_details, UserDetails},
+};
+
+/// What a deleted buzz says instead of its description.
+pub const DELETED_BUZZ_DESCRIPTION: &str = "This buzz was deleted";
+
+/// What a buzz hidden by a moderator says instead of its description.
+pub const HIDDEN_BUZZ_DESCRIPTION: &str = "This buzz was hidden by a moderator";
+
 #[derive(GraphQLInputObject)]
 #[graphql(description = "Create the buzz")]
 pub struct BuzzInput {
-    pub user_id: String,
+    pub user_id: ID,
+    pub description: String,
+    pub image_link: Option<String>,
+    pub video_link: Option<String>,
+    pub buzz_words: Option<String>,
+    #[graphql(description = "ignored, mentions are read from the text")]
+    pub mentioned_users: Option<String>,
+    #[graphql(description = "the buzz this one quotes")]
+    pub quoted_buzz_id: Option<ID>,
+    pub poll: Option<PollInput>,
+}
+
+#[derive(GraphQLInputObject, Clone)]
+#[graphql(description = "Edit the buzz")]
+pub struct BuzzUpdateInput {
     pub description: String,
     pub image_link: Option<String>,
     pub video_link: Option<String>,
     pub buzz_words: Option<String>,
+    #[graphql(description = "ignored, mentions are read from the text")]
     pub mentioned_users: Option<String>,
 }
 
@@ -16,23 +52,241 @@ pub struct BuzzInput {
 pub struct GetAllBuzzInput {
     pub page_size: i32,
     pub page_number: i32,
-    pub user_id: Option<String>,
+    pub user_id: Option<ID>,
 }
 
-#[derive(GraphQLObject)]
 pub struct BuzzResult {
-    pub id: String,
-    pub user_id: String,
+    pub id: i64,
+    pub user_id: i64,
     pub description: String,
     pub image_link: Option<String>,
     pub video_link: Option<String>,
     pub buzz_words: Option<String>,
     pub mentioned_users: Option<String>,
-    pub ratings_id: Option<String>,
+    pub ratings_id: Option<i64>,
+    pub quoted_buzz_id: Option<i64>,
     pub created_at: DateTimeWithTimeZone,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub hidden_at: Option<DateTimeWithTimeZone>,
+    /// Set when the buzz shows up in a timeline because it was rebuzzed.
+    pub rebuzzed_by: Option<i64>,
+    pub rebuzzed_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<entity::buzz::Model> for BuzzResult {
+    fn from(buzz: entity::buzz::Model) -> Self {
+        BuzzResult {
+            id: buzz.id,
+            user_id: buzz.user_id,
+            description: buzz.description,
+            image_link: buzz.image_link,
+            video_link: buzz.video_link,
+            buzz_words: buzz.buzz_words,
+            mentioned_users: buzz.mentioned_users,
+            ratings_id: buzz.ratings_id,
+            quoted_buzz_id: buzz.quoted_buzz_id,
+            created_at: buzz.created_at,
+            edited_at: buzz.edited_at,
+            deleted_at: buzz.deleted_at,
+            hidden_at: buzz.hidden_at,
+            rebuzzed_by: None,
+            rebuzzed_at: None,
+        }
+    }
+}
+
+impl BuzzResult {
+    /// `field` unless a moderator hid the buzz.
+    fn visible<'a>(&self, field: &'a Option<String>) -> Option<&'a String> {
+        match self.hidden_at {
+            Some(_) => None,
+            None => field.as_ref(),
+        }
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl BuzzResult {
+    fn id(&self) -> ID {
+        ID::from(self.id.to_string())
+    }
+
+    fn user_id(&self) -> ID {
+        ID::from(self.user_id.to_string())
+    }
+
+    fn description(&self) -> &str {
+        if self.deleted_at.is_some() {
+            DELETED_BUZZ_DESCRIPTION
+        } else if self.hidden_at.is_some() {
+            HIDDEN_BUZZ_DESCRIPTION
+        } else {
+            &self.description
+        }
+    }
+
+    fn image_link(&self) -> Option<&String> {
+        self.visible(&self.image_link)
+    }
+
+    fn video_link(&self) -> Option<&String> {
+        self.visible(&self.video_link)
+    }
+
+    fn buzz_words(&self) -> Option<&String> {
+        self.visible(&self.buzz_words)
+    }
+
+    fn mentioned_users(&self) -> Option<&String> {
+        self.visible(&self.mentioned_users)
+    }
+
+    async fn mentions(&self, context: &Context) -> AppResult<Vec<UserDetails>> {
+        if self.hidden_at.is_some() {
+            return Ok(Vec::new());
+        }
+        load_mentioned_users(context, Tagged::Buzz(self.id)).await
+    }
+
+    fn ratings_id(&self) -> Option<ID> {
+        self.ratings_id
+            .map(|ratings_id| ID::from(ratings_id.to_string()))
+    }
+
+    fn quoted_buzz_id(&self) -> Option<ID> {
+        self.quoted_buzz_id
+            .map(|quoted_buzz_id| ID::from(quoted_buzz_id.to_string()))
+    }
+
+    async fn quoted_buzz(&self, context: &Context) -> AppResult<Option<BuzzResult>> {
+        match self.quoted_buzz_id {
+            Some(quoted_buzz_id) => {
+                let blocked = context.blocked_user_ids().await?;
+                let quoted_buzz = match context.loaders.buzzes.load(quoted_buzz_id).await? {
+                    Some(quoted_buzz) if !blocked.contains(&quoted_buzz.user_id) => quoted_buzz,
+                    _ => return Ok(None),
+                };
+                if context.is_locked(quoted_buzz.user_id).await? {
+                    return Ok(None);
+                }
+                Ok(Some(BuzzResult::from(quoted_buzz)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn rebuzzed_by(&self, context: &Context) -> AppResult<Option<UserDetails>> {
+        match self.rebuzzed_by {
+            Some(user_id) => Ok(Some(load_user_details(context, user_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    fn rebuzzed_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.rebuzzed_at
+    }
+
+    #[graphql(description = "bookmarked by the user of the jwt argument")]
+    async fn is_bookmarked(&self, context: &Context) -> AppResult<bool> {
+        match context.viewer_id() {
+            Some(user_id) => Ok(context
+                .loaders
+                .bookmarked
+                .load(BookmarkKey {
+                    user_id,
+                    buzz_id: self.id,
+                })
+                .await?),
+            None => Ok(false),
+        }
+    }
+
+    fn created_at(&self) -> &DateTimeWithTimeZone {
+        &self.created_at
+    }
+
+    fn edited_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.edited_at
+    }
+
+    #[graphql(description = "set once the author deleted the buzz, its replies stay readable")]
+    fn deleted_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.deleted_at
+    }
+
+    #[graphql(description = "set while a moderator keeps the buzz hidden")]
+    fn hidden_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.hidden_at
+    }
+
+    #[graphql(description = "previous versions, newest first")]
+    async fn revisions(&self, context: &Context) -> AppResult<Vec<RevisionDetails>> {
+        if self.hidden_at.is_some() {
+            return Ok(Vec::new());
+        }
+        load_revisions(context, RevisionOwner::Buzz(self.id)).await
+    }
+
+    async fn author(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.user_id).await
+    }
+
+    async fn poll(&self, context: &Context) -> AppResult<Option<PollDetails>> {
+        if self.deleted_at.is_some() || self.hidden_at.is_some() {
+            return Ok(None);
+        }
+        Ok(context
+            .loaders
+            .polls
+            .load(self.id)
+            .await?
+            .map(|poll| PollDetails::new(poll, self.user_id, context.viewer_id())))
+    }
+
+    async fn ratings(&self, context: &Context) -> AppResult<Option<RatingsDetails>> {
+        load_ratings_details(context, self.ratings_id).await
+    }
+
+    async fn replies(
+        &self,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<AllReplyResult> {
+        check_page(page_size, page_number)?;
+
+        let total_replies = context
+            .loaders
+            .reply_counts
+            .load(ReplyCountKey {
+                buzz_id: self.id,
+                viewer_id: context.viewer_id(),
+            })
+            .await?;
+        let replies = context
+            .loaders
+            .replies
+            .load(ReplyPageKey {
+                buzz_id: self.id,
+                page_size: page_size as i64,
+                page_number: page_number as i64,
+                viewer_id: context.viewer_id(),
+            })
+            .await?;
+
+        Ok(AllReplyResult {
+            replies: replies.into_iter().map(ReplyResult::from).collect(),
+            total_replies: total_replies as i32,
+            total_pages: ((total_replies + page_size as i64 - 1) / page_size as i64) as i32,
+            page_number,
+            page_size,
+        })
+    }
 }
 
 #[derive(GraphQLObject)]
+#[graphql(Context = Context)]
 pub struct AllBuzzResult {
     pub buzzes: Vec<BuzzResult>,
     pub total_buzzes: i32,
@@ -40,3 +294,15 @@ pub struct AllBuzzResult {
     pub page_number: i32,
     pub page_size: i32,
 }
+
+#[derive(GraphQLObject)]
+pub struct RebuzzResponse {
+    pub id: ID,
+    pub is_rebuzzed: bool,
+}
+
+#[derive(GraphQLObject)]
+pub struct BookmarkResponse {
+    pub id: ID,
+    pub is_bookmarked: bool,
+}
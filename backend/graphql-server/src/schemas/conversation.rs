@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+
+use juniper::ID;
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbBackend, FromQueryResult, Statement};
+
+use crate::lib::{
+    common::env_or,
+    errors::{check_page, parse_id, AppError, AppResult},
+    privacy::{is_locked, not_locked, not_locked_sql},
+};
+use crate::schemas::{buzz::BuzzResult, reply::ReplyResult, root::Context};
+
+#[derive(GraphQLInputObject)]
+#[graphql(description = "Get the conversation below a buzz or a reply")]
+pub struct GetConversationInput {
+    pub buzz_id: Option<ID>,
+    pub reply_id: Option<ID>,
+    pub page_size: i32,
+    pub page_number: i32,
+    #[graphql(description = "levels of replies to load, 1 loads only the direct replies")]
+    pub depth: Option<i32>,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct ReplyThread {
+    pub reply: ReplyResult,
+    pub children: Vec<ReplyThread>,
+    pub total_children: i32,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct ConversationResult {
+    pub buzz: BuzzResult,
+    pub reply: Option<ReplyResult>,
+    #[graphql(description = "replies above `reply`, the top level reply first")]
+    pub ancestors: Vec<ReplyResult>,
+    pub replies: Vec<ReplyThread>,
+    pub total_replies: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+#[derive(FromQueryResult)]
+struct ChildCount {
+    parent_reply_id: i64,
+    count: i64,
+}
+
+/// Direct replies are paginated, below them every reply shows its oldest
+/// `REPLY_THREAD_CHILD_LIMIT` replies down to `depth` levels (at most
+/// `REPLY_THREAD_MAX_DEPTH`). `total_children` tells clients where to ask
+/// for the conversation of a reply to see the rest. What `blocked` users wrote
+/// is not found. Replies of `hidden` users and of private accounts
+/// `viewer_id` may not see are left out together with what was said below
+/// them, before pages are cut and replies counted.
+pub async fn load_conversation(
+    connection: &DatabaseConnection,
+    input: GetConversationInput,
+    blocked: &[i64],
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+) -> AppResult<ConversationResult> {
+    check_page(input.page_size, input.page_number)?;
+
+    let max_depth: i32 = env_or("REPLY_THREAD_MAX_DEPTH", 3);
+    let depth = input.depth.unwrap_or(max_depth);
+    if depth < 1 || depth > max_depth {
+        return Err(AppError::BadRequest(format!(
+            "depth must be between 1 and {}",
+            max_depth
+        )));
+    }
+
+    let (buzz_id, reply, ancestors) = match (&input.buzz_id, &input.reply_id) {
+        (Some(buzz_id), None) => (parse_id(buzz_id)?, None, Vec::new()),
+        (None, Some(reply_id)) => {
+            let reply = entity::reply::Entity::find_by_id(parse_id(reply_id)?)
+                .one(connection)
+                .await?
+                .filter(|reply| !blocked.contains(&reply.user_id))
+                .ok_or(AppError::NotFound("Reply"))?;
+            if is_locked(connection, reply.user_id, viewer_id).await? {
+                return Err(AppError::Forbidden("Account is private"));
+            }
+            let ancestors = load_ancestors(connection, &reply, hidden, viewer_id).await?;
+            (reply.buzz_id, Some(reply), ancestors)
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Exactly one of buzz_id and reply_id is required".to_string(),
+            ))
+        }
+    };
+
+    let buzz = entity::buzz::Entity::find_by_id(buzz_id)
+        .one(connection)
+        .await?
+        .filter(|buzz| !blocked.contains(&buzz.user_id))
+        .ok_or(AppError::NotFound("Buzz"))?;
+    if is_locked(connection, buzz.user_id, viewer_id).await? {
+        return Err(AppError::Forbidden("Account is private"));
+    }
+
+    let direct_replies = match &reply {
+        Some(reply) => {
+            entity::reply::Entity::find().filter(entity::reply::Column::ParentReplyId.eq(reply.id))
+        }
+        None => entity::reply::Entity::find()
+            .filter(entity::reply::Column::BuzzId.eq(buzz_id))
+            .filter(entity::reply::Column::ParentReplyId.is_null()),
+    }
+    .filter(entity::reply::Column::UserId.is_not_in(hidden.to_vec()))
+    .filter(not_locked("reply.user_id", viewer_id))
+    .order_by(entity::reply::Column::CreatedAt, Order::Asc)
+    .order_by(entity::reply::Column::Id, Order::Asc)
+    .paginate(connection, input.page_size as usize);
+
+    let total_pages = direct_replies.num_pages().await? as i32;
+    let total_replies = direct_replies.num_items().await? as i32;
+    let top = direct_replies
+        .fetch_page((input.page_number - 1) as usize)
+        .await?;
+
+    let child_limit: i64 = env_or("REPLY_THREAD_CHILD_LIMIT", 3);
+    let mut children: HashMap<i64, Vec<entity::reply::Model>> = HashMap::new();
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+
+    // One level per round trip, `depth` is small.
+    let mut level: Vec<i64> = top.iter().map(|reply| reply.id).collect();
+    for current in 1..=depth {
+        if level.is_empty() {
+            break;
+        }
+        counts.extend(count_children(connection, &level, hidden, viewer_id).await?);
+        if current == depth {
+            break;
+        }
+
+        let replies = load_children(connection, &level, child_limit, hidden, viewer_id).await?;
+        level = replies.iter().map(|reply| reply.id).collect();
+        for reply in replies {
+            if let Some(parent_reply_id) = reply.parent_reply_id {
+                children.entry(parent_reply_id).or_default().push(reply);
+            }
+        }
+    }
+
+    Ok(ConversationResult {
+        buzz: BuzzResult::from(buzz),
+        reply: reply.map(ReplyResult::from),
+        ancestors: ancestors.into_iter().map(ReplyResult::from).collect(),
+        replies: top
+            .into_iter()
+            .map(|reply| build_thread(reply, &mut children, &counts))
+            .collect(),
+        total_replies,
+        total_pages,
+        page_number: input.page_number,
+        page_size: input.page_size,
+    })
+}
+
+fn build_thread(
+    reply: entity::reply::Model,
+    children: &mut HashMap<i64, Vec<entity::reply::Model>>,
+    counts: &HashMap<i64, i64>,
+) -> ReplyThread {
+    let total_children = *counts.get(&reply.id).unwrap_or(&0) as i32;
+    let replies = children.remove(&reply.id).unwrap_or_default();
+
+    ReplyThread {
+        children: replies
+            .into_iter()
+            .map(|reply| build_thread(reply, children, counts))
+            .collect(),
+        reply: ReplyResult::from(reply),
+        total_children,
+    }
+}
+
+/// Replies above `reply` `viewer_id` may see, the top level reply first.
+async fn load_ancestors(
+    connection: &DatabaseConnection,
+    reply: &entity::reply::Model,
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+) -> AppResult<Vec<entity::reply::Model>> {
+    let parent_reply_id = match reply.parent_reply_id {
+        Some(parent_reply_id) => parent_reply_id,
+        None => return Ok(Vec::new()),
+    };
+
+    let sql = format!(
+        r#"WITH RECURSIVE ancestors AS (
+            SELECT reply.*, 1 AS distance FROM reply WHERE reply.id = $1
+            UNION ALL
+            SELECT reply.*, ancestors.distance + 1 FROM reply
+            JOIN ancestors ON reply.id = ancestors.parent_reply_id
+        )
+        SELECT * FROM ancestors WHERE {}
+        ORDER BY distance DESC"#,
+        visible_sql("ancestors.user_id", 2, hidden.len())
+    );
+
+    let ancestors = entity::reply::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &sql,
+            [parent_reply_id.into(), viewer_id.into()]
+                .into_iter()
+                .chain(hidden.iter().map(|id| (*id).into())),
+        ))
+        .all(connection)
+        .await?;
+
+    Ok(ancestors)
+}
+
+/// The oldest `limit` replies of each of `parent_ids` `viewer_id` may see.
+async fn load_children(
+    connection: &DatabaseConnection,
+    parent_ids: &[i64],
+    limit: i64,
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+) -> AppResult<Vec<entity::reply::Model>> {
+    let placeholders = (1..=parent_ids.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let sql = format!(
+        r#"SELECT * FROM (
+            SELECT reply.*, ROW_NUMBER() OVER (
+                PARTITION BY reply.parent_reply_id ORDER BY reply.created_at, reply.id
+            ) AS row_number
+            FROM reply WHERE reply.parent_reply_id IN ({}) AND {}
+        ) AS ranked
+        WHERE ranked.row_number <= {}
+        ORDER BY ranked.parent_reply_id, ranked.row_number"#,
+        placeholders,
+        visible_sql("reply.user_id", parent_ids.len() + 1, hidden.len()),
+        limit
+    );
+
+    let replies = entity::reply::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &sql,
+            parent_ids
+                .iter()
+                .map(|id| (*id).into())
+                .chain([viewer_id.into()])
+                .chain(hidden.iter().map(|id| (*id).into())),
+        ))
+        .all(connection)
+        .await?;
+
+    Ok(replies)
+}
+
+async fn count_children(
+    connection: &DatabaseConnection,
+    parent_ids: &[i64],
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+) -> AppResult<HashMap<i64, i64>> {
+    let counts = entity::reply::Entity::find()
+        .select_only()
+        .column(entity::reply::Column::ParentReplyId)
+        .column_as(entity::reply::Column::Id.count(), "count")
+        .filter(entity::reply::Column::ParentReplyId.is_in(parent_ids.to_vec()))
+        .filter(entity::reply::Column::UserId.is_not_in(hidden.to_vec()))
+        .filter(not_locked("reply.user_id", viewer_id))
+        .group_by(entity::reply::Column::ParentReplyId)
+        .into_model::<ChildCount>()
+        .all(connection)
+        .await?;
+
+    Ok(counts
+        .into_iter()
+        .map(|count| (count.parent_reply_id, count.count))
+        .collect())
+}
+
+/// Keeps replies of `author_column` the viewer bound to `$viewer` may see and
+/// that are not by the `hidden` users bound to the placeholders after it.
+fn visible_sql(author_column: &str, viewer: usize, hidden: usize) -> String {
+    let mut visible = not_locked_sql(author_column, &format!("${}", viewer));
+    if hidden > 0 {
+        visible.push_str(&format!(
+            " AND {} NOT IN ({})",
+            author_column,
+            (viewer + 1..=viewer + hidden)
+                .map(|i| format!("${}", i))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_sql_numbers_hidden_users_after_the_viewer() {
+        let sql = visible_sql("reply.user_id", 3, 2);
+        assert!(sql.starts_with(&not_locked_sql("reply.user_id", "$3")));
+        assert!(sql.ends_with(" AND reply.user_id NOT IN ($4, $5)"));
+    }
+
+    #[test]
+    fn visible_sql_without_hidden_users() {
+        assert_eq!(
+            visible_sql("reply.user_id", 1, 0),
+            not_locked_sql("reply.user_id", "$1")
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use juniper::ID;
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, sea_query::Expr, DatabaseConnection,
+};
+
+use crate::lib::{common::env_or, errors::AppResult, exports::DataExportStatus};
+use crate::schemas::root::Context;
+
+pub struct DataExportDetails {
+    data_export: entity::data_exports::Model,
+}
+
+impl From<entity::data_exports::Model> for DataExportDetails {
+    fn from(data_export: entity::data_exports::Model) -> Self {
+        DataExportDetails { data_export }
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl DataExportDetails {
+    fn id(&self) -> ID {
+        ID::from(self.data_export.id.to_string())
+    }
+
+    fn status(&self) -> DataExportStatus {
+        DataExportStatus::from_key(&self.data_export.status).unwrap_or(DataExportStatus::Failed)
+    }
+
+    fn requested_at(&self) -> DateTimeWithTimeZone {
+        self.data_export.requested_at
+    }
+
+    fn completed_at(&self) -> Option<DateTimeWithTimeZone> {
+        self.data_export.completed_at
+    }
+
+    #[graphql(description = "when the download link stops working")]
+    fn expires_at(&self) -> Option<DateTimeWithTimeZone> {
+        self.data_export.expires_at
+    }
+
+    #[graphql(description = "link to the archive, only while it is ready")]
+    fn download_url(&self) -> Option<String> {
+        if self.data_export.status != DataExportStatus::Ready.key() {
+            return None;
+        }
+        Some(format!(
+            "{}/{}",
+            env_or("DATA_EXPORT_URL", "/exports".to_string()),
+            self.data_export.token
+        ))
+    }
+}
+
+/// Exports `user_id` asked for, newest first. The archives themselves are
+/// left out, they are only served by the download link.
+pub async fn load_data_exports(
+    connection: &DatabaseConnection,
+    user_id: i64,
+) -> AppResult<Vec<DataExportDetails>> {
+    let data_exports = entity::data_exports::Entity::find()
+        .select_only()
+        .column(entity::data_exports::Column::Id)
+        .column(entity::data_exports::Column::UserId)
+        .column(entity::data_exports::Column::Token)
+        .column(entity::data_exports::Column::Status)
+        .column_as(Expr::cust("NULL::BYTEA"), "archive")
+        .column(entity::data_exports::Column::RequestedAt)
+        .column(entity::data_exports::Column::CompletedAt)
+        .column(entity::data_exports::Column::ExpiresAt)
+        .filter(entity::data_exports::Column::UserId.eq(user_id))
+        .order_by(entity::data_exports::Column::Id, Order::Desc)
+        .all(connection)
+        .await?;
+
+    Ok(data_exports
+        .into_iter()
+        .map(DataExportDetails::from)
+        .collect())
+}
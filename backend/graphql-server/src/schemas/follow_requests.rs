@@ -0,0 +1,83 @@
+use juniper::ID;
+use sea_orm::{entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection};
+
+use crate::lib::errors::{check_page, AppResult};
+use crate::schemas::{
+    root::Context,
+    users::{load_user_details, UserDetails},
+};
+
+pub struct FollowRequestDetails {
+    follow_request: entity::follow_requests::Model,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl FollowRequestDetails {
+    fn id(&self) -> ID {
+        ID::from(self.follow_request.id.to_string())
+    }
+
+    async fn requester(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.follow_request.requester_id).await
+    }
+
+    async fn target(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.follow_request.target_id).await
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.follow_request.created_at
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllFollowRequestResult {
+    pub follow_requests: Vec<FollowRequestDetails>,
+    pub total_follow_requests: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+/// Pending follow requests sent to `user_id`, or sent by them when
+/// `incoming` is false, newest first.
+pub async fn load_follow_requests(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    incoming: bool,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllFollowRequestResult> {
+    check_page(page_size, page_number)?;
+
+    let column = if incoming {
+        entity::follow_requests::Column::TargetId
+    } else {
+        entity::follow_requests::Column::RequesterId
+    };
+
+    let paginated_requests = entity::follow_requests::Entity::find()
+        .filter(column.eq(user_id))
+        .order_by(entity::follow_requests::Column::CreatedAt, Order::Desc)
+        .order_by(entity::follow_requests::Column::Id, Order::Desc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_requests.num_pages().await? as i32;
+    let total_follow_requests = paginated_requests.num_items().await? as i32;
+
+    let follow_requests = paginated_requests
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    Ok(AllFollowRequestResult {
+        follow_requests: follow_requests
+            .into_iter()
+            .map(|follow_request| FollowRequestDetails { follow_request })
+            .collect(),
+        total_follow_requests,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use juniper::ID;
+use sea_orm::{entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection};
+
+use crate::lib::errors::{check_page, AppResult};
+use crate::schemas::{buzz::BuzzResult, reply::ReplyResult, root::Context};
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct MentionDetails {
+    pub id: ID,
+    pub buzz: Option<BuzzResult>,
+    pub reply: Option<ReplyResult>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllMentionResult {
+    pub mentions: Vec<MentionDetails>,
+    pub total_mentions: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+/// Buzzes and replies mentioning `user_id`, newest first. Mentions by
+/// `hidden` users are dropped from the page.
+pub async fn load_mentions_of(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    hidden: &[i64],
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllMentionResult> {
+    check_page(page_size, page_number)?;
+
+    let paginated_mentions = entity::mentions::Entity::find()
+        .filter(entity::mentions::Column::UserId.eq(user_id))
+        .order_by(entity::mentions::Column::CreatedAt, Order::Desc)
+        .order_by(entity::mentions::Column::Id, Order::Desc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_mentions.num_pages().await? as i32;
+    let total_mentions = paginated_mentions.num_items().await? as i32;
+
+    let mentions = paginated_mentions
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    let buzzes: HashMap<i64, entity::buzz::Model> = entity::buzz::Entity::find()
+        .filter(
+            entity::buzz::Column::Id.is_in(
+                mentions
+                    .iter()
+                    .filter_map(|mention| mention.buzz_id)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .all(connection)
+        .await?
+        .into_iter()
+        .filter(|buzz| !hidden.contains(&buzz.user_id))
+        .map(|buzz| (buzz.id, buzz))
+        .collect();
+
+    let replies: HashMap<i64, entity::reply::Model> = entity::reply::Entity::find()
+        .filter(
+            entity::reply::Column::Id.is_in(
+                mentions
+                    .iter()
+                    .filter_map(|mention| mention.reply_id)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .all(connection)
+        .await?
+        .into_iter()
+        .filter(|reply| !hidden.contains(&reply.user_id))
+        .map(|reply| (reply.id, reply))
+        .collect();
+
+    Ok(AllMentionResult {
+        mentions: mentions
+            .into_iter()
+            .map(|mention| MentionDetails {
+                id: ID::from(mention.id.to_string()),
+                buzz: mention
+                    .buzz_id
+                    .and_then(|buzz_id| buzzes.get(&buzz_id).cloned())
+                    .map(BuzzResult::from),
+                reply: mention
+                    .reply_id
+                    .and_then(|reply_id| replies.get(&reply_id).cloned())
+                    .map(ReplyResult::from),
+                created_at: mention.created_at,
+            })
+            .filter(|mention| mention.buzz.is_some() || mention.reply.is_some())
+            .collect(),
+        total_mentions,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use juniper::ID;
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, sea_query::Expr, ConnectionTrait,
+    DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+
+use crate::lib::{
+    blocks::require_not_blocked,
+    errors::{check_page, AppError, AppResult},
+    follows::is_following,
+};
+use crate::schemas::{
+    root::Context,
+    users::{load_user_details, load_users, UserDetails},
+};
+
+/// Who may start a conversation with a user or write to them one to one.
+#[derive(GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmPolicy {
+    Everyone,
+    Following,
+    Nobody,
+}
+
+impl DmPolicy {
+    /// Stored in `users.dm_policy`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            DmPolicy::Everyone => "everyone",
+            DmPolicy::Following => "following",
+            DmPolicy::Nobody => "nobody",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "following" => DmPolicy::Following,
+            "nobody" => DmPolicy::Nobody,
+            _ => DmPolicy::Everyone,
+        }
+    }
+}
+
+pub struct ConversationDetails {
+    conversation: entity::conversations::Model,
+    member_ids: Vec<i64>,
+    unread_count: i32,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl ConversationDetails {
+    fn id(&self) -> ID {
+        ID::from(self.conversation.id.to_string())
+    }
+
+    fn title(&self) -> Option<&str> {
+        self.conversation.title.as_deref()
+    }
+
+    fn is_group(&self) -> bool {
+        self.conversation.is_group
+    }
+
+    async fn members(&self, context: &Context) -> AppResult<Vec<UserDetails>> {
+        load_users(context, self.member_ids.clone()).await
+    }
+
+    #[graphql(description = "messages from others the viewer has not read yet")]
+    fn unread_count(&self) -> i32 {
+        self.unread_count
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.conversation.created_at
+    }
+
+    fn last_message_at(&self) -> Option<DateTimeWithTimeZone> {
+        self.conversation.last_message_at
+    }
+}
+
+pub struct MessageDetails {
+    message: entity::messages::Model,
+}
+
+impl From<entity::messages::Model> for MessageDetails {
+    fn from(message: entity::messages::Model) -> Self {
+        MessageDetails { message }
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl MessageDetails {
+    fn id(&self) -> ID {
+        ID::from(self.message.id.to_string())
+    }
+
+    fn conversation_id(&self) -> ID {
+        ID::from(self.message.conversation_id.to_string())
+    }
+
+    fn body(&self) -> &str {
+        &self.message.body
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.message.created_at
+    }
+
+    async fn sender(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.message.sender_id).await
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllConversationResult {
+    pub conversations: Vec<ConversationDetails>,
+    pub total_conversations: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllMessageResult {
+    pub messages: Vec<MessageDetails>,
+    pub total_messages: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+#[derive(FromQueryResult)]
+struct UnreadCount {
+    conversation_id: i64,
+    unread_count: i64,
+}
+
+/// Fails unless `recipient_id`'s DM policy lets `sender_id` write to them
+/// and neither of them blocked the other.
+pub async fn check_can_message<C: ConnectionTrait>(
+    connection: &C,
+    sender_id: i64,
+    recipient_id: i64,
+) -> AppResult<()> {
+    require_not_blocked(
+        connection,
+        sender_id,
+        recipient_id,
+        "Cant message this user",
+    )
+    .await?;
+
+    let recipient = entity::users::Entity::find_by_id(recipient_id)
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("User"))?;
+
+    match DmPolicy::from_key(&recipient.dm_policy) {
+        DmPolicy::Everyone => Ok(()),
+        DmPolicy::Following => {
+            if is_following(connection, recipient.id, sender_id).await? {
+                Ok(())
+            } else {
+                Err(AppError::Forbidden(
+                    "User only accepts messages from people they follow",
+                ))
+            }
+        }
+        DmPolicy::Nobody => Err(AppError::Forbidden("User does not accept messages")),
+    }
+}
+
+/// `user_id`'s membership of `conversation_id`. Conversations the user is not
+/// part of are reported as missing.
+pub async fn require_member<C: ConnectionTrait>(
+    connection: &C,
+    conversation_id: i64,
+    user_id: i64,
+) -> AppResult<entity::conversation_members::Model> {
+    entity::conversation_members::Entity::find()
+        .filter(entity::conversation_members::Column::ConversationId.eq(conversation_id))
+        .filter(entity::conversation_members::Column::UserId.eq(user_id))
+        .one(connection)
+        .await?
+        .ok_or(AppError::NotFound("Conversation"))
+}
+
+/// Member ids of each conversation, the other party first in one to one
+/// conversations.
+pub async fn load_member_ids<C: ConnectionTrait>(
+    connection: &C,
+    conversation_ids: Vec<i64>,
+) -> AppResult<HashMap<i64, Vec<i64>>> {
+    let mut member_ids: HashMap<i64, Vec<i64>> = HashMap::new();
+    for member in entity::conversation_members::Entity::find()
+        .filter(entity::conversation_members::Column::ConversationId.is_in(conversation_ids))
+        .order_by(entity::conversation_members::Column::Id, Order::Asc)
+        .all(connection)
+        .await?
+    {
+        member_ids
+            .entry(member.conversation_id)
+            .or_default()
+            .push(member.user_id);
+    }
+    Ok(member_ids)
+}
+
+/// Attaches members and `user_id`'s unread counts to `conversations`.
+pub async fn conversation_details(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    conversations: Vec<entity::conversations::Model>,
+) -> AppResult<Vec<ConversationDetails>> {
+    if conversations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conversation_ids = conversations
+        .iter()
+        .map(|conversation| conversation.id)
+        .collect::<Vec<i64>>();
+    let mut member_ids = load_member_ids(connection, conversation_ids.clone()).await?;
+
+    let placeholders = (2..conversation_ids.len() + 2)
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut values = vec![user_id.into()];
+    values.extend(conversation_ids.iter().map(|id| (*id).into()));
+
+    let unread_counts: HashMap<i64, i64> =
+        UnreadCount::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                r#"SELECT members.conversation_id, COUNT(messages.id) AS unread_count
+                FROM conversation_members AS members
+                JOIN messages ON messages.conversation_id = members.conversation_id
+                    AND messages.sender_id <> members.user_id
+                    AND (members.last_read_at IS NULL OR messages.created_at > members.last_read_at)
+                WHERE members.user_id = $1 AND members.conversation_id IN ({})
+                GROUP BY members.conversation_id"#,
+                placeholders
+            ),
+            values,
+        ))
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|count| (count.conversation_id, count.unread_count))
+        .collect();
+
+    Ok(conversations
+        .into_iter()
+        .map(|conversation| ConversationDetails {
+            member_ids: member_ids.remove(&conversation.id).unwrap_or_default(),
+            unread_count: unread_counts.get(&conversation.id).copied().unwrap_or(0) as i32,
+            conversation,
+        })
+        .collect())
+}
+
+/// Conversations `user_id` is part of, most recently active first.
+pub async fn load_conversations(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllConversationResult> {
+    check_page(page_size, page_number)?;
+
+    let paginated_conversations = entity::conversations::Entity::find()
+        .inner_join(entity::conversation_members::Entity)
+        .filter(entity::conversation_members::Column::UserId.eq(user_id))
+        .order_by(
+            Expr::cust("COALESCE(conversations.last_message_at, conversations.created_at)"),
+            Order::Desc,
+        )
+        .order_by(entity::conversations::Column::Id, Order::Desc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_conversations.num_pages().await? as i32;
+    let total_conversations = paginated_conversations.num_items().await? as i32;
+
+    let conversations = paginated_conversations
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    Ok(AllConversationResult {
+        conversations: conversation_details(connection, user_id, conversations).await?,
+        total_conversations,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
+
+/// Messages of `conversation_id`, newest first.
+pub async fn load_messages(
+    connection: &DatabaseConnection,
+    conversation_id: i64,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllMessageResult> {
+    check_page(page_size, page_number)?;
+
+    let paginated_messages = entity::messages::Entity::find()
+        .filter(entity::messages::Column::ConversationId.eq(conversation_id))
+        .order_by(entity::messages::Column::CreatedAt, Order::Desc)
+        .order_by(entity::messages::Column::Id, Order::Desc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_messages.num_pages().await? as i32;
+    let total_messages = paginated_messages.num_items().await? as i32;
+
+    let messages = paginated_messages
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    Ok(AllMessageResult {
+        messages: messages.into_iter().map(MessageDetails::from).collect(),
+        total_messages,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dm_policy_keys_round_trip() {
+        for policy in [DmPolicy::Everyone, DmPolicy::Following, DmPolicy::Nobody] {
+            assert_eq!(DmPolicy::from_key(policy.key()), policy);
+        }
+    }
+
+    #[test]
+    fn unknown_dm_policy_keys_allow_everyone() {
+        assert_eq!(DmPolicy::from_key(""), DmPolicy::Everyone);
+        assert_eq!(DmPolicy::from_key("Nobody"), DmPolicy::Everyone);
+    }
+}
@@ -7,7 +7,19 @@ pub mod trending;
 */
 pub mod auth;
 pub mod buzz;
+pub mod conversation;
+pub mod exports;
+pub mod follow_requests;
+pub mod mentions;
+pub mod messages;
+pub mod notifications;
+pub mod polls;
 pub mod ratings;
 pub mod reply;
+pub mod reports;
+pub mod revisions;
 pub mod root;
+pub mod search;
+pub mod timeline;
+pub mod trending;
 pub mod users;
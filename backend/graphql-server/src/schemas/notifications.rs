@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use juniper::ID;
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection, DbBackend,
+    FromQueryResult, Statement, Value,
+};
+
+use crate::lib::{
+    common::env_or,
+    errors::{check_page, AppResult},
+    notifications::NotificationKind,
+};
+use crate::schemas::{
+    buzz::BuzzResult,
+    reply::ReplyResult,
+    root::Context,
+    users::{load_users, UserDetails},
+};
+
+/// Follows and upvotes on the same buzz or reply collapse into one entry,
+/// replies and mentions are listed one by one.
+const GROUPED_KINDS: [NotificationKind; 2] = [NotificationKind::Follow, NotificationKind::Upvote];
+
+#[derive(FromQueryResult)]
+struct NotificationRow {
+    id: i64,
+    kind: String,
+    buzz_id: Option<i64>,
+    reply_id: Option<i64>,
+    actor_count: i64,
+    unread_count: i64,
+    actor_ids: Option<String>,
+    latest_at: DateTimeWithTimeZone,
+}
+
+#[derive(FromQueryResult)]
+struct NotificationTotal {
+    total: i64,
+}
+
+pub struct NotificationGroup {
+    id: i64,
+    kind: NotificationKind,
+    actor_count: i32,
+    actor_ids: Vec<i64>,
+    is_read: bool,
+    buzz: Option<entity::buzz::Model>,
+    reply: Option<entity::reply::Model>,
+    latest_at: DateTimeWithTimeZone,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl NotificationGroup {
+    /// The newest notification in the group.
+    fn id(&self) -> ID {
+        ID::from(self.id.to_string())
+    }
+
+    fn kind(&self) -> NotificationKind {
+        self.kind
+    }
+
+    #[graphql(description = "number of people behind the group, as in \"5 people upvoted\"")]
+    fn actor_count(&self) -> i32 {
+        self.actor_count
+    }
+
+    #[graphql(description = "the most recent of those people")]
+    async fn actors(&self, context: &Context) -> AppResult<Vec<UserDetails>> {
+        load_users(context, self.actor_ids.clone()).await
+    }
+
+    fn is_read(&self) -> bool {
+        self.is_read
+    }
+
+    fn buzz(&self) -> Option<BuzzResult> {
+        self.buzz.clone().map(BuzzResult::from)
+    }
+
+    fn reply(&self) -> Option<ReplyResult> {
+        self.reply.clone().map(ReplyResult::from)
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.latest_at
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllNotificationResult {
+    pub notifications: Vec<NotificationGroup>,
+    pub total_notifications: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+fn grouped_notifications_sql(hidden: &[i64]) -> String {
+    let grouped_kinds = GROUPED_KINDS
+        .iter()
+        .map(|kind| format!("'{}'", kind.key()))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    // Placeholders after the user id and before the page bounds.
+    let not_hidden = if hidden.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " AND actor_id NOT IN ({})",
+            (2..hidden.len() + 2)
+                .map(|i| format!("${}", i))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    };
+
+    format!(
+        r#"SELECT MAX(id) AS id, kind, buzz_id, reply_id,
+            COUNT(DISTINCT actor_id) AS actor_count,
+            COUNT(*) FILTER (WHERE read_at IS NULL) AS unread_count,
+            array_to_string(
+                (array_agg(actor_id ORDER BY created_at DESC, id DESC))[1:{}], ','
+            ) AS actor_ids,
+            MAX(created_at) AS latest_at
+        FROM notifications
+        WHERE user_id = $1{}
+        GROUP BY kind, buzz_id, reply_id,
+            CASE WHEN kind IN ({}) THEN 0 ELSE id END"#,
+        env_or("NOTIFICATION_ACTORS_SHOWN", 3),
+        not_hidden,
+        grouped_kinds
+    )
+}
+
+/// Notifications for `user_id`, newest group first. Whatever `hidden` users
+/// did is left out.
+pub async fn load_notifications(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    hidden: &[i64],
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllNotificationResult> {
+    check_page(page_size, page_number)?;
+
+    let grouped = grouped_notifications_sql(hidden);
+    let mut values: Vec<Value> = vec![user_id.into()];
+    values.extend(hidden.iter().map(|actor_id| (*actor_id).into()));
+
+    let total_notifications = NotificationTotal::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!("SELECT COUNT(*) AS total FROM ({}) AS grouped", grouped),
+        values.clone(),
+    ))
+    .one(connection)
+    .await?
+    .map_or(0, |count| count.total) as i32;
+    let total_pages = (total_notifications + page_size - 1) / page_size;
+
+    let rows = NotificationRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            "{} ORDER BY latest_at DESC, id DESC LIMIT {} OFFSET {}",
+            grouped,
+            page_size,
+            (page_number as i64 - 1) * page_size as i64
+        ),
+        values,
+    ))
+    .all(connection)
+    .await?;
+
+    let buzzes: HashMap<i64, entity::buzz::Model> = entity::buzz::Entity::find()
+        .filter(
+            entity::buzz::Column::Id.is_in(
+                rows.iter()
+                    .filter_map(|row| row.buzz_id)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|buzz| (buzz.id, buzz))
+        .collect();
+
+    let replies: HashMap<i64, entity::reply::Model> = entity::reply::Entity::find()
+        .filter(
+            entity::reply::Column::Id.is_in(
+                rows.iter()
+                    .filter_map(|row| row.reply_id)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|reply| (reply.id, reply))
+        .collect();
+
+    Ok(AllNotificationResult {
+        notifications: rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(NotificationGroup {
+                    id: row.id,
+                    kind: NotificationKind::from_key(&row.kind)?,
+                    actor_count: row.actor_count as i32,
+                    actor_ids: row
+                        .actor_ids
+                        .unwrap_or_default()
+                        .split(',')
+                        .filter_map(|actor_id| actor_id.parse::<i64>().ok())
+                        .collect(),
+                    is_read: row.unread_count == 0,
+                    buzz: row
+                        .buzz_id
+                        .and_then(|buzz_id| buzzes.get(&buzz_id).cloned()),
+                    reply: row
+                        .reply_id
+                        .and_then(|reply_id| replies.get(&reply_id).cloned()),
+                    latest_at: row.latest_at,
+                })
+            })
+            .collect(),
+        total_notifications,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
+
+/// Unread notifications for `user_id`, counted one by one rather than by group.
+pub async fn count_unread(
+    connection: &DatabaseConnection,
+    user_id: i64,
+    hidden: &[i64],
+) -> AppResult<i32> {
+    Ok(entity::notifications::Entity::find()
+        .filter(entity::notifications::Column::UserId.eq(user_id))
+        .filter(entity::notifications::Column::ActorId.is_not_in(hidden.to_vec()))
+        .filter(entity::notifications::Column::ReadAt.is_null())
+        .count(connection)
+        .await? as i32)
+}
@@ -0,0 +1,166 @@
+use juniper::ID;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+use crate::lib::{
+    errors::AppResult,
+    loaders::{PollTally, PollVoteKey},
+    polls::is_closed,
+};
+use crate::schemas::root::Context;
+
+#[derive(GraphQLInputObject)]
+#[graphql(description = "A poll attached to a new buzz")]
+pub struct PollInput {
+    #[graphql(description = "2 to 4 answers, in the order they are shown")]
+    pub options: Vec<String>,
+    #[graphql(description = "minutes the poll takes votes")]
+    pub duration_minutes: i32,
+    #[graphql(description = "whether voters may pick more than one option, false by default")]
+    pub multiple_choice: Option<bool>,
+    #[graphql(description = "keep the results from whoever has not voted until the poll closes")]
+    pub hide_results: Option<bool>,
+}
+
+pub struct PollDetails {
+    poll: entity::polls::Model,
+    author_id: i64,
+    viewer_id: Option<i64>,
+}
+
+impl PollDetails {
+    /// `viewer_id` is whom votes and results are worked out for.
+    pub fn new(poll: entity::polls::Model, author_id: i64, viewer_id: Option<i64>) -> Self {
+        PollDetails {
+            poll,
+            author_id,
+            viewer_id,
+        }
+    }
+
+    /// Options the viewer voted for.
+    async fn choices(&self, context: &Context) -> AppResult<Vec<i64>> {
+        match self.viewer_id {
+            Some(user_id) => Ok(context
+                .loaders
+                .poll_choices
+                .load(PollVoteKey {
+                    user_id,
+                    poll_id: self.poll.id,
+                })
+                .await?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Results are always shown once the poll closed, to its author and to
+    /// those who voted.
+    fn shows_results(&self, choices: &[i64]) -> bool {
+        !self.poll.hide_results
+            || is_closed(&self.poll)
+            || !choices.is_empty()
+            || self.viewer_id == Some(self.author_id)
+    }
+
+    async fn tally(&self, context: &Context) -> AppResult<PollTally> {
+        Ok(context.loaders.poll_tallies.load(self.poll.id).await?)
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl PollDetails {
+    fn id(&self) -> ID {
+        ID::from(self.poll.id.to_string())
+    }
+
+    fn multiple_choice(&self) -> bool {
+        self.poll.multiple_choice
+    }
+
+    fn hide_results(&self) -> bool {
+        self.poll.hide_results
+    }
+
+    fn closes_at(&self) -> DateTimeWithTimeZone {
+        self.poll.closes_at
+    }
+
+    fn is_closed(&self) -> bool {
+        is_closed(&self.poll)
+    }
+
+    #[graphql(description = "voted by the user of the jwt argument")]
+    async fn has_voted(&self, context: &Context) -> AppResult<bool> {
+        Ok(!self.choices(context).await?.is_empty())
+    }
+
+    #[graphql(description = "whether counts and percentages are shown to the caller")]
+    async fn results_visible(&self, context: &Context) -> AppResult<bool> {
+        let choices = self.choices(context).await?;
+        Ok(self.shows_results(&choices))
+    }
+
+    #[graphql(description = "users who voted, null while the results are hidden")]
+    async fn total_votes(&self, context: &Context) -> AppResult<Option<i32>> {
+        let choices = self.choices(context).await?;
+        if !self.shows_results(&choices) {
+            return Ok(None);
+        }
+        Ok(Some(self.tally(context).await?.voters as i32))
+    }
+
+    async fn options(&self, context: &Context) -> AppResult<Vec<PollOptionDetails>> {
+        let choices = self.choices(context).await?;
+        let shows_results = self.shows_results(&choices);
+        let tally = self.tally(context).await?;
+
+        Ok(tally
+            .options
+            .into_iter()
+            .map(|(option, votes)| PollOptionDetails {
+                id: ID::from(option.id.to_string()),
+                label: option.label,
+                votes: shows_results.then_some(votes as i32),
+                percentage: shows_results.then(|| percentage(votes, tally.voters)),
+                is_chosen: choices.contains(&option.id),
+            })
+            .collect())
+    }
+}
+
+/// Share of the voters who picked an option, to one decimal. Options of a
+/// multiple choice poll can add up to more than 100.
+fn percentage(votes: i64, voters: i64) -> f64 {
+    if voters == 0 {
+        return 0.0;
+    }
+    (votes as f64 * 1000.0 / voters as f64).round() / 10.0
+}
+
+#[derive(GraphQLObject)]
+pub struct PollOptionDetails {
+    pub id: ID,
+    pub label: String,
+    #[graphql(description = "null while the results are hidden")]
+    pub votes: Option<i32>,
+    #[graphql(description = "share of the voters, null while the results are hidden")]
+    pub percentage: Option<f64>,
+    #[graphql(description = "picked by the user of the jwt argument")]
+    pub is_chosen: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentage_is_rounded_to_one_decimal() {
+        assert_eq!(percentage(1, 3), 33.3);
+        assert_eq!(percentage(2, 3), 66.7);
+        assert_eq!(percentage(3, 3), 100.0);
+    }
+
+    #[test]
+    fn percentage_without_voters_is_zero() {
+        assert_eq!(percentage(0, 0), 0.0);
+    }
+}
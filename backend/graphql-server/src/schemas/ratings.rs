@@ -1,12 +1,65 @@
+use juniper::ID;
+use sea_orm::{entity::*, query::*, sea_query::Expr, ConnectionTrait, DbErr};
+
+use crate::lib::errors::AppResult;
+use crate::schemas::root::Context;
+
 #[derive(GraphQLObject)]
 pub struct RatingsDetails {
-    pub id: i32,
+    pub id: ID,
     pub upvotes: String,
     pub views: String,
+    pub rebuzzes: String,
+    pub quotes: String,
+}
+
+impl From<entity::ratings::Model> for RatingsDetails {
+    fn from(ratings: entity::ratings::Model) -> Self {
+        RatingsDetails {
+            id: ID::from(ratings.id.to_string()),
+            upvotes: ratings.upvotes.unwrap_or(0).to_string(),
+            views: ratings.views.unwrap_or(0).to_string(),
+            rebuzzes: ratings.rebuzzes.unwrap_or(0).to_string(),
+            quotes: ratings.quotes.unwrap_or(0).to_string(),
+        }
+    }
+}
+
+pub async fn load_ratings_details(
+    context: &Context,
+    ratings_id: Option<i64>,
+) -> AppResult<Option<RatingsDetails>> {
+    match ratings_id {
+        Some(ratings_id) => Ok(context
+            .loaders
+            .ratings
+            .load(ratings_id)
+            .await?
+            .map(RatingsDetails::from)),
+        None => Ok(None),
+    }
+}
+
+/// Adds `delta` to a counter in the database instead of writing back a value
+/// read earlier, so concurrent updates are not lost.
+pub async fn adjust_ratings_counter<C: ConnectionTrait>(
+    connection: &C,
+    ratings_id: Option<i64>,
+    column: entity::ratings::Column,
+    delta: i64,
+) -> Result<(), DbErr> {
+    if let Some(ratings_id) = ratings_id {
+        entity::ratings::Entity::update_many()
+            .col_expr(column, Expr::expr(Expr::col(column).if_null(0)).add(delta))
+            .filter(entity::ratings::Column::Id.eq(ratings_id))
+            .exec(connection)
+            .await?;
+    }
+    Ok(())
 }
 
 #[derive(GraphQLObject)]
 pub struct UpvoteResponse {
-    pub id: String,
+    pub id: ID,
     pub is_upvoted: bool,
 }
@@ -1,12 +1,38 @@
+use juniper::ID;
 use sea_orm::prelude::DateTimeWithTimeZone;
 
+use crate::lib::{common::Tagged, errors::AppResult, loaders::RevisionOwner};
+use crate::schemas::{
+    ratings::{load_ratings_details, RatingsDetails},
+    revisions::{load_revisions, RevisionDetails},
+    root::Context,
+    users::{load_mentioned_users, load_user_details, UserDetails},
+};
+
+/// What a reply hidden by a moderator says instead of its content.
+pub const HIDDEN_REPLY_CONTENT: &str = "This reply was hidden by a moderator";
+
+/// What a reply tombstone says instead of its content.
+pub const DELETED_REPLY_CONTENT: &str = "This reply was deleted";
+
 #[derive(GraphQLInputObject)]
 #[graphql(description = "Create reply")]
 pub struct ReplyInput {
-    pub user_id: String,
-    pub buzz_id: String,
+    pub user_id: ID,
+    pub buzz_id: ID,
+    pub parent_reply_id: Option<ID>,
+    pub reply_content: String,
+    pub buzz_words: Option<String>,
+    #[graphql(description = "ignored, mentions are read from the text")]
+    pub mentioned_users: Option<String>,
+}
+
+#[derive(GraphQLInputObject, Clone)]
+#[graphql(description = "Edit reply")]
+pub struct ReplyUpdateInput {
     pub reply_content: String,
     pub buzz_words: Option<String>,
+    #[graphql(description = "ignored, mentions are read from the text")]
     pub mentioned_users: Option<String>,
 }
 
@@ -15,22 +41,144 @@ pub struct ReplyInput {
 pub struct GetAllRepliesInput {
     pub page_size: i32,
     pub page_number: i32,
-    pub buzz_id: String,
+    pub buzz_id: ID,
 }
 
-#[derive(GraphQLObject)]
 pub struct ReplyResult {
-    pub id: String,
-    pub user_id: String,
-    pub buzz_id: String,
+    pub id: i64,
+    pub user_id: i64,
+    pub buzz_id: i64,
+    pub parent_reply_id: Option<i64>,
     pub reply_content: String,
     pub buzz_words: Option<String>,
     pub mentioned_users: Option<String>,
-    pub ratings_id: Option<String>,
+    pub ratings_id: Option<i64>,
     pub created_at: DateTimeWithTimeZone,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+    pub hidden_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<entity::reply::Model> for ReplyResult {
+    fn from(reply: entity::reply::Model) -> Self {
+        ReplyResult {
+            id: reply.id,
+            user_id: reply.user_id,
+            buzz_id: reply.buzz_id,
+            parent_reply_id: reply.parent_reply_id,
+            reply_content: reply.reply_content,
+            buzz_words: reply.buzz_words,
+            mentioned_users: reply.mentioned_users,
+            ratings_id: reply.ratings_id,
+            created_at: reply.created_at,
+            edited_at: reply.edited_at,
+            hidden_at: reply.hidden_at,
+            deleted_at: reply.deleted_at,
+        }
+    }
+}
+
+impl ReplyResult {
+    /// `field` unless the reply was deleted or a moderator hid it.
+    fn visible<'a>(&self, field: &'a Option<String>) -> Option<&'a String> {
+        if self.is_gone() {
+            None
+        } else {
+            field.as_ref()
+        }
+    }
+
+    fn is_gone(&self) -> bool {
+        self.deleted_at.is_some() || self.hidden_at.is_some()
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl ReplyResult {
+    fn id(&self) -> ID {
+        ID::from(self.id.to_string())
+    }
+
+    fn user_id(&self) -> ID {
+        ID::from(self.user_id.to_string())
+    }
+
+    fn buzz_id(&self) -> ID {
+        ID::from(self.buzz_id.to_string())
+    }
+
+    fn parent_reply_id(&self) -> Option<ID> {
+        self.parent_reply_id
+            .map(|parent_reply_id| ID::from(parent_reply_id.to_string()))
+    }
+
+    fn reply_content(&self) -> &str {
+        if self.deleted_at.is_some() {
+            DELETED_REPLY_CONTENT
+        } else if self.hidden_at.is_some() {
+            HIDDEN_REPLY_CONTENT
+        } else {
+            &self.reply_content
+        }
+    }
+
+    fn buzz_words(&self) -> Option<&String> {
+        self.visible(&self.buzz_words)
+    }
+
+    fn mentioned_users(&self) -> Option<&String> {
+        self.visible(&self.mentioned_users)
+    }
+
+    async fn mentions(&self, context: &Context) -> AppResult<Vec<UserDetails>> {
+        if self.is_gone() {
+            return Ok(Vec::new());
+        }
+        load_mentioned_users(context, Tagged::Reply(self.id)).await
+    }
+
+    fn ratings_id(&self) -> Option<ID> {
+        self.ratings_id
+            .map(|ratings_id| ID::from(ratings_id.to_string()))
+    }
+
+    fn created_at(&self) -> &DateTimeWithTimeZone {
+        &self.created_at
+    }
+
+    fn edited_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.edited_at
+    }
+
+    #[graphql(description = "set once the author deleted the reply, the thread stays readable")]
+    fn deleted_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.deleted_at
+    }
+
+    #[graphql(description = "set while a moderator keeps the reply hidden")]
+    fn hidden_at(&self) -> &Option<DateTimeWithTimeZone> {
+        &self.hidden_at
+    }
+
+    #[graphql(description = "previous versions, newest first")]
+    async fn revisions(&self, context: &Context) -> AppResult<Vec<RevisionDetails>> {
+        if self.is_gone() {
+            return Ok(Vec::new());
+        }
+        load_revisions(context, RevisionOwner::Reply(self.id)).await
+    }
+
+    async fn author(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.user_id).await
+    }
+
+    async fn ratings(&self, context: &Context) -> AppResult<Option<RatingsDetails>> {
+        load_ratings_details(context, self.ratings_id).await
+    }
 }
 
 #[derive(GraphQLObject)]
+#[graphql(Context = Context)]
 pub struct AllReplyResult {
     pub replies: Vec<ReplyResult>,
     pub total_replies: i32,
@@ -0,0 +1,237 @@
+use juniper::ID;
+use sea_orm::{entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection};
+
+use crate::lib::{
+    errors::{check_page, AppResult},
+    moderation::{ModerationActionKind, ReportReason, ReportStatus},
+};
+use crate::schemas::{
+    buzz::BuzzResult,
+    reply::ReplyResult,
+    root::Context,
+    users::{load_user_details, UserDetails},
+};
+
+pub struct ReportDetails {
+    report: entity::reports::Model,
+}
+
+impl From<entity::reports::Model> for ReportDetails {
+    fn from(report: entity::reports::Model) -> Self {
+        ReportDetails { report }
+    }
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl ReportDetails {
+    fn id(&self) -> ID {
+        ID::from(self.report.id.to_string())
+    }
+
+    fn reason(&self) -> ReportReason {
+        ReportReason::from_key(&self.report.reason).unwrap_or(ReportReason::Other)
+    }
+
+    fn details(&self) -> &Option<String> {
+        &self.report.details
+    }
+
+    fn status(&self) -> ReportStatus {
+        ReportStatus::from_key(&self.report.status).unwrap_or(ReportStatus::Open)
+    }
+
+    async fn reporter(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.report.reporter_id).await
+    }
+
+    #[graphql(description = "the reported user, or the author of the reported buzz or reply")]
+    async fn reported_user(&self, context: &Context) -> AppResult<UserDetails> {
+        load_user_details(context, self.report.reported_user_id).await
+    }
+
+    async fn buzz(&self, context: &Context) -> AppResult<Option<BuzzResult>> {
+        match self.report.buzz_id {
+            Some(buzz_id) => Ok(context
+                .loaders
+                .buzzes
+                .load(buzz_id)
+                .await?
+                .map(BuzzResult::from)),
+            None => Ok(None),
+        }
+    }
+
+    async fn reply(&self, context: &Context) -> AppResult<Option<ReplyResult>> {
+        match self.report.reply_id {
+            Some(reply_id) => Ok(entity::reply::Entity::find_by_id(reply_id)
+                .one(&context.connection)
+                .await?
+                .map(ReplyResult::from)),
+            None => Ok(None),
+        }
+    }
+
+    #[graphql(description = "the reported buzz or reply as it read when it was reported")]
+    fn content_snapshot(&self) -> &Option<String> {
+        &self.report.content_snapshot
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.report.created_at
+    }
+
+    async fn resolved_by(&self, context: &Context) -> AppResult<Option<UserDetails>> {
+        match self.report.resolved_by {
+            Some(user_id) => Ok(Some(load_user_details(context, user_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    fn resolved_at(&self) -> Option<DateTimeWithTimeZone> {
+        self.report.resolved_at
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllReportResult {
+    pub reports: Vec<ReportDetails>,
+    pub total_reports: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+/// Reports with `status`, oldest first so the queue is worked in order.
+pub async fn load_reports(
+    connection: &DatabaseConnection,
+    status: ReportStatus,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllReportResult> {
+    check_page(page_size, page_number)?;
+
+    let paginated_reports = entity::reports::Entity::find()
+        .filter(entity::reports::Column::Status.eq(status.key()))
+        .order_by(entity::reports::Column::CreatedAt, Order::Asc)
+        .order_by(entity::reports::Column::Id, Order::Asc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_reports.num_pages().await? as i32;
+    let total_reports = paginated_reports.num_items().await? as i32;
+
+    let reports = paginated_reports
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    Ok(AllReportResult {
+        reports: reports.into_iter().map(ReportDetails::from).collect(),
+        total_reports,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
+
+pub struct ModerationActionDetails {
+    moderation_action: entity::moderation_actions::Model,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl ModerationActionDetails {
+    fn id(&self) -> ID {
+        ID::from(self.moderation_action.id.to_string())
+    }
+
+    fn action(&self) -> Option<ModerationActionKind> {
+        ModerationActionKind::from_key(&self.moderation_action.action)
+    }
+
+    fn reason(&self) -> &str {
+        &self.moderation_action.reason
+    }
+
+    async fn moderator(&self, context: &Context) -> AppResult<Option<UserDetails>> {
+        match self.moderation_action.moderator_id {
+            Some(user_id) => Ok(Some(load_user_details(context, user_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    #[graphql(description = "the user acted on, or the author of the buzz or reply")]
+    async fn user(&self, context: &Context) -> AppResult<Option<UserDetails>> {
+        match self.moderation_action.user_id {
+            Some(user_id) => Ok(Some(load_user_details(context, user_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    fn buzz_id(&self) -> Option<ID> {
+        self.moderation_action
+            .buzz_id
+            .map(|buzz_id| ID::from(buzz_id.to_string()))
+    }
+
+    fn reply_id(&self) -> Option<ID> {
+        self.moderation_action
+            .reply_id
+            .map(|reply_id| ID::from(reply_id.to_string()))
+    }
+
+    fn report_id(&self) -> Option<ID> {
+        self.moderation_action
+            .report_id
+            .map(|report_id| ID::from(report_id.to_string()))
+    }
+
+    fn created_at(&self) -> DateTimeWithTimeZone {
+        self.moderation_action.created_at
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(Context = Context)]
+pub struct AllModerationActionResult {
+    pub moderation_actions: Vec<ModerationActionDetails>,
+    pub total_moderation_actions: i32,
+    pub total_pages: i32,
+    pub page_number: i32,
+    pub page_size: i32,
+}
+
+/// The moderation log, newest first, only what concerns `user_id` when
+/// given.
+pub async fn load_moderation_actions(
+    connection: &DatabaseConnection,
+    user_id: Option<i64>,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllModerationActionResult> {
+    check_page(page_size, page_number)?;
+
+    let mut query = entity::moderation_actions::Entity::find();
+    if let Some(user_id) = user_id {
+        query = query.filter(entity::moderation_actions::Column::UserId.eq(user_id));
+    }
+    let paginated_actions = query
+        .order_by(entity::moderation_actions::Column::Id, Order::Desc)
+        .paginate(connection, page_size as usize);
+
+    let total_pages = paginated_actions.num_pages().await? as i32;
+    let total_moderation_actions = paginated_actions.num_items().await? as i32;
+
+    let moderation_actions = paginated_actions
+        .fetch_page((page_number - 1) as usize)
+        .await?;
+
+    Ok(AllModerationActionResult {
+        moderation_actions: moderation_actions
+            .into_iter()
+            .map(|moderation_action| ModerationActionDetails { moderation_action })
+            .collect(),
+        total_moderation_actions,
+        total_pages,
+        page_number,
+        page_size,
+    })
+}
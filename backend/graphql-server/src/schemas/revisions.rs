@@ -0,0 +1,42 @@
+use juniper::ID;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+use crate::lib::{errors::AppResult, loaders::RevisionOwner};
+use crate::schemas::root::Context;
+
+/// A previous version of a buzz or reply, `created_at` is when that version
+/// was written and `revised_at` when it was replaced.
+#[derive(GraphQLObject)]
+pub struct RevisionDetails {
+    pub id: ID,
+    pub content: String,
+    pub image_link: Option<String>,
+    pub video_link: Option<String>,
+    pub buzz_words: Option<String>,
+    pub mentioned_users: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub revised_at: DateTimeWithTimeZone,
+}
+
+impl From<entity::buzz_revisions::Model> for RevisionDetails {
+    fn from(revision: entity::buzz_revisions::Model) -> Self {
+        RevisionDetails {
+            id: ID::from(revision.id.to_string()),
+            content: revision.content,
+            image_link: revision.image_link,
+            video_link: revision.video_link,
+            buzz_words: revision.buzz_words,
+            mentioned_users: revision.mentioned_users,
+            created_at: revision.created_at,
+            revised_at: revision.revised_at,
+        }
+    }
+}
+
+pub async fn load_revisions(
+    context: &Context,
+    owner: RevisionOwner,
+) -> AppResult<Vec<RevisionDetails>> {
+    let revisions = context.loaders.revisions.load(owner).await?;
+    Ok(revisions.into_iter().map(RevisionDetails::from).collect())
+}
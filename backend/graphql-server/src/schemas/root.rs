@@ -1,13 +1,41 @@
 use std::io::Write;
-use juniper::{EmptySubscription, FieldError, FieldResult, IntrospectionFormat, RootNode};
-use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr, InsertResult};
+use std::sync::Arc;
+use std::time::Duration;
+use juniper::{BoxFuture, EmptySubscription, IntrospectionFormat, RootNode, ID};
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, sea_query::Expr, ConnectionTrait,
+    DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, Statement, TransactionTrait,
+};
+use tokio::sync::OnceCell;
 
 use crate::lib::{
+    blocks::{hidden_user_ids, require_not_blocked},
     common::*,
+    errors::{check_page, parse_id, AppError, AppResult},
+    exports::{new_download_token, DataExportStatus},
+    follows::{
+        follow, follower_ids, following_ids, is_following, remove_follows_between, unfollow,
+    },
+    hashtags::{parse_hashtags, save_hashtags, to_buzz_words},
+    loaders::Loaders,
+    mentions::{parse_mentions, resolve_mentions, save_mentions, to_mentioned_users},
+    moderation::{
+        check_reason, file_report, record_moderation_action, require_moderator, resolve_reports,
+        ContentAction, ModerationActionKind, ModerationTarget, ReportReason, ReportStatus,
+    },
+    notifications::{notify, ratings_owner, retract, NotificationKind},
+    polls::{check_poll, create_poll, vote_poll},
+    privacy::{is_locked, not_locked, not_locked_sql},
+    rate_limits::{check_rate_limit, MutationClass},
+    rebuzzes::toggle_rebuzz,
+    security_events::{record_security_event, SecurityEventKind},
     server_auth::{
-        authenticate,
+        authenticate, require_auth, require_one_time_auth, Authenticated as AuthenticatedUser,
         AuthenticationStatus::{Authenticated, Unauthenticated},
     },
+    tombstones::{tombstone_buzz, tombstone_reply},
+    upvotes::{toggle_upvote, upvoted_ratings_ids},
+    views::record_views,
 };
 
 use argonautica::Hasher;
@@ -17,6 +45,119 @@ use crate::schemas;
 #[derive(Debug, Clone)]
 pub struct Context {
     pub connection: DatabaseConnection,
+    pub loaders: Loaders,
+    client_ip: Option<String>,
+    viewer: Arc<OnceCell<i64>>,
+    blocked: Arc<OnceCell<Vec<i64>>>,
+}
+
+impl Context {
+    pub fn new(connection: DatabaseConnection) -> Self {
+        Context::with_client_ip(connection, None)
+    }
+
+    /// `client_ip` is the address the request came from.
+    pub fn with_client_ip(connection: DatabaseConnection, client_ip: Option<String>) -> Self {
+        let loaders = Loaders::new(&connection);
+        Context {
+            connection,
+            loaders,
+            client_ip,
+            viewer: Arc::new(OnceCell::new()),
+            blocked: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn client_ip(&self) -> Option<&str> {
+        self.client_ip.as_deref()
+    }
+
+    /// Checks `jwt` like `require_auth` and makes its user the viewer of
+    /// the request, the one fields below look at. All `jwt` arguments of a
+    /// request have to belong to the same user.
+    pub async fn require_auth(&self, jwt: String) -> AppResult<AuthenticatedUser> {
+        let authenticated = require_auth(jwt).await?;
+        if self.viewer.set(authenticated.user_id).is_err()
+            && self.viewer.get() != Some(&authenticated.user_id)
+        {
+            return Err(AppError::BadRequest(
+                "All jwt arguments of a request must belong to the same user".to_string(),
+            ));
+        }
+        Ok(authenticated)
+    }
+
+    /// `require_auth` for fields anonymous callers may use too.
+    pub async fn optional_auth(&self, jwt: Option<String>) -> AppResult<Option<i64>> {
+        match jwt {
+            Some(jwt) => Ok(Some(self.require_auth(jwt).await?.user_id)),
+            None => Ok(self.viewer_id()),
+        }
+    }
+
+    /// The user a `jwt` argument of the request authenticated, if any.
+    pub fn viewer_id(&self) -> Option<i64> {
+        self.viewer.get().copied()
+    }
+
+    /// Users the viewer blocked or was blocked by, what they wrote is kept
+    /// away from the viewer. Empty for anonymous requests.
+    pub async fn blocked_user_ids(&self) -> Result<&[i64], DbErr> {
+        let viewer_id = match self.viewer_id() {
+            Some(viewer_id) => viewer_id,
+            None => return Ok(&[]),
+        };
+        let blocked = self
+            .blocked
+            .get_or_try_init(|| hidden_user_ids(&self.connection, viewer_id, false))
+            .await?;
+        Ok(blocked)
+    }
+
+    /// Whether `user_id` is a private account the viewer may not see.
+    pub async fn is_locked(&self, user_id: i64) -> Result<bool, DbErr> {
+        is_locked(&self.connection, user_id, self.viewer_id()).await
+    }
+
+    /// Runs `f` in a serializable transaction that is committed when it
+    /// returns `Ok` and rolled back otherwise, so a mutation writes everything
+    /// or nothing. Transactions Postgres aborts for a concurrent one
+    /// (serialization failures, deadlocks) are run again with a fresh copy of
+    /// `input`, up to `TRANSACTION_ATTEMPTS` times in all.
+    pub async fn transaction<I, T, F>(&self, input: I, f: F) -> AppResult<T>
+    where
+        I: Clone + Send,
+        T: Send,
+        F: for<'t> Fn(&'t DatabaseTransaction, I) -> BoxFuture<'t, AppResult<T>> + Sync,
+    {
+        let attempts: u64 = env_or("TRANSACTION_ATTEMPTS", 3);
+
+        let mut attempt = 1;
+        loop {
+            let txn = self.connection.begin().await?;
+            txn.execute(Statement::from_string(
+                DbBackend::Postgres,
+                "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE".to_string(),
+            ))
+            .await?;
+            let result = match f(&txn, input.clone()).await {
+                Ok(value) => txn.commit().await.map(|_| value).map_err(AppError::from),
+                Err(e) => {
+                    txn.rollback().await?;
+                    Err(e)
+                }
+            };
+
+            match result {
+                Err(e) if e.is_retryable() && attempt < attempts => {
+                    log::warn!("transaction attempt {} failed, retrying: {:?}", attempt, e);
+                    actix_web::rt::time::sleep(Duration::from_millis(10 * attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 }
 
 impl juniper::Context for Context {}
@@ -27,251 +168,627 @@ pub struct QueryRoot;
 impl QueryRoot {
     async fn get_buzzes(
         page_details: schemas::buzz::GetAllBuzzInput,
+        jwt: Option<String>,
         context: &Context,
-    ) -> FieldResult<schemas::buzz::AllBuzzResult> {
+    ) -> AppResult<schemas::buzz::AllBuzzResult> {
         let connection = &context.connection;
-
-        let paginated_posts = match page_details.user_id {
-            Some(user_id) => {
-                let posts = entity::buzz::Entity::find()
-                    .filter(entity::buzz::Column::UserId.eq(user_id.parse::<i64>().unwrap()))
-                    .order_by(entity::buzz::Column::CreatedAt, Order::Desc)
-                    .paginate(connection, page_details.page_size as usize);
-                posts
+        context.optional_auth(jwt).await?;
+        check_page(page_details.page_size, page_details.page_number)?;
+
+        // A user's timeline also has the buzzes they rebuzzed.
+        if let Some(user_id) = &page_details.user_id {
+            let user_id = parse_id(user_id)?;
+            if context.is_locked(user_id).await? {
+                return Err(AppError::Forbidden("Account is private"));
             }
+            let hidden = context.blocked_user_ids().await?;
+            let user_ids = if hidden.contains(&user_id) {
+                Vec::new()
+            } else {
+                vec![user_id]
+            };
+            return schemas::timeline::load_timeline(
+                connection,
+                &user_ids,
+                hidden,
+                context.viewer_id(),
+                page_details.page_size,
+                page_details.page_number,
+            )
+            .await;
+        }
 
-            None => {
-                let posts = entity::buzz::Entity::find()
-                    .order_by(entity::buzz::Column::CreatedAt, Order::Desc)
-                    .paginate(connection, page_details.page_size as usize);
-                posts
-            }
+        let viewer_id = context.viewer_id();
+        let hidden = match viewer_id {
+            Some(viewer_id) => hidden_user_ids(connection, viewer_id, true).await?,
+            None => Vec::new(),
         };
 
-        let total_pages = paginated_posts.num_pages().await.unwrap() as i32;
-        let total_buzzes = paginated_posts.num_items().await.unwrap() as i32;
+        let paginated_posts = entity::buzz::Entity::find()
+            .filter(entity::buzz::Column::DeletedAt.is_null())
+            .filter(entity::buzz::Column::HiddenAt.is_null())
+            .filter(entity::buzz::Column::UserId.is_not_in(hidden))
+            .filter(not_locked("buzz.user_id", viewer_id))
+            .order_by(entity::buzz::Column::CreatedAt, Order::Desc)
+            .paginate(connection, page_details.page_size as usize);
+
+        let total_pages = paginated_posts.num_pages().await? as i32;
+        let total_buzzes = paginated_posts.num_items().await? as i32;
 
-        let fetch_buzzes: Result<Vec<entity::buzz::Model>, DbErr> = paginated_posts
+        let buzzes = paginated_posts
             .fetch_page((page_details.page_number - 1) as usize)
-            .await;
+            .await?;
+
+        let return_buzzes: Vec<schemas::buzz::BuzzResult> = buzzes
+            .into_iter()
+            .map(schemas::buzz::BuzzResult::from)
+            .collect();
+
+        Ok(schemas::buzz::AllBuzzResult {
+            buzzes: return_buzzes,
+            total_buzzes,
+            total_pages,
+            page_number: page_details.page_number,
+            page_size: page_details.page_size,
+        })
+    }
 
-        return match fetch_buzzes {
-            Ok(buzzes) => {
-                let return_buzzes: Vec<schemas::buzz::BuzzResult> = buzzes
-                    .into_iter()
-                    .map(|buzz| schemas::buzz::BuzzResult {
-                        id: buzz.id.to_string(),
-                        user_id: buzz.user_id.to_string(),
-                        description: buzz.description.to_string(),
-                        image_link: buzz.image_link,
-                        video_link: buzz.video_link,
-                        buzz_words: buzz.buzz_words,
-                        mentioned_users: buzz.mentioned_users,
-                        ratings_id: Some(buzz.ratings_id.unwrap_or(-1).to_string()),
-                        created_at: buzz.created_at,
-                    })
-                    .collect();
+    #[graphql(description = "buzzes and rebuzzes of the user and everyone they follow")]
+    async fn get_home_timeline(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::buzz::AllBuzzResult> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
 
-                Ok(schemas::buzz::AllBuzzResult {
-                    buzzes: return_buzzes,
-                    total_buzzes,
-                    total_pages,
-                    page_number: page_details.page_number,
-                    page_size: page_details.page_size,
-                })
-            }
+        let user = entity::users::Entity::find_by_id(authenticated.user_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+
+        let mut user_ids = following_ids(connection, user.id).await?;
+        user_ids.push(user.id);
+
+        let hidden = hidden_user_ids(connection, user.id, true).await?;
+        user_ids.retain(|user_id| !hidden.contains(user_id));
+
+        schemas::timeline::load_timeline(
+            connection,
+            &user_ids,
+            &hidden,
+            Some(user.id),
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "the user's bookmarked buzzes, latest bookmark first")]
+    async fn my_bookmarks(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::buzz::AllBuzzResult> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+        check_page(page_size, page_number)?;
+
+        // Buzzes the user may not see anymore stay bookmarked but out of sight.
+        let mut visible = format!(
+            "EXISTS (SELECT 1 FROM buzz WHERE buzz.id = bookmarks.buzz_id AND {}",
+            not_locked_sql("buzz.user_id", "$1")
+        );
+        let blocked = context.blocked_user_ids().await?;
+        if !blocked.is_empty() {
+            visible.push_str(&format!(
+                " AND buzz.user_id NOT IN ({})",
+                to_id_list(blocked.to_vec())
+            ));
+        }
+        visible.push(')');
+
+        let paginated_bookmarks = entity::bookmarks::Entity::find()
+            .filter(entity::bookmarks::Column::UserId.eq(authenticated.user_id))
+            .filter(Expr::cust_with_values(
+                &visible,
+                vec![authenticated.user_id],
+            ))
+            .order_by(entity::bookmarks::Column::CreatedAt, Order::Desc)
+            .order_by(entity::bookmarks::Column::Id, Order::Desc)
+            .paginate(connection, page_size as usize);
+
+        let total_pages = paginated_bookmarks.num_pages().await? as i32;
+        let total_buzzes = paginated_bookmarks.num_items().await? as i32;
+
+        let bookmarks = paginated_bookmarks
+            .fetch_page((page_number - 1) as usize)
+            .await?;
+
+        let mut buzzes: std::collections::HashMap<i64, entity::buzz::Model> =
+            entity::buzz::Entity::find()
+                .filter(
+                    entity::buzz::Column::Id.is_in(
+                        bookmarks
+                            .iter()
+                            .map(|bookmark| bookmark.buzz_id)
+                            .collect::<Vec<i64>>(),
+                    ),
+                )
+                .all(connection)
+                .await?
+                .into_iter()
+                .map(|buzz| (buzz.id, buzz))
+                .collect();
+
+        Ok(schemas::buzz::AllBuzzResult {
+            buzzes: bookmarks
+                .iter()
+                .filter_map(|bookmark| buzzes.remove(&bookmark.buzz_id))
+                .map(schemas::buzz::BuzzResult::from)
+                .collect(),
+            total_buzzes,
+            total_pages,
+            page_number,
+            page_size,
+        })
+    }
+
+    #[graphql(description = "full-text search over buzzes")]
+    async fn search_buzzes(
+        query: String,
+        filters: Option<schemas::search::SearchFilters>,
+        page_size: i32,
+        page_number: i32,
+        jwt: Option<String>,
+        context: &Context,
+    ) -> AppResult<schemas::buzz::AllBuzzResult> {
+        context.optional_auth(jwt).await?;
+        schemas::search::search_buzzes(
+            &context.connection,
+            query,
+            filters,
+            context.blocked_user_ids().await?,
+            context.viewer_id(),
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "most used hashtags, refreshed in the background")]
+    async fn get_trending(
+        window: Option<schemas::trending::TrendingWindow>,
+        context: &Context,
+    ) -> AppResult<Vec<schemas::trending::TrendingDetails>> {
+        let window = window.unwrap_or(schemas::trending::TrendingWindow::Day);
+
+        let trending = entity::trending::Entity::find()
+            .filter(entity::trending::Column::TimeWindow.eq(window.key()))
+            .order_by(entity::trending::Column::Rank, Order::Asc)
+            .find_also_related(entity::hashtags::Entity)
+            .all(&context.connection)
+            .await?;
+
+        Ok(trending
+            .into_iter()
+            .filter_map(|(trending, hashtag)| Some((trending, hashtag?)))
+            .map(schemas::trending::TrendingDetails::from)
+            .collect())
+    }
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
+    #[graphql(description = "buzzes and replies mentioning the user")]
+    async fn mentions_of_me(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::mentions::AllMentionResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        let hidden = hidden_user_ids(&context.connection, authenticated.user_id, true).await?;
+        schemas::mentions::load_mentions_of(
+            &context.connection,
+            authenticated.user_id,
+            &hidden,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "notifications for the user, follows and upvotes are grouped")]
+    async fn notifications(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::notifications::AllNotificationResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        let hidden = hidden_user_ids(&context.connection, authenticated.user_id, true).await?;
+        schemas::notifications::load_notifications(
+            &context.connection,
+            authenticated.user_id,
+            &hidden,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "number of unread notifications")]
+    async fn unread_notifications_count(jwt: String, context: &Context) -> AppResult<i32> {
+        let authenticated = context.require_auth(jwt).await?;
+        let hidden = hidden_user_ids(&context.connection, authenticated.user_id, true).await?;
+        schemas::notifications::count_unread(&context.connection, authenticated.user_id, &hidden)
+            .await
+    }
+
+    #[graphql(description = "data exports the user asked for, newest first")]
+    async fn my_data_exports(
+        jwt: String,
+        context: &Context,
+    ) -> AppResult<Vec<schemas::exports::DataExportDetails>> {
+        let authenticated = context.require_auth(jwt).await?;
+        schemas::exports::load_data_exports(&context.connection, authenticated.user_id).await
+    }
+
+    #[graphql(description = "reports waiting for a moderator, oldest first, moderators only")]
+    async fn moderation_queue(
+        jwt: String,
+        status: Option<ReportStatus>,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::reports::AllReportResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        require_moderator(&context.connection, authenticated.user_id).await?;
+        schemas::reports::load_reports(
+            &context.connection,
+            status.unwrap_or(ReportStatus::Open),
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "what moderators did and why, newest first, moderators only")]
+    async fn moderation_log(
+        jwt: String,
+        user_id: Option<ID>,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::reports::AllModerationActionResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        require_moderator(&context.connection, authenticated.user_id).await?;
+        let user_id = match user_id {
+            Some(user_id) => Some(parse_id(&user_id)?),
+            None => None,
         };
+        schemas::reports::load_moderation_actions(
+            &context.connection,
+            user_id,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "direct conversations of the user, most recently active first")]
+    async fn my_conversations(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::messages::AllConversationResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        schemas::messages::load_conversations(
+            &context.connection,
+            authenticated.user_id,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "messages of a direct conversation, newest first")]
+    async fn conversation_messages(
+        jwt: String,
+        conversation_id: ID,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::messages::AllMessageResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        let conversation_id = parse_id(&conversation_id)?;
+        schemas::messages::require_member(
+            &context.connection,
+            conversation_id,
+            authenticated.user_id,
+        )
+        .await?;
+
+        schemas::messages::load_messages(
+            &context.connection,
+            conversation_id,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "follow requests waiting for the user to accept or reject them")]
+    async fn pending_follow_requests(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::follow_requests::AllFollowRequestResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        schemas::follow_requests::load_follow_requests(
+            &context.connection,
+            authenticated.user_id,
+            true,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "follow requests the user sent that are still pending")]
+    async fn sent_follow_requests(
+        jwt: String,
+        page_size: i32,
+        page_number: i32,
+        context: &Context,
+    ) -> AppResult<schemas::follow_requests::AllFollowRequestResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        schemas::follow_requests::load_follow_requests(
+            &context.connection,
+            authenticated.user_id,
+            false,
+            page_size,
+            page_number,
+        )
+        .await
+    }
+
+    #[graphql(description = "users the user blocked")]
+    async fn blocked_users(
+        jwt: String,
+        context: &Context,
+    ) -> AppResult<Vec<schemas::users::UserDetails>> {
+        let authenticated = context.require_auth(jwt).await?;
+
+        let blocked_ids = entity::blocks::Entity::find()
+            .filter(entity::blocks::Column::BlockerId.eq(authenticated.user_id))
+            .order_by(entity::blocks::Column::CreatedAt, Order::Desc)
+            .all(&context.connection)
+            .await?
+            .into_iter()
+            .map(|block| block.blocked_id)
+            .collect();
+
+        schemas::users::load_users(context, blocked_ids).await
+    }
+
+    #[graphql(description = "users the user muted")]
+    async fn muted_users(
+        jwt: String,
+        context: &Context,
+    ) -> AppResult<Vec<schemas::users::UserDetails>> {
+        let authenticated = context.require_auth(jwt).await?;
+
+        let muted_ids = entity::mutes::Entity::find()
+            .filter(entity::mutes::Column::MuterId.eq(authenticated.user_id))
+            .order_by(entity::mutes::Column::CreatedAt, Order::Desc)
+            .all(&context.connection)
+            .await?
+            .into_iter()
+            .map(|mute| mute.muted_id)
+            .collect();
+
+        schemas::users::load_users(context, muted_ids).await
     }
 
     async fn get_buzz_details(
-        id: String,
+        id: ID,
+        jwt: Option<String>,
         context: &Context,
-    ) -> FieldResult<schemas::buzz::BuzzResult> {
+    ) -> AppResult<schemas::buzz::BuzzResult> {
         let connection = &context.connection;
+        context.optional_auth(jwt).await?;
 
         let buzz = entity::buzz::Entity::find()
-            .filter(entity::buzz::Column::Id.eq(id.parse::<i64>().unwrap()))
+            .filter(entity::buzz::Column::Id.eq(parse_id(&id)?))
             .one(connection)
-            .await;
-
-        return match buzz {
-            Ok(buzz) => match buzz {
-                Some(buzz) => Ok(schemas::buzz::BuzzResult {
-                    id: buzz.id.to_string(),
-                    user_id: buzz.user_id.to_string(),
-                    description: buzz.description.to_string(),
-                    image_link: buzz.image_link,
-                    video_link: buzz.video_link,
-                    buzz_words: buzz.buzz_words,
-                    mentioned_users: buzz.mentioned_users,
-                    ratings_id: Some(buzz.ratings_id.unwrap_or(-1).to_string()),
-                    created_at: buzz.created_at,
-                }),
+            .await?;
 
-                None => Err(FieldError::new("Buzz not found", juniper::Value::Null)),
-            },
-
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        };
+        let blocked = context.blocked_user_ids().await?;
+        match buzz.filter(|buzz| !blocked.contains(&buzz.user_id)) {
+            Some(buzz) if context.is_locked(buzz.user_id).await? => {
+                Err(AppError::Forbidden("Account is private"))
+            }
+            Some(buzz) => Ok(schemas::buzz::BuzzResult::from(buzz)),
+            None => Err(AppError::NotFound("Buzz")),
+        }
     }
 
     async fn get_replies(
         page_details: schemas::reply::GetAllRepliesInput,
+        jwt: Option<String>,
         context: &Context,
-    ) -> FieldResult<schemas::reply::AllReplyResult> {
+    ) -> AppResult<schemas::reply::AllReplyResult> {
         let connection = &context.connection;
+        context.optional_auth(jwt).await?;
+        check_page(page_details.page_size, page_details.page_number)?;
+
+        let buzz = entity::buzz::Entity::find_by_id(parse_id(&page_details.buzz_id)?)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Buzz"))?;
+        if context.is_locked(buzz.user_id).await? {
+            return Err(AppError::Forbidden("Account is private"));
+        }
 
         let paginated_posts = entity::reply::Entity::find()
-            .filter(entity::reply::Column::BuzzId.eq(page_details.buzz_id.parse::<i64>().unwrap()))
+            .filter(entity::reply::Column::BuzzId.eq(buzz.id))
+            .filter(
+                entity::reply::Column::UserId.is_not_in(context.blocked_user_ids().await?.to_vec()),
+            )
+            .filter(not_locked("reply.user_id", context.viewer_id()))
             .order_by(entity::reply::Column::CreatedAt, Order::Desc)
             .paginate(connection, page_details.page_size as usize);
 
-        let total_pages = paginated_posts.num_pages().await.unwrap() as i32;
-        let total_replies = paginated_posts.num_items().await.unwrap() as i32;
+        let total_pages = paginated_posts.num_pages().await? as i32;
+        let total_replies = paginated_posts.num_items().await? as i32;
 
-        let fetch_replies: Result<Vec<entity::reply::Model>, DbErr> = paginated_posts
+        let replies = paginated_posts
             .fetch_page((page_details.page_number - 1) as usize)
-            .await;
-
-        return match fetch_replies {
-            Ok(replies) => {
-                let return_replies = replies
-                    .into_iter()
-                    .map(|reply| schemas::reply::ReplyResult {
-                        id: reply.id.to_string(),
-                        buzz_id: reply.buzz_id.to_string(),
-                        reply_content: "".to_string(),
-                        buzz_words: None,
-                        mentioned_users: None,
-                        ratings_id: None,
-                        user_id: reply.user_id.to_string(),
-                        created_at: reply.created_at,
-                    })
-                    .collect();
-                Ok(schemas::reply::AllReplyResult {
-                    replies: return_replies,
-                    total_replies,
-                    total_pages,
-                    page_number: page_details.page_number,
-                    page_size: page_details.page_size,
-                })
-            }
+            .await?;
+
+        let return_replies = replies
+            .into_iter()
+            .map(schemas::reply::ReplyResult::from)
+            .collect();
+
+        Ok(schemas::reply::AllReplyResult {
+            replies: return_replies,
+            total_replies,
+            total_pages,
+            page_number: page_details.page_number,
+            page_size: page_details.page_size,
+        })
+    }
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
+    async fn get_conversation(
+        conversation: schemas::conversation::GetConversationInput,
+        jwt: Option<String>,
+        context: &Context,
+    ) -> AppResult<schemas::conversation::ConversationResult> {
+        context.optional_auth(jwt).await?;
+        // Muted users drop out of the replies, what they wrote can still be
+        // opened.
+        let hidden = match context.viewer_id() {
+            Some(viewer_id) => hidden_user_ids(&context.connection, viewer_id, true).await?,
+            None => Vec::new(),
         };
+        schemas::conversation::load_conversation(
+            &context.connection,
+            conversation,
+            context.blocked_user_ids().await?,
+            &hidden,
+            context.viewer_id(),
+        )
+        .await
     }
 
     async fn get_user_details(
-        user_id: String,
+        user_id: ID,
         context: &Context,
-    ) -> FieldResult<schemas::users::UserDetails> {
+    ) -> AppResult<schemas::users::UserDetails> {
         let connection = &context.connection;
 
         let user = entity::users::Entity::find()
-            .filter(entity::users::Column::Id.eq(user_id.parse::<i64>().unwrap()))
+            .filter(entity::users::Column::Id.eq(parse_id(&user_id)?))
             .one(connection)
-            .await;
-
-        return match user {
-            Ok(user) => match user {
-                Some(user) => Ok(schemas::users::UserDetails {
-                    id: user.id as i32,
-                    auth_id: Some(user.auth_id as i32),
-                    full_name: user.full_name.to_string(),
-                    description: user.description,
-                    profile_picture: user.profile_picture,
-                    location_or_region: user.location_or_region,
-                }),
-
-                None => Err(FieldError::new("User not found", juniper::Value::Null)),
-            },
+            .await?;
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        };
+        match user {
+            Some(user) => Ok(schemas::users::UserDetails::from(user)),
+            None => Err(AppError::NotFound("User")),
+        }
     }
 
     async fn get_auth_details(
-        auth_id: String,
+        auth_id: ID,
         jwt: Option<String>,
         context: &Context,
-    ) -> FieldResult<schemas::auth::AuthResponse> {
+    ) -> AppResult<schemas::auth::AuthResponse> {
         let connection = &context.connection;
 
-        let authentication = authenticate(jwt.unwrap_or_else(|| "".to_string())).await;
+        let authentication = authenticate(jwt.unwrap_or_default()).await;
 
         let auth = entity::auth::Entity::find()
-            .filter(entity::auth::Column::Id.eq(auth_id.parse::<i64>().unwrap()))
+            .filter(entity::auth::Column::Id.eq(parse_id(&auth_id)?))
             .one(connection)
-            .await;
-
-        return match auth {
-            Ok(user) => match user {
-                Some(user) => match authentication {
-                    Authenticated(_) => Ok(schemas::auth::AuthResponse {
-                        username: user.username.to_string(),
-                        contact_number: user.contact_number,
-                        email: user.email.to_string(),
-                    }),
-
-                    Unauthenticated => Ok(schemas::auth::AuthResponse {
-                        username: user.username.to_string(),
-                        contact_number: None,
-                        email: "".to_string(),
-                    }),
-                },
+            .await?;
+
+        match auth {
+            Some(user) => match authentication {
+                Authenticated(_) => Ok(schemas::auth::AuthResponse {
+                    username: user.username.to_string(),
+                    contact_number: user.contact_number,
+                    email: user.email.to_string(),
+                }),
 
-                None => Err(FieldError::new("User not found", juniper::Value::Null)),
+                Unauthenticated => Ok(schemas::auth::AuthResponse {
+                    username: user.username.to_string(),
+                    contact_number: None,
+                    email: "".to_string(),
+                }),
             },
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        };
+            None => Err(AppError::NotFound("User")),
+        }
     }
 
-    async fn get_user_upvotes(user_id: String, context: &Context) -> FieldResult<String> {
+    async fn get_user_upvotes(user_id: ID, context: &Context) -> AppResult<String> {
         let connection = &context.connection;
 
         let user = entity::users::Entity::find()
-            .filter(entity::users::Column::Id.eq(user_id.parse::<i64>().unwrap()))
+            .filter(entity::users::Column::Id.eq(parse_id(&user_id)?))
             .one(connection)
-            .await;
-
-        return match user {
-            Ok(user) => match user {
-                Some(user) => Ok(user.upvoted.unwrap_or_else(|| "".to_string())),
-
-                None => Err(FieldError::new("User not found", juniper::Value::Null)),
-            },
+            .await?;
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        };
+        match user {
+            Some(user) => Ok(to_id_list(upvoted_ratings_ids(connection, user.id).await?)),
+            None => Err(AppError::NotFound("User")),
+        }
     }
 
     async fn get_user_follows(
-        user_id: String,
+        user_id: ID,
         context: &Context,
-    ) -> FieldResult<schemas::users::FollowList> {
+    ) -> AppResult<schemas::users::FollowList> {
         let connection = &context.connection;
 
         let user = entity::users::Entity::find()
-            .filter(entity::users::Column::Id.eq(user_id.parse::<i64>().unwrap()))
+            .filter(entity::users::Column::Id.eq(parse_id(&user_id)?))
             .one(connection)
-            .await;
+            .await?;
+
+        match user {
+            Some(user) => Ok(schemas::users::FollowList {
+                following: to_id_list(following_ids(connection, user.id).await?),
+                followers: to_id_list(follower_ids(connection, user.id).await?),
+            }),
+            None => Err(AppError::NotFound("User")),
+        }
+    }
+}
 
-        return match user {
-            Ok(user) => match user {
-                Some(user) => Ok(schemas::users::FollowList {
-                    following: user.following.unwrap_or_else(|| "".to_string()),
-                    followers: user.followers.unwrap_or_else(|| "".to_string()),
-                }),
+/// Buzzes and replies can be edited for `EDIT_WINDOW_MINUTES` after they
+/// were created.
+fn check_edit_window(created_at: &chrono::DateTime<chrono::FixedOffset>) -> AppResult<()> {
+    let window = chrono::Duration::minutes(env_or("EDIT_WINDOW_MINUTES", 30));
+    if chrono::Utc::now().signed_duration_since(*created_at) > window {
+        return Err(AppError::Forbidden("Edit window has expired"));
+    }
+    Ok(())
+}
 
-                None => Err(FieldError::new("User not found", juniper::Value::Null)),
-            },
+fn hash_password(password: String) -> AppResult<String> {
+    let key = std::env::var("PASSWORD_SECRET_KEY")
+        .map_err(|_| AppError::Internal("PASSWORD_SECRET_KEY must be set".to_string()))?;
 
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        };
-    }
+    Hasher::default()
+        .with_password(password)
+        .with_secret_key(key)
+        .hash()
+        .map_err(|e| AppError::Internal(e.to_string()))
 }
 
 pub struct MutationRoot;
@@ -283,16 +800,8 @@ impl MutationRoot {
         user: schemas::users::UserModify,
         authentication_details: schemas::auth::AuthModify,
         context: &Context,
-    ) -> FieldResult<schemas::users::UserDetails> {
-        let connection = &context.connection;
-
-        let key = std::env::var("PASSWORD_SECRET_KEY").expect("SECRET_KEY must be set");
-
-        let password = Hasher::default()
-            .with_password(authentication_details.password)
-            .with_secret_key(key)
-            .hash()
-            .unwrap();
+    ) -> AppResult<schemas::users::UserDetails> {
+        let password = hash_password(authentication_details.password)?;
         let auth_table = entity::auth::ActiveModel {
             contact_number: Set(authentication_details.contact_number),
             email: Set(authentication_details.email),
@@ -302,98 +811,113 @@ impl MutationRoot {
             ..Default::default()
         };
 
-        let auth_insert: Result<InsertResult<entity::auth::ActiveModel>, DbErr> =
-            entity::auth::Entity::insert(auth_table)
-                .exec(connection)
-                .await;
-
-        match auth_insert {
-            Ok(auth_id) => {
-                let user_table = entity::users::ActiveModel {
-                    auth_id: Set(auth_id.last_insert_id),
-                    full_name: Set(user.full_name),
-                    profile_picture: Set(user.profile_picture),
-                    description: Set(user.description),
-                    location_or_region: Set(user.location_or_region),
-                    created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
-                    ..Default::default()
-                };
-
-                let user_table = user_table.insert(connection).await;
-
-                match user_table {
-                    Ok(user_table) => Ok(schemas::users::UserDetails {
-                        id: user_table.id as i32,
-                        auth_id: Some(user_table.auth_id as i32),
-                        full_name: user_table.full_name,
-                        description: user_table.description,
-                        profile_picture: user_table.profile_picture,
-                        location_or_region: user_table.location_or_region,
-                    }),
-
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                }
-            }
-            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-        }
+        let user_table = entity::users::ActiveModel {
+            full_name: Set(user.full_name),
+            profile_picture: Set(user.profile_picture),
+            description: Set(user.description),
+            location_or_region: Set(user.location_or_region),
+            created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+            ..Default::default()
+        };
+
+        // No `auth` row without its `users` row.
+        context
+            .transaction(
+                (auth_table, user_table),
+                |txn, (auth_table, mut user_table)| {
+                    Box::pin(async move {
+                        let auth_id = entity::auth::Entity::insert(auth_table).exec(txn).await?;
+                        user_table.auth_id = Set(auth_id.last_insert_id);
+
+                        let user = user_table.insert(txn).await?;
+                        Ok(schemas::users::UserDetails::from(user))
+                    })
+                },
+            )
+            .await
     }
 
-    #[graphql(description = "delete user")]
-    async fn delete_user(jwt: String, context: &Context) -> FieldResult<bool> {
+    #[graphql(
+        description = "schedule deletion of the user, logging in during the grace period cancels it"
+    )]
+    async fn delete_user(jwt: String, context: &Context) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-        match authentication {
-            Authenticated(authentication) => {
-                if authentication.is_one_time_jwt {
-                    let user = entity::users::Entity::find_by_id(authentication.user_id)
-                        .one(connection)
-                        .await;
-                    match user {
-                        Ok(user) => {
-                            let user = user.unwrap();
-                            let user = user.delete(connection).await;
-                            return match user {
-                                Ok(_) => {
-                                    let auth =
-                                        entity::auth::Entity::find_by_id(authentication.auth_id)
-                                            .one(connection)
-                                            .await;
-                                    match auth {
-                                        Ok(auth) => {
-                                            let auth = auth.unwrap();
-                                            let auth = auth.delete(connection).await;
-                                            match auth {
-                                                Ok(_) => Ok(true),
-                                                Err(e) => Err(FieldError::new(
-                                                    e.to_string(),
-                                                    juniper::Value::Null,
-                                                )),
-                                            }
-                                        }
-                                        Err(e) => Err(FieldError::new(
-                                            e.to_string(),
-                                            juniper::Value::Null,
-                                        )),
-                                    }
-                                }
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            };
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
+        let authentication = require_one_time_auth(jwt).await?;
 
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
+        let user = entity::users::Entity::find_by_id(authentication.user_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+
+        // The account deletion job takes it from here.
+        if user.deletion_requested_at.is_none() {
+            let mut user: entity::users::ActiveModel = user.into();
+            user.deletion_requested_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+            user.update(connection).await?;
+            record_security_event(
+                connection,
+                authentication.user_id,
+                SecurityEventKind::DeletionRequested,
+                context.client_ip(),
+            )
+            .await?;
         }
+
+        Ok(true)
+    }
+
+    #[graphql(
+        description = "ask for an archive of everything kept about the user, built in the background"
+    )]
+    async fn request_data_export(
+        jwt: String,
+        context: &Context,
+    ) -> AppResult<schemas::exports::DataExportDetails> {
+        let authenticated = require_one_time_auth(jwt).await?;
+        let client_ip = context.client_ip().map(|client_ip| client_ip.to_string());
+
+        let data_export = context
+            .transaction(
+                (authenticated.user_id, client_ip),
+                |txn, (user_id, client_ip)| {
+                    Box::pin(async move {
+                        // An archive still being built covers this request too.
+                        if let Some(pending) = entity::data_exports::Entity::find()
+                            .filter(entity::data_exports::Column::UserId.eq(user_id))
+                            .filter(
+                                entity::data_exports::Column::Status
+                                    .eq(DataExportStatus::Pending.key()),
+                            )
+                            .one(txn)
+                            .await?
+                        {
+                            return Ok(pending);
+                        }
+
+                        let data_export = entity::data_exports::ActiveModel {
+                            user_id: Set(user_id),
+                            token: Set(new_download_token()),
+                            status: Set(DataExportStatus::Pending.key().to_string()),
+                            requested_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+                            ..Default::default()
+                        }
+                        .insert(txn)
+                        .await?;
+                        record_security_event(
+                            txn,
+                            user_id,
+                            SecurityEventKind::DataExportRequested,
+                            client_ip.as_deref(),
+                        )
+                        .await?;
+
+                        Ok(data_export)
+                    })
+                },
+            )
+            .await?;
+
+        Ok(schemas::exports::DataExportDetails::from(data_export))
     }
 
     #[graphql(description = "update user")]
@@ -401,180 +925,96 @@ impl MutationRoot {
         jwt: String,
         user_modify: schemas::users::UserModify,
         context: &Context,
-    ) -> FieldResult<schemas::users::UserDetails> {
+    ) -> AppResult<schemas::users::UserDetails> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                let user = entity::users::Entity::find_by_id(authenticated.user_id)
-                    .one(connection)
-                    .await;
-                match user {
-                    Ok(user) => {
-                        let mut user: entity::users::ActiveModel = user.unwrap().into();
-
-                        user.full_name = Set(user_modify.full_name);
-                        user.description = Set(user_modify.description);
-                        user.profile_picture = Set(user_modify.profile_picture);
-                        user.location_or_region = Set(user_modify.location_or_region);
-
-                        let user: Result<entity::users::Model, DbErr> =
-                            user.update(connection).await;
-
-                        match user {
-                            Ok(user) => Ok(schemas::users::UserDetails {
-                                id: user.id as i32,
-                                auth_id: Some(user.auth_id as i32),
-                                full_name: user.full_name,
-                                description: user.description,
-                                profile_picture: user.profile_picture,
-                                location_or_region: user.location_or_region,
-                            }),
-                            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                        }
-                    }
+        let authenticated = context.require_auth(jwt).await?;
 
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let user = entity::users::Entity::find_by_id(authenticated.user_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let mut user: entity::users::ActiveModel = user.into();
+
+        user.full_name = Set(user_modify.full_name);
+        user.description = Set(user_modify.description);
+        user.profile_picture = Set(user_modify.profile_picture);
+        user.location_or_region = Set(user_modify.location_or_region);
+
+        let user = user.update(connection).await?;
+
+        Ok(schemas::users::UserDetails::from(user))
     }
 
     #[graphql(description = "change username")]
-    async fn change_username(
-        jwt: String,
-        username: String,
-        context: &Context,
-    ) -> FieldResult<bool> {
+    async fn change_username(jwt: String, username: String, context: &Context) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.is_one_time_jwt {
-                    let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
-                        .one(connection)
-                        .await;
-                    match auth {
-                        Ok(auth) => {
-                            let mut auth: entity::auth::ActiveModel = auth.unwrap().into();
-
-                            auth.username = Set(username);
-                            let auth: Result<entity::auth::Model, DbErr> =
-                                auth.update(connection).await;
-                            match auth {
-                                Ok(_) => Ok(true),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            }
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let authenticated = require_one_time_auth(jwt).await?;
+
+        let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let mut auth: entity::auth::ActiveModel = auth.into();
+
+        auth.username = Set(username);
+        auth.update(connection).await?;
+        record_security_event(
+            connection,
+            authenticated.user_id,
+            SecurityEventKind::UsernameChanged,
+            context.client_ip(),
+        )
+        .await?;
+
+        Ok(true)
     }
 
     #[graphql(description = "change password")]
-    async fn change_password(
-        jwt: String,
-        password: String,
-        context: &Context,
-    ) -> FieldResult<bool> {
+    async fn change_password(jwt: String, password: String, context: &Context) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-        let key = std::env::var("PASSWORD_SECRET_KEY").expect("SECRET_KEY must be set");
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.is_one_time_jwt {
-                    let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
-                        .one(connection)
-                        .await;
-                    match auth {
-                        Ok(auth) => {
-                            let mut auth: entity::auth::ActiveModel = auth.unwrap().into();
-
-                            let password = Hasher::default()
-                                .with_password(password)
-                                .with_secret_key(key)
-                                .hash()
-                                .unwrap();
-
-                            auth.user_password = Set(password);
-                            let auth: Result<entity::auth::Model, DbErr> =
-                                auth.update(connection).await;
-                            match auth {
-                                Ok(_) => Ok(true),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            }
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let authenticated = require_one_time_auth(jwt).await?;
+
+        let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let mut auth: entity::auth::ActiveModel = auth.into();
+
+        auth.user_password = Set(hash_password(password)?);
+        auth.update(connection).await?;
+        record_security_event(
+            connection,
+            authenticated.user_id,
+            SecurityEventKind::PasswordChanged,
+            context.client_ip(),
+        )
+        .await?;
+
+        Ok(true)
     }
 
     #[graphql(description = "change email")]
-    async fn change_email(jwt: String, email: String, context: &Context) -> FieldResult<bool> {
+    async fn change_email(jwt: String, email: String, context: &Context) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.is_one_time_jwt {
-                    let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
-                        .one(connection)
-                        .await;
-                    match auth {
-                        Ok(auth) => {
-                            let mut auth: entity::auth::ActiveModel = auth.unwrap().into();
-
-                            auth.email = Set(email);
-                            let auth: Result<entity::auth::Model, DbErr> =
-                                auth.update(connection).await;
-                            match auth {
-                                Ok(_) => Ok(true),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            }
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let authenticated = require_one_time_auth(jwt).await?;
+
+        let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let mut auth: entity::auth::ActiveModel = auth.into();
+
+        auth.email = Set(email);
+        auth.update(connection).await?;
+        record_security_event(
+            connection,
+            authenticated.user_id,
+            SecurityEventKind::EmailChanged,
+            context.client_ip(),
+        )
+        .await?;
+
+        Ok(true)
     }
 
     #[graphql(description = "change contact number")]
@@ -582,81 +1022,102 @@ impl MutationRoot {
         jwt: String,
         contact_number: String,
         context: &Context,
-    ) -> FieldResult<bool> {
+    ) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.is_one_time_jwt {
-                    let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
-                        .one(connection)
-                        .await;
-                    match auth {
-                        Ok(auth) => {
-                            let mut auth: entity::auth::ActiveModel = auth.unwrap().into();
-
-                            auth.contact_number = Set(Some(contact_number));
-                            let auth: Result<entity::auth::Model, DbErr> =
-                                auth.update(connection).await;
-                            match auth {
-                                Ok(_) => Ok(true),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            }
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let authenticated = require_one_time_auth(jwt).await?;
+
+        let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let mut auth: entity::auth::ActiveModel = auth.into();
+
+        auth.contact_number = Set(Some(contact_number));
+        auth.update(connection).await?;
+        record_security_event(
+            connection,
+            authenticated.user_id,
+            SecurityEventKind::ContactNumberChanged,
+            context.client_ip(),
+        )
+        .await?;
+
+        Ok(true)
     }
 
     #[graphql(description = "logout from all devices")]
-    async fn logout_from_all_devices(jwt: String, context: &Context) -> FieldResult<bool> {
+    async fn logout_from_all_devices(jwt: String, context: &Context) -> AppResult<bool> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.is_one_time_jwt {
-                    let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
-                        .one(connection)
-                        .await;
-                    match auth {
-                        Ok(auth) => {
-                            let mut auth: entity::auth::ActiveModel = auth.unwrap().into();
-
-                            auth.password_version = Set(auth.password_version.unwrap() + 0.1_f64);
-                            let auth: Result<entity::auth::Model, DbErr> =
-                                auth.update(connection).await;
-                            match auth {
-                                Ok(_) => Ok(true),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                            }
-                        }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Authentication Failed",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
+        let authenticated = require_one_time_auth(jwt).await?;
+
+        let auth = entity::auth::Entity::find_by_id(authenticated.auth_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        let password_version = auth.password_version;
+        let mut auth: entity::auth::ActiveModel = auth.into();
+
+        auth.password_version = Set(password_version + 0.1_f64);
+        auth.update(connection).await?;
+        record_security_event(
+            connection,
+            authenticated.user_id,
+            SecurityEventKind::LoggedOutEverywhere,
+            context.client_ip(),
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    #[graphql(description = "mark notifications up to `until` as read, all of them when omitted")]
+    async fn mark_notifications_read(
+        jwt: String,
+        until: Option<DateTimeWithTimeZone>,
+        context: &Context,
+    ) -> AppResult<i32> {
+        let authenticated = context.require_auth(jwt).await?;
+        let now: chrono::DateTime<chrono::FixedOffset> = chrono::DateTime::from(chrono::Utc::now());
+
+        let result = entity::notifications::Entity::update_many()
+            .col_expr(entity::notifications::Column::ReadAt, Expr::value(now))
+            .filter(entity::notifications::Column::UserId.eq(authenticated.user_id))
+            .filter(entity::notifications::Column::ReadAt.is_null())
+            .filter(entity::notifications::Column::CreatedAt.lte(until.unwrap_or(now)))
+            .exec(&context.connection)
+            .await?;
+
+        Ok(result.rows_affected as i32)
+    }
+
+    #[graphql(
+        description = "record that the viewer saw these buzzes or replies, seeing one again within a while counts once"
+    )]
+    async fn record_views(
+        ratings_ids: Vec<ID>,
+        jwt: Option<String>,
+        context: &Context,
+    ) -> AppResult<i32> {
+        let viewer_id = context.optional_auth(jwt).await?;
+        let max_batch: usize = env_or("VIEWS_BATCH_MAX", 100);
+        if ratings_ids.len() > max_batch {
+            return Err(AppError::BadRequest(format!(
+                "At most {} views can be recorded at once",
+                max_batch
+            )));
+        }
+        let ratings_ids = ratings_ids
+            .iter()
+            .map(parse_id)
+            .collect::<AppResult<Vec<i64>>>()?;
+
+        let viewer_key = match (viewer_id, context.client_ip()) {
+            (Some(user_id), _) => format!("user:{}", user_id),
+            (None, Some(client_ip)) => format!("ip:{}", client_ip),
+            (None, None) => return Ok(0),
         };
+
+        Ok(record_views(&context.connection, &viewer_key, &ratings_ids).await? as i32)
     }
 
     #[graphql(description = "create a buzz")]
@@ -664,472 +1125,1726 @@ impl MutationRoot {
         jwt: String,
         buzz: schemas::buzz::BuzzInput,
         context: &Context,
-    ) -> FieldResult<schemas::buzz::BuzzResult> {
-        let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
+    ) -> AppResult<schemas::buzz::BuzzResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        check_rate_limit(
+            &context.connection,
+            MutationClass::CreateBuzz,
+            authenticated.user_id,
+            context.client_ip(),
+        )
+        .await?;
+
+        if authenticated.user_id != parse_id(&buzz.user_id)? {
+            return Err(AppError::Forbidden(
+                "Cant create buzz on behalf of other users",
+            ));
+        }
 
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.user_id.to_string() == buzz.user_id {
-                    let ratings_table = entity::ratings::ActiveModel {
-                        ..Default::default()
-                    };
-
-                    let ratings_insert: Result<InsertResult<entity::ratings::ActiveModel>, DbErr> =
-                        entity::ratings::Entity::insert(ratings_table)
-                            .exec(connection)
-                            .await;
-
-                    match ratings_insert {
-                        Ok(ratings) => {
-                            let buzz_table = entity::buzz::ActiveModel {
-                                user_id: Set(buzz.user_id.to_string().parse::<i64>().unwrap()),
-                                description: Set(buzz.description),
-                                image_link: Set(buzz.image_link),
-                                video_link: Set(buzz.video_link),
-                                buzz_words: Set(buzz.buzz_words),
-                                mentioned_users: Set(buzz.mentioned_users),
-                                ratings_id: Set(Some(ratings.last_insert_id)),
-                                created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+        let quoted_buzz_id = buzz.quoted_buzz_id.as_ref().map(parse_id).transpose()?;
+        let poll = buzz
+            .poll
+            .map(|poll| {
+                check_poll(
+                    poll.options,
+                    poll.duration_minutes,
+                    poll.multiple_choice.unwrap_or(false),
+                    poll.hide_results.unwrap_or(false),
+                )
+            })
+            .transpose()?;
+        let hashtags = parse_hashtags(&buzz.description, buzz.buzz_words.as_deref());
+        let usernames = parse_mentions(&buzz.description);
+
+        let buzz_table = entity::buzz::ActiveModel {
+            user_id: Set(authenticated.user_id),
+            description: Set(buzz.description),
+            image_link: Set(buzz.image_link),
+            video_link: Set(buzz.video_link),
+            buzz_words: Set(to_buzz_words(&hashtags)),
+            created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
 
-                                ..Default::default()
-                            };
-
-                            let buzz_insert = buzz_table.insert(connection).await;
-
-                            match buzz_insert {
-                                Ok(buzz) => Ok(schemas::buzz::BuzzResult {
-                                    id: buzz.id.to_string(),
-                                    user_id: buzz.user_id.to_string(),
-                                    description: buzz.description,
-                                    image_link: buzz.image_link,
-                                    video_link: buzz.video_link,
-                                    buzz_words: buzz.buzz_words,
-                                    mentioned_users: buzz.mentioned_users,
-                                    ratings_id: Some(buzz.ratings_id.unwrap_or(-1).to_string()),
-                                    created_at: buzz.created_at,
-                                }),
-
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
+            ..Default::default()
+        };
+
+        // No `ratings` row without its buzz.
+        context
+            .transaction(
+                (buzz_table, hashtags, usernames, poll),
+                move |txn, (mut buzz_table, hashtags, usernames, poll)| {
+                    Box::pin(async move {
+                        let quoted_buzz = match quoted_buzz_id {
+                            Some(quoted_buzz_id) => {
+                                let quoted_buzz = entity::buzz::Entity::find_by_id(quoted_buzz_id)
+                                    .filter(entity::buzz::Column::DeletedAt.is_null())
+                                    .filter(entity::buzz::Column::HiddenAt.is_null())
+                                    .one(txn)
+                                    .await?
+                                    .ok_or(AppError::NotFound("Quoted buzz"))?;
+                                if is_locked(txn, quoted_buzz.user_id, Some(authenticated.user_id))
+                                    .await?
+                                {
+                                    return Err(AppError::Forbidden("Account is private"));
+                                }
+                                require_not_blocked(
+                                    txn,
+                                    authenticated.user_id,
+                                    quoted_buzz.user_id,
+                                    "Cant quote this buzz",
+                                )
+                                .await?;
+                                Some(quoted_buzz)
                             }
+                            None => None,
+                        };
+
+                        let ratings_table = entity::ratings::ActiveModel {
+                            ..Default::default()
+                        };
+
+                        let ratings = entity::ratings::Entity::insert(ratings_table)
+                            .exec(txn)
+                            .await?;
+
+                        let mentions =
+                            resolve_mentions(txn, authenticated.user_id, &usernames).await?;
+
+                        buzz_table.mentioned_users = Set(to_mentioned_users(&mentions));
+                        buzz_table.ratings_id = Set(Some(ratings.last_insert_id));
+                        buzz_table.quoted_buzz_id =
+                            Set(quoted_buzz.as_ref().map(|quoted_buzz| quoted_buzz.id));
+
+                        let buzz = buzz_table.insert(txn).await?;
+                        if let Some(poll) = poll {
+                            create_poll(txn, buzz.id, poll, buzz.created_at).await?;
+                        }
+                        save_hashtags(txn, Tagged::Buzz(buzz.id), &hashtags, buzz.created_at)
+                            .await?;
+                        let mentioned =
+                            save_mentions(txn, Tagged::Buzz(buzz.id), &mentions, buzz.created_at)
+                                .await?;
+                        notify(
+                            txn,
+                            NotificationKind::Mention,
+                            &mentioned,
+                            authenticated.user_id,
+                            Some(Tagged::Buzz(buzz.id)),
+                        )
+                        .await?;
+
+                        if let Some(quoted_buzz) = quoted_buzz {
+                            schemas::ratings::adjust_ratings_counter(
+                                txn,
+                                quoted_buzz.ratings_id,
+                                entity::ratings::Column::Quotes,
+                                1,
+                            )
+                            .await?;
                         }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                    }
-                } else {
-                    Err(FieldError::new(
-                        "Cant create buzz on behalf of other users",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
 
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+                        Ok(schemas::buzz::BuzzResult::from(buzz))
+                    })
+                },
+            )
+            .await
     }
 
     #[graphql(description = "delete buzz")]
-    async fn delete_buzz(jwt: String, buzz_id: String, context: &Context) -> FieldResult<bool> {
-        let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                let get_buzz = entity::buzz::Entity::find_by_id(buzz_id.parse::<i64>().unwrap())
-                    .one(connection)
-                    .await;
-                match get_buzz {
-                    Ok(buzz) => match buzz {
-                        Some(buzz) => {
-                            if buzz.user_id.to_string() == authenticated.user_id.to_string() {
-                                let buzz_delete = entity::buzz::Entity::delete_by_id(
-                                    buzz_id.parse::<i64>().unwrap(),
-                                )
-                                    .exec(connection)
-                                    .await;
-                                match buzz_delete {
-                                    Ok(_) => Ok(true),
-                                    Err(e) => {
-                                        Err(FieldError::new(e.to_string(), juniper::Value::Null))
-                                    }
-                                }
-                            } else {
-                                Err(FieldError::new(
-                                    "Cant delete buzz on behalf of other users",
-                                    juniper::Value::Null,
-                                ))
-                            }
+    async fn delete_buzz(jwt: String, buzz_id: ID, context: &Context) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let buzz_id = parse_id(&buzz_id)?;
+
+        // Replies keep their thread, the buzz becomes a tombstone until the
+        // purge job removes it.
+        context
+            .transaction(
+                (authenticated.user_id, buzz_id),
+                |txn, (user_id, buzz_id)| {
+                    Box::pin(async move {
+                        let buzz = entity::buzz::Entity::find_by_id(buzz_id)
+                            .filter(entity::buzz::Column::DeletedAt.is_null())
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Buzz"))?;
+
+                        if buzz.user_id != user_id {
+                            return Err(AppError::Forbidden(
+                                "Cant delete buzz on behalf of other users",
+                            ));
                         }
-                        None => Err(FieldError::new("Buzz not found", juniper::Value::Null)),
-                    },
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+
+                        tombstone_buzz(txn, buzz).await?;
+                        Ok(true)
+                    })
+                },
+            )
+            .await
     }
 
-    #[graphql(description = "create reply")]
-    async fn create_reply(
+    #[graphql(description = "bookmark a buzz, or remove the bookmark")]
+    async fn toggle_bookmark(
         jwt: String,
-        reply: schemas::reply::ReplyInput,
+        buzz_id: ID,
         context: &Context,
-    ) -> FieldResult<schemas::reply::ReplyResult> {
+    ) -> AppResult<schemas::buzz::BookmarkResponse> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
+        let authenticated = context.require_auth(jwt).await?;
 
-        return match authentication {
-            Authenticated(authenticated) => {
-                if authenticated.user_id.to_string() == reply.user_id {
-                    let ratings_table = entity::ratings::ActiveModel {
-                        ..Default::default()
-                    };
+        let buzz = entity::buzz::Entity::find_by_id(parse_id(&buzz_id)?)
+            .filter(entity::buzz::Column::DeletedAt.is_null())
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Buzz"))?;
+        if is_locked(connection, buzz.user_id, Some(authenticated.user_id)).await? {
+            return Err(AppError::Forbidden("Account is private"));
+        }
+
+        let is_bookmarked = context
+            .transaction(buzz.id, move |txn, buzz_id| {
+                Box::pin(async move {
+                    let bookmark = entity::bookmarks::Entity::find()
+                        .filter(entity::bookmarks::Column::UserId.eq(authenticated.user_id))
+                        .filter(entity::bookmarks::Column::BuzzId.eq(buzz_id))
+                        .one(txn)
+                        .await?;
 
-                    let ratings_insert: Result<InsertResult<entity::ratings::ActiveModel>, DbErr> =
-                        entity::ratings::Entity::insert(ratings_table)
-                            .exec(connection)
-                            .await;
+                    let is_bookmarked = bookmark.is_none();
 
-                    match ratings_insert {
-                        Ok(ratings) => {
-                            let reply_table = entity::reply::ActiveModel {
+                    match bookmark {
+                        Some(bookmark) => {
+                            bookmark.delete(txn).await?;
+                        }
+                        None => {
+                            entity::bookmarks::ActiveModel {
                                 user_id: Set(authenticated.user_id),
-                                buzz_id: Set(reply.buzz_id.parse().unwrap()),
-                                reply_content: Set(reply.reply_content),
-                                buzz_words: Set(reply.buzz_words),
-                                mentioned_users: Set(reply.mentioned_users),
-                                ratings_id: Set(Some(ratings.last_insert_id)),
+                                buzz_id: Set(buzz_id),
                                 created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
                                 ..Default::default()
-                            };
-
-                            let reply_insert = reply_table.insert(connection).await;
-
-                            match reply_insert {
-                                Ok(reply) => Ok(schemas::reply::ReplyResult {
-                                    id: reply.id.to_string(),
-                                    user_id: reply.user_id.to_string(),
-                                    buzz_id: reply.buzz_id.to_string(),
-                                    reply_content: reply.reply_content,
-                                    buzz_words: reply.buzz_words,
-                                    mentioned_users: reply.mentioned_users,
-                                    ratings_id: Some(reply.ratings_id.unwrap_or(-1).to_string()),
-                                    created_at: reply.created_at,
-                                }),
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
                             }
+                            .insert(txn)
+                            .await?;
                         }
-                        Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
                     }
-                } else {
-                    Err(FieldError::new(
-                        "Cant create reply on behalf of other users",
-                        juniper::Value::Null,
-                    ))
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+
+                    Ok(is_bookmarked)
+                })
+            })
+            .await?;
+
+        Ok(schemas::buzz::BookmarkResponse {
+            id: buzz_id,
+            is_bookmarked,
+        })
     }
 
-    #[graphql(description = "delete reply")]
-    async fn delete_reply(jwt: String, reply_id: String, context: &Context) -> FieldResult<bool> {
-        let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                let get_reply = entity::reply::Entity::find_by_id(reply_id.parse::<i64>().unwrap())
-                    .one(connection)
-                    .await;
-                match get_reply {
-                    Ok(reply) => match reply {
-                        Some(reply) => {
-                            if reply.user_id.to_string() == authenticated.user_id.to_string() {
-                                let reply_delete = entity::reply::Entity::delete_by_id(
-                                    reply_id.parse::<i64>().unwrap(),
-                                )
-                                    .exec(connection)
-                                    .await;
-                                match reply_delete {
-                                    Ok(_) => Ok(true),
-                                    Err(e) => {
-                                        Err(FieldError::new(e.to_string(), juniper::Value::Null))
-                                    }
+    #[graphql(description = "rebuzz a buzz, or undo the rebuzz")]
+    async fn rebuzz(
+        jwt: String,
+        buzz_id: ID,
+        context: &Context,
+    ) -> AppResult<schemas::buzz::RebuzzResponse> {
+        let authenticated = context.require_auth(jwt).await?;
+
+        let is_rebuzzed = context
+            .transaction(
+                (authenticated.user_id, parse_id(&buzz_id)?),
+                |txn, (user_id, buzz_id)| {
+                    Box::pin(async move {
+                        let buzz = entity::buzz::Entity::find_by_id(buzz_id)
+                            .filter(entity::buzz::Column::DeletedAt.is_null())
+                            .filter(entity::buzz::Column::HiddenAt.is_null())
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Buzz"))?;
+
+                        if buzz.user_id == user_id {
+                            return Err(AppError::BadRequest(
+                                "Cant rebuzz your own buzz".to_string(),
+                            ));
+                        }
+
+                        Ok(toggle_rebuzz(txn, user_id, &buzz).await?)
+                    })
+                },
+            )
+            .await?;
+
+        Ok(schemas::buzz::RebuzzResponse {
+            id: buzz_id,
+            is_rebuzzed,
+        })
+    }
+
+    #[graphql(description = "edit buzz, the previous version is kept as a revision")]
+    async fn update_buzz(
+        jwt: String,
+        buzz_id: ID,
+        buzz: schemas::buzz::BuzzUpdateInput,
+        context: &Context,
+    ) -> AppResult<schemas::buzz::BuzzResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        let buzz_id = parse_id(&buzz_id)?;
+        let hashtags = parse_hashtags(&buzz.description, buzz.buzz_words.as_deref());
+        let usernames = parse_mentions(&buzz.description);
+
+        context
+            .transaction(
+                (buzz, hashtags, usernames),
+                move |txn, (buzz, hashtags, usernames)| {
+                    Box::pin(async move {
+                        let user_id = authenticated.user_id;
+                        let previous = entity::buzz::Entity::find_by_id(buzz_id)
+                            .filter(entity::buzz::Column::DeletedAt.is_null())
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Buzz"))?;
+
+                        if previous.user_id != user_id {
+                            return Err(AppError::Forbidden(
+                                "Cant edit buzz on behalf of other users",
+                            ));
+                        }
+                        check_edit_window(&previous.created_at)?;
+
+                        let now: chrono::DateTime<chrono::FixedOffset> =
+                            chrono::DateTime::from(chrono::Utc::now());
+
+                        entity::buzz_revisions::ActiveModel {
+                            buzz_id: Set(Some(previous.id)),
+                            content: Set(previous.description.clone()),
+                            image_link: Set(previous.image_link.clone()),
+                            video_link: Set(previous.video_link.clone()),
+                            buzz_words: Set(previous.buzz_words.clone()),
+                            mentioned_users: Set(previous.mentioned_users.clone()),
+                            created_at: Set(previous.edited_at.unwrap_or(previous.created_at)),
+                            revised_at: Set(now),
+                            ..Default::default()
+                        }
+                        .insert(txn)
+                        .await?;
+
+                        save_hashtags(
+                            txn,
+                            Tagged::Buzz(previous.id),
+                            &hashtags,
+                            previous.created_at,
+                        )
+                        .await?;
+                        let mentions = resolve_mentions(txn, user_id, &usernames).await?;
+                        let mentioned = save_mentions(
+                            txn,
+                            Tagged::Buzz(previous.id),
+                            &mentions,
+                            previous.created_at,
+                        )
+                        .await?;
+                        notify(
+                            txn,
+                            NotificationKind::Mention,
+                            &mentioned,
+                            user_id,
+                            Some(Tagged::Buzz(previous.id)),
+                        )
+                        .await?;
+
+                        let mut buzz_table: entity::buzz::ActiveModel = previous.into();
+                        buzz_table.description = Set(buzz.description);
+                        buzz_table.image_link = Set(buzz.image_link);
+                        buzz_table.video_link = Set(buzz.video_link);
+                        buzz_table.buzz_words = Set(to_buzz_words(&hashtags));
+                        buzz_table.mentioned_users = Set(to_mentioned_users(&mentions));
+                        buzz_table.edited_at = Set(Some(now));
+
+                        let buzz = buzz_table.update(txn).await?;
+                        Ok(schemas::buzz::BuzzResult::from(buzz))
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "create reply")]
+    async fn create_reply(
+        jwt: String,
+        reply: schemas::reply::ReplyInput,
+        context: &Context,
+    ) -> AppResult<schemas::reply::ReplyResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        check_rate_limit(
+            &context.connection,
+            MutationClass::CreateReply,
+            authenticated.user_id,
+            context.client_ip(),
+        )
+        .await?;
+
+        if authenticated.user_id != parse_id(&reply.user_id)? {
+            return Err(AppError::Forbidden(
+                "Cant create reply on behalf of other users",
+            ));
+        }
+        let buzz_id = parse_id(&reply.buzz_id)?;
+        let parent_reply_id = reply.parent_reply_id.as_ref().map(parse_id).transpose()?;
+        let hashtags = parse_hashtags(&reply.reply_content, reply.buzz_words.as_deref());
+        let usernames = parse_mentions(&reply.reply_content);
+
+        // No `ratings` row without its reply.
+        context
+            .transaction(
+                (reply.reply_content, hashtags, usernames),
+                move |txn, (reply_content, hashtags, usernames)| {
+                    Box::pin(async move {
+                        let user_id = authenticated.user_id;
+                        let buzz = entity::buzz::Entity::find_by_id(buzz_id)
+                            .filter(entity::buzz::Column::DeletedAt.is_null())
+                            .filter(entity::buzz::Column::HiddenAt.is_null())
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Buzz"))?;
+
+                        let parent_reply = match parent_reply_id {
+                            Some(parent_reply_id) => {
+                                let parent = entity::reply::Entity::find_by_id(parent_reply_id)
+                                    .filter(entity::reply::Column::DeletedAt.is_null())
+                                    .one(txn)
+                                    .await?
+                                    .ok_or(AppError::NotFound("Parent reply"))?;
+                                if parent.buzz_id != buzz.id {
+                                    return Err(AppError::BadRequest(
+                                        "Parent reply belongs to another buzz".to_string(),
+                                    ));
                                 }
-                            } else {
-                                Err(FieldError::new(
-                                    "Cant delete reply on behalf of other users",
-                                    juniper::Value::Null,
-                                ))
+                                Some(parent)
                             }
+                            None => None,
+                        };
+
+                        require_not_blocked(txn, user_id, buzz.user_id, "Cant reply to this user")
+                            .await?;
+                        if let Some(parent) = &parent_reply {
+                            require_not_blocked(
+                                txn,
+                                user_id,
+                                parent.user_id,
+                                "Cant reply to this user",
+                            )
+                            .await?;
                         }
-                        None => Err(FieldError::new("Reply not found", juniper::Value::Null)),
-                    },
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
+
+                        let ratings_table = entity::ratings::ActiveModel {
+                            ..Default::default()
+                        };
+
+                        let ratings = entity::ratings::Entity::insert(ratings_table)
+                            .exec(txn)
+                            .await?;
+
+                        let mentions = resolve_mentions(txn, user_id, &usernames).await?;
+
+                        let reply_table = entity::reply::ActiveModel {
+                            user_id: Set(user_id),
+                            buzz_id: Set(buzz.id),
+                            parent_reply_id: Set(parent_reply.as_ref().map(|parent| parent.id)),
+                            reply_content: Set(reply_content),
+                            buzz_words: Set(to_buzz_words(&hashtags)),
+                            mentioned_users: Set(to_mentioned_users(&mentions)),
+                            ratings_id: Set(Some(ratings.last_insert_id)),
+                            created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+                            ..Default::default()
+                        };
+
+                        let reply = reply_table.insert(txn).await?;
+                        save_hashtags(txn, Tagged::Reply(reply.id), &hashtags, reply.created_at)
+                            .await?;
+                        let mut mentioned = save_mentions(
+                            txn,
+                            Tagged::Reply(reply.id),
+                            &mentions,
+                            reply.created_at,
+                        )
+                        .await?;
+
+                        let mut replied_to = vec![buzz.user_id];
+                        replied_to.extend(parent_reply.map(|parent| parent.user_id));
+                        notify(
+                            txn,
+                            NotificationKind::Reply,
+                            &replied_to,
+                            user_id,
+                            Some(Tagged::Reply(reply.id)),
+                        )
+                        .await?;
+                        mentioned.retain(|user_id| !replied_to.contains(user_id));
+                        notify(
+                            txn,
+                            NotificationKind::Mention,
+                            &mentioned,
+                            user_id,
+                            Some(Tagged::Reply(reply.id)),
+                        )
+                        .await?;
+
+                        Ok(schemas::reply::ReplyResult::from(reply))
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "report a buzz to the moderators")]
+    async fn report_buzz(
+        jwt: String,
+        buzz_id: ID,
+        reason: ReportReason,
+        details: Option<String>,
+        context: &Context,
+    ) -> AppResult<schemas::reports::ReportDetails> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+
+        let buzz = entity::buzz::Entity::find_by_id(parse_id(&buzz_id)?)
+            .filter(entity::buzz::Column::DeletedAt.is_null())
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Buzz"))?;
+        if buzz.user_id == authenticated.user_id {
+            return Err(AppError::BadRequest(
+                "Cant report your own buzz".to_string(),
+            ));
+        }
+
+        let target = ModerationTarget::Buzz {
+            buzz_id: buzz.id,
+            user_id: buzz.user_id,
         };
+        let report = file_report(
+            connection,
+            authenticated.user_id,
+            target,
+            reason,
+            details,
+            Some(buzz.description),
+        )
+        .await?;
+
+        Ok(schemas::reports::ReportDetails::from(report))
     }
 
-    #[graphql(description = "upvote buzz/reply")]
-    async fn upvote(
+    #[graphql(description = "report a reply to the moderators")]
+    async fn report_reply(
         jwt: String,
-        ratings_id: String,
+        reply_id: ID,
+        reason: ReportReason,
+        details: Option<String>,
         context: &Context,
-    ) -> FieldResult<schemas::ratings::UpvoteResponse> {
+    ) -> AppResult<schemas::reports::ReportDetails> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        return match authentication {
-            Authenticated(authenticated) => {
-                let get_ratings =
-                    entity::ratings::Entity::find_by_id(ratings_id.parse::<i64>().unwrap())
-                        .one(connection)
-                        .await;
-
-                match get_ratings {
-                    Ok(upvote) => match upvote {
-                        Some(upvote) => {
-                            let mut ratings_table: entity::ratings::ActiveModel = upvote.into();
-                            let users = ratings_table
-                                .get(entity::ratings::Column::UpvotedBy)
-                                .into_value()
-                                .unwrap()
-                                .unwrap::<Option<String>>();
-
-                            let mut user_table: entity::users::ActiveModel =
-                                entity::users::Entity::find_by_id(authenticated.user_id)
-                                    .one(connection)
-                                    .await
-                                    .unwrap()
-                                    .unwrap()
-                                    .into();
-
-                            let upvoted_by_set = convert_string_to_set(
-                                user_table
-                                    .get(entity::users::Column::Upvoted)
-                                    .into_value()
-                                    .unwrap()
-                                    .unwrap::<Option<String>>()
-                                    .unwrap_or_else(|| "".to_string()),
-                            );
-
-                            let mut finally_is_it_upvote: bool = false;
-
-                            let mut add_user_to_upvoted_by =
-                                |mut user_details: std::collections::HashSet<String>,
-                                 value: i64| {
-                                    if value == 1 {
-                                        user_details.insert(authenticated.user_id.to_string());
-                                        finally_is_it_upvote = true;
-                                    } else {
-                                        user_details.remove(&authenticated.user_id.to_string());
-                                        finally_is_it_upvote = false;
-                                    }
+        let authenticated = context.require_auth(jwt).await?;
 
-                                    ratings_table.upvotes = Set(Some(
-                                        ratings_table
-                                            .get(entity::ratings::Column::Upvotes)
-                                            .into_value()
-                                            .unwrap()
-                                            .unwrap::<i64>()
-                                            + value,
-                                    ));
-                                    ratings_table.upvoted_by =
-                                        Set(Some(convert_set_to_string(user_details)));
-                                };
+        let reply = entity::reply::Entity::find_by_id(parse_id(&reply_id)?)
+            .filter(entity::reply::Column::DeletedAt.is_null())
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Reply"))?;
+        if reply.user_id == authenticated.user_id {
+            return Err(AppError::BadRequest(
+                "Cant report your own reply".to_string(),
+            ));
+        }
 
-                            let mut add_ratings_id_to_upvoting_users =
-                                |mut user_details: std::collections::HashSet<String>,
-                                 value: i64| {
-                                    if value == 1 {
-                                        user_details.insert(ratings_id.to_string());
-                                    } else {
-                                        user_details.remove(&ratings_id.to_string());
-                                    }
-                                    user_table.upvoted =
-                                        Set(Some(convert_set_to_string(user_details)));
-                                };
+        let target = ModerationTarget::Reply {
+            reply_id: reply.id,
+            user_id: reply.user_id,
+        };
+        let report = file_report(
+            connection,
+            authenticated.user_id,
+            target,
+            reason,
+            details,
+            Some(reply.reply_content),
+        )
+        .await?;
+
+        Ok(schemas::reports::ReportDetails::from(report))
+    }
 
-                            match users {
-                                Some(users) => {
-                                    let users_set: std::collections::HashSet<String> =
-                                        convert_string_to_set(users);
-
-                                    if users_set.contains(&authenticated.user_id.to_string()) {
-                                        add_user_to_upvoted_by(users_set, -1);
-                                        add_ratings_id_to_upvoting_users(upvoted_by_set, -1);
-                                    } else {
-                                        add_user_to_upvoted_by(users_set, 1);
-                                        add_ratings_id_to_upvoting_users(upvoted_by_set, 1);
-                                    }
-                                }
+    #[graphql(description = "report a user to the moderators")]
+    async fn report_user(
+        jwt: String,
+        user_id: ID,
+        reason: ReportReason,
+        details: Option<String>,
+        context: &Context,
+    ) -> AppResult<schemas::reports::ReportDetails> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+
+        let user = entity::users::Entity::find_by_id(parse_id(&user_id)?)
+            .filter(entity::users::Column::DeletedAt.is_null())
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+        if user.id == authenticated.user_id {
+            return Err(AppError::BadRequest("Cant report yourself".to_string()));
+        }
 
-                                None => {
-                                    let users_set: std::collections::HashSet<String> =
-                                        std::collections::HashSet::new();
+        // The profile as it read, for impersonation and the like.
+        let profile = match &user.description {
+            Some(description) => format!("{}\n{}", user.full_name, description),
+            None => user.full_name.clone(),
+        };
+        let report = file_report(
+            connection,
+            authenticated.user_id,
+            ModerationTarget::User { user_id: user.id },
+            reason,
+            details,
+            Some(profile),
+        )
+        .await?;
+
+        Ok(schemas::reports::ReportDetails::from(report))
+    }
 
-                                    add_user_to_upvoted_by(users_set, 1);
-                                    add_ratings_id_to_upvoting_users(upvoted_by_set, 1);
+    #[graphql(description = "hide, show again or remove a buzz, moderators only")]
+    async fn moderate_buzz(
+        jwt: String,
+        buzz_id: ID,
+        action: ContentAction,
+        reason: String,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let buzz_id = parse_id(&buzz_id)?;
+        let reason = check_reason(&reason)?;
+
+        context
+            .transaction(
+                (authenticated.user_id, buzz_id, action, reason),
+                |txn, (moderator_id, buzz_id, action, reason)| {
+                    Box::pin(async move {
+                        require_moderator(txn, moderator_id).await?;
+
+                        let buzz = entity::buzz::Entity::find_by_id(buzz_id)
+                            .filter(entity::buzz::Column::DeletedAt.is_null())
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Buzz"))?;
+                        let target = ModerationTarget::Buzz {
+                            buzz_id,
+                            user_id: buzz.user_id,
+                        };
+
+                        let kind = match action {
+                            ContentAction::Hide | ContentAction::Unhide => {
+                                let hidden_at = match action {
+                                    ContentAction::Hide => {
+                                        Some(chrono::DateTime::from(chrono::Utc::now()))
+                                    }
+                                    _ => None,
+                                };
+                                let mut buzz: entity::buzz::ActiveModel = buzz.into();
+                                buzz.hidden_at = Set(hidden_at);
+                                buzz.update(txn).await?;
+                                match action {
+                                    ContentAction::Hide => ModerationActionKind::HideBuzz,
+                                    _ => ModerationActionKind::UnhideBuzz,
                                 }
                             }
+                            ContentAction::Remove => {
+                                tombstone_buzz(txn, buzz).await?;
+                                ModerationActionKind::RemoveBuzz
+                            }
+                        };
+
+                        if action != ContentAction::Unhide {
+                            resolve_reports(txn, moderator_id, target).await?;
+                        }
+                        record_moderation_action(txn, moderator_id, kind, target, None, reason)
+                            .await?;
+
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
 
-                            let ratings_update = ratings_table.update(connection).await;
-                            let user_update = user_table.update(connection).await;
-                            match ratings_update {
-                                Ok(_) => match user_update {
-                                    Ok(_) => Ok(schemas::ratings::UpvoteResponse {
-                                        is_upvoted: finally_is_it_upvote,
-                                        id: ratings_id,
-                                    }),
-                                    Err(e) => {
-                                        Err(FieldError::new(e.to_string(), juniper::Value::Null))
+    #[graphql(description = "hide, show again or remove a reply, moderators only")]
+    async fn moderate_reply(
+        jwt: String,
+        reply_id: ID,
+        action: ContentAction,
+        reason: String,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let reply_id = parse_id(&reply_id)?;
+        let reason = check_reason(&reason)?;
+
+        context
+            .transaction(
+                (authenticated.user_id, reply_id, action, reason),
+                |txn, (moderator_id, reply_id, action, reason)| {
+                    Box::pin(async move {
+                        require_moderator(txn, moderator_id).await?;
+
+                        let reply = entity::reply::Entity::find_by_id(reply_id)
+                            .filter(entity::reply::Column::DeletedAt.is_null())
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Reply"))?;
+                        let target = ModerationTarget::Reply {
+                            reply_id,
+                            user_id: reply.user_id,
+                        };
+
+                        let kind = match action {
+                            ContentAction::Hide | ContentAction::Unhide => {
+                                let hidden_at = match action {
+                                    ContentAction::Hide => {
+                                        Some(chrono::DateTime::from(chrono::Utc::now()))
                                     }
-                                },
-                                Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
+                                    _ => None,
+                                };
+                                let mut reply: entity::reply::ActiveModel = reply.into();
+                                reply.hidden_at = Set(hidden_at);
+                                reply.update(txn).await?;
+                                match action {
+                                    ContentAction::Hide => ModerationActionKind::HideReply,
+                                    _ => ModerationActionKind::UnhideReply,
+                                }
+                            }
+                            // Like `deleteReply`, the replies below it stay.
+                            ContentAction::Remove => {
+                                tombstone_reply(txn, reply).await?;
+                                ModerationActionKind::RemoveReply
                             }
+                        };
+
+                        if action != ContentAction::Unhide {
+                            resolve_reports(txn, moderator_id, target).await?;
                         }
-                        None => Err(FieldError::new("Upvote not found", juniper::Value::Null)),
-                    },
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                }
-            }
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+                        record_moderation_action(txn, moderator_id, kind, target, None, reason)
+                            .await?;
+
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(
+        description = "suspend a user for `days`, or until lifted when omitted, moderators only"
+    )]
+    async fn suspend_user(
+        jwt: String,
+        user_id: ID,
+        reason: String,
+        days: Option<i32>,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let user_id = parse_id(&user_id)?;
+        let reason = check_reason(&reason)?;
+        if user_id == authenticated.user_id {
+            return Err(AppError::BadRequest("Cant suspend yourself".to_string()));
+        }
+        if days.is_some_and(|days| days < 1) {
+            return Err(AppError::BadRequest("days must be positive".to_string()));
+        }
+
+        context
+            .transaction(
+                (authenticated.user_id, user_id, reason),
+                move |txn, (moderator_id, user_id, reason)| {
+                    Box::pin(async move {
+                        require_moderator(txn, moderator_id).await?;
+
+                        let user = entity::users::Entity::find_by_id(user_id)
+                            .filter(entity::users::Column::DeletedAt.is_null())
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("User"))?;
+                        let auth_id = user.auth_id;
+
+                        let now = chrono::Utc::now();
+                        let mut user: entity::users::ActiveModel = user.into();
+                        user.suspended_at = Set(Some(chrono::DateTime::from(now)));
+                        user.suspended_until = Set(days.map(|days| {
+                            chrono::DateTime::from(now + chrono::Duration::days(days as i64))
+                        }));
+                        user.update(txn).await?;
+
+                        // Like `logoutFromAllDevices`, every session ends.
+                        if let Some(auth) =
+                            entity::auth::Entity::find_by_id(auth_id).one(txn).await?
+                        {
+                            let password_version = auth.password_version;
+                            let mut auth: entity::auth::ActiveModel = auth.into();
+                            auth.password_version = Set(password_version + 0.1_f64);
+                            auth.update(txn).await?;
+                        }
+
+                        let target = ModerationTarget::User { user_id };
+                        resolve_reports(txn, moderator_id, target).await?;
+                        record_moderation_action(
+                            txn,
+                            moderator_id,
+                            ModerationActionKind::SuspendUser,
+                            target,
+                            None,
+                            reason,
+                        )
+                        .await?;
+
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "lift the suspension of a user, moderators only")]
+    async fn unsuspend_user(
+        jwt: String,
+        user_id: ID,
+        reason: String,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let user_id = parse_id(&user_id)?;
+        let reason = check_reason(&reason)?;
+
+        context
+            .transaction(
+                (authenticated.user_id, user_id, reason),
+                |txn, (moderator_id, user_id, reason)| {
+                    Box::pin(async move {
+                        require_moderator(txn, moderator_id).await?;
+
+                        let user = entity::users::Entity::find_by_id(user_id)
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("User"))?;
+                        let mut user: entity::users::ActiveModel = user.into();
+                        user.suspended_at = Set(None);
+                        user.suspended_until = Set(None);
+                        user.update(txn).await?;
+
+                        record_moderation_action(
+                            txn,
+                            moderator_id,
+                            ModerationActionKind::UnsuspendUser,
+                            ModerationTarget::User { user_id },
+                            None,
+                            reason,
+                        )
+                        .await?;
+
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "close a report without acting on it, moderators only")]
+    async fn dismiss_report(
+        jwt: String,
+        report_id: ID,
+        reason: String,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let report_id = parse_id(&report_id)?;
+        let reason = check_reason(&reason)?;
+
+        context
+            .transaction(
+                (authenticated.user_id, report_id, reason),
+                |txn, (moderator_id, report_id, reason)| {
+                    Box::pin(async move {
+                        require_moderator(txn, moderator_id).await?;
+
+                        let report = entity::reports::Entity::find_by_id(report_id)
+                            .filter(entity::reports::Column::Status.eq(ReportStatus::Open.key()))
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Report"))?;
+                        let target = match (report.buzz_id, report.reply_id) {
+                            (Some(buzz_id), _) => ModerationTarget::Buzz {
+                                buzz_id,
+                                user_id: report.reported_user_id,
+                            },
+                            (None, Some(reply_id)) => ModerationTarget::Reply {
+                                reply_id,
+                                user_id: report.reported_user_id,
+                            },
+                            (None, None) => ModerationTarget::User {
+                                user_id: report.reported_user_id,
+                            },
+                        };
+
+                        let mut report: entity::reports::ActiveModel = report.into();
+                        report.status = Set(ReportStatus::Dismissed.key().to_string());
+                        report.resolved_by = Set(Some(moderator_id));
+                        report.resolved_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+                        report.update(txn).await?;
+
+                        record_moderation_action(
+                            txn,
+                            moderator_id,
+                            ModerationActionKind::DismissReport,
+                            target,
+                            Some(report_id),
+                            reason,
+                        )
+                        .await?;
+
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "delete reply")]
+    async fn delete_reply(jwt: String, reply_id: ID, context: &Context) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        let reply_id = parse_id(&reply_id)?;
+
+        // Replies below keep their thread, the reply becomes a tombstone
+        // until the purge job removes it.
+        context
+            .transaction(
+                (authenticated.user_id, reply_id),
+                |txn, (user_id, reply_id)| {
+                    Box::pin(async move {
+                        let reply = entity::reply::Entity::find_by_id(reply_id)
+                            .filter(entity::reply::Column::DeletedAt.is_null())
+                            .lock_exclusive()
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Reply"))?;
+
+                        if reply.user_id != user_id {
+                            return Err(AppError::Forbidden(
+                                "Cant delete reply on behalf of other users",
+                            ));
+                        }
+
+                        tombstone_reply(txn, reply).await?;
+                        Ok(true)
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "edit reply, the previous version is kept as a revision")]
+    async fn update_reply(
+        jwt: String,
+        reply_id: ID,
+        reply: schemas::reply::ReplyUpdateInput,
+        context: &Context,
+    ) -> AppResult<schemas::reply::ReplyResult> {
+        let authenticated = context.require_auth(jwt).await?;
+        let reply_id = parse_id(&reply_id)?;
+        context
+            .transaction(reply, move |txn, reply| {
+                Box::pin(async move {
+                    let previous = entity::reply::Entity::find_by_id(reply_id)
+                        .filter(entity::reply::Column::DeletedAt.is_null())
+                        .lock_exclusive()
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("Reply"))?;
+
+                    if previous.user_id != authenticated.user_id {
+                        return Err(AppError::Forbidden(
+                            "Cant edit reply on behalf of other users",
+                        ));
+                    }
+                    check_edit_window(&previous.created_at)?;
+
+                    let now: chrono::DateTime<chrono::FixedOffset> =
+                        chrono::DateTime::from(chrono::Utc::now());
+
+                    entity::buzz_revisions::ActiveModel {
+                        reply_id: Set(Some(previous.id)),
+                        content: Set(previous.reply_content.clone()),
+                        buzz_words: Set(previous.buzz_words.clone()),
+                        mentioned_users: Set(previous.mentioned_users.clone()),
+                        created_at: Set(previous.edited_at.unwrap_or(previous.created_at)),
+                        revised_at: Set(now),
+                        ..Default::default()
+                    }
+                    .insert(txn)
+                    .await?;
+
+                    let hashtags =
+                        parse_hashtags(&reply.reply_content, reply.buzz_words.as_deref());
+                    save_hashtags(
+                        txn,
+                        Tagged::Reply(previous.id),
+                        &hashtags,
+                        previous.created_at,
+                    )
+                    .await?;
+                    let mentions = resolve_mentions(
+                        txn,
+                        authenticated.user_id,
+                        &parse_mentions(&reply.reply_content),
+                    )
+                    .await?;
+                    let mentioned = save_mentions(
+                        txn,
+                        Tagged::Reply(previous.id),
+                        &mentions,
+                        previous.created_at,
+                    )
+                    .await?;
+                    notify(
+                        txn,
+                        NotificationKind::Mention,
+                        &mentioned,
+                        authenticated.user_id,
+                        Some(Tagged::Reply(previous.id)),
+                    )
+                    .await?;
+
+                    let mut reply_table: entity::reply::ActiveModel = previous.into();
+                    reply_table.reply_content = Set(reply.reply_content);
+                    reply_table.buzz_words = Set(to_buzz_words(&hashtags));
+                    reply_table.mentioned_users = Set(to_mentioned_users(&mentions));
+                    reply_table.edited_at = Set(Some(now));
+
+                    let reply = reply_table.update(txn).await?;
+
+                    Ok(schemas::reply::ReplyResult::from(reply))
+                })
+            })
+            .await
     }
 
     #[graphql(description = "upvote buzz/reply")]
-    async fn change_follow_user(
+    async fn upvote(
+        jwt: String,
+        ratings_id: ID,
+        context: &Context,
+    ) -> AppResult<schemas::ratings::UpvoteResponse> {
+        let authenticated = context.require_auth(jwt).await?;
+        check_rate_limit(
+            &context.connection,
+            MutationClass::Upvote,
+            authenticated.user_id,
+            context.client_ip(),
+        )
+        .await?;
+        context
+            .transaction(ratings_id, move |txn, ratings_id| {
+                Box::pin(async move {
+                    let ratings = entity::ratings::Entity::find_by_id(parse_id(&ratings_id)?)
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("Upvote"))?;
+
+                    entity::users::Entity::find_by_id(authenticated.user_id)
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("User"))?;
+
+                    let is_upvoted = toggle_upvote(txn, authenticated.user_id, ratings.id).await?;
+
+                    if let Some((owner_id, target)) = ratings_owner(txn, ratings.id).await? {
+                        if is_upvoted {
+                            notify(
+                                txn,
+                                NotificationKind::Upvote,
+                                &[owner_id],
+                                authenticated.user_id,
+                                Some(target),
+                            )
+                            .await?;
+                        } else {
+                            retract(
+                                txn,
+                                NotificationKind::Upvote,
+                                owner_id,
+                                authenticated.user_id,
+                                Some(target),
+                            )
+                            .await?;
+                        }
+                    }
+
+                    Ok(schemas::ratings::UpvoteResponse {
+                        is_upvoted,
+                        id: ratings_id,
+                    })
+                })
+            })
+            .await
+    }
+
+    #[graphql(description = "vote once in a poll, several options only in multiple choice polls")]
+    async fn vote_poll(
         jwt: String,
-        follow_id: String,
+        poll_id: ID,
+        option_ids: Vec<ID>,
         context: &Context,
-    ) -> FieldResult<schemas::users::FollowResponse> {
+    ) -> AppResult<schemas::polls::PollDetails> {
         let connection = &context.connection;
-        let authentication = authenticate(jwt).await;
-
-        let change_following_table = |mut following_table: entity::users::ActiveModel,
-                                      follow: bool| {
-            let followers_list = following_table
-                .get(entity::users::Column::Followers)
-                .into_value()
-                .unwrap()
-                .unwrap::<Option<String>>();
-
-            let mut followers_set: std::collections::HashSet<String> =
-                convert_string_to_set(followers_list.unwrap_or_else(|| "".to_string()));
-
-            if follow {
-                followers_set.insert(follow_id.clone());
-                following_table.followers = Set(Some(convert_set_to_string(followers_set)));
-            } else {
-                followers_set.remove(&follow_id);
-                following_table.followers = Set(Some(convert_set_to_string(followers_set)));
-            }
-            following_table
-        };
+        let authenticated = context.require_auth(jwt).await?;
+        let poll_id = parse_id(&poll_id)?;
+        let option_ids = option_ids
+            .iter()
+            .map(parse_id)
+            .collect::<AppResult<Vec<i64>>>()?;
+
+        let poll = entity::polls::Entity::find_by_id(poll_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Poll"))?;
+        let buzz = entity::buzz::Entity::find_by_id(poll.buzz_id)
+            .filter(entity::buzz::Column::DeletedAt.is_null())
+            .filter(entity::buzz::Column::HiddenAt.is_null())
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Buzz"))?;
+
+        if is_locked(connection, buzz.user_id, Some(authenticated.user_id)).await? {
+            return Err(AppError::Forbidden("Account is private"));
+        }
+        require_not_blocked(
+            connection,
+            authenticated.user_id,
+            buzz.user_id,
+            "Cant vote in this poll",
+        )
+        .await?;
+
+        let poll = context
+            .transaction(option_ids, move |txn, option_ids| {
+                Box::pin(async move {
+                    vote_poll(txn, authenticated.user_id, poll_id, &option_ids).await
+                })
+            })
+            .await?;
+
+        Ok(schemas::polls::PollDetails::new(
+            poll,
+            buzz.user_id,
+            Some(authenticated.user_id),
+        ))
+    }
+
+    #[graphql(
+        description = "follow or unfollow a user, following a private account sends a follow request"
+    )]
+    async fn change_follow_user(
+        jwt: String,
+        follow_id: ID,
+        context: &Context,
+    ) -> AppResult<schemas::users::FollowResponse> {
+        let authenticated = context.require_auth(jwt).await?;
+        check_rate_limit(
+            &context.connection,
+            MutationClass::ChangeFollowUser,
+            authenticated.user_id,
+            context.client_ip(),
+        )
+        .await?;
+        let follow_id = parse_id(&follow_id)?;
+        if follow_id == authenticated.user_id {
+            return Err(AppError::BadRequest("Cant follow yourself".to_string()));
+        }
 
-        return match authentication {
-            Authenticated(authenticated) => {
-                let get_follower = entity::users::Entity::find_by_id(authenticated.user_id)
-                    .one(connection)
-                    .await;
-
-                let get_following =
-                    entity::users::Entity::find_by_id(follow_id.parse::<i64>().unwrap())
-                        .one(connection)
-                        .await;
-
-                match get_follower {
-                    Ok(follower) => match follower {
-                        Some(follower) => match get_following {
-                            Ok(following) => match following {
-                                Some(following) => {
-                                    let mut follower_table: entity::users::ActiveModel =
-                                        follower.into();
-                                    let mut following_table: entity::users::ActiveModel =
-                                        following.into();
-                                    let following_list = follower_table
-                                        .get(entity::users::Column::Following)
-                                        .into_value()
-                                        .unwrap()
-                                        .unwrap::<Option<String>>();
-
-                                    let mut following_list_set: std::collections::HashSet<String> =
-                                        convert_string_to_set(
-                                            following_list.unwrap_or_else(|| "".to_string()),
-                                        );
-                                    if following_list_set.contains(&follow_id) {
-                                        following_list_set.remove(&follow_id);
-                                        following_table =
-                                            change_following_table(following_table, false);
-                                    } else {
-                                        following_list_set.insert(follow_id.clone());
-                                        following_table =
-                                            change_following_table(following_table, true);
+        context
+            .transaction(
+                (authenticated.user_id, follow_id),
+                |txn, (follower_id, follow_id)| {
+                    Box::pin(async move {
+                        let follower = entity::users::Entity::find_by_id(follower_id)
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Follower user"))?;
+
+                        let following = entity::users::Entity::find_by_id(follow_id)
+                            .one(txn)
+                            .await?
+                            .ok_or(AppError::NotFound("Following user"))?;
+
+                        let is_following = if is_following(txn, follower.id, following.id).await? {
+                            unfollow(txn, follower.id, following.id).await?;
+                            false
+                        } else {
+                            require_not_blocked(
+                                txn,
+                                follower.id,
+                                following.id,
+                                "Cant follow this user",
+                            )
+                            .await?;
+
+                            // Private accounts approve their followers, asking again takes
+                            // the request back.
+                            if following.is_private {
+                                let existing = entity::follow_requests::Entity::find()
+                                    .filter(
+                                        entity::follow_requests::Column::RequesterId
+                                            .eq(follower.id),
+                                    )
+                                    .filter(
+                                        entity::follow_requests::Column::TargetId.eq(following.id),
+                                    )
+                                    .one(txn)
+                                    .await?;
+                                let is_requested = existing.is_none();
+
+                                match existing {
+                                    Some(follow_request) => {
+                                        follow_request.delete(txn).await?;
+                                        retract(
+                                            txn,
+                                            NotificationKind::FollowRequest,
+                                            following.id,
+                                            follower.id,
+                                            None,
+                                        )
+                                        .await?;
                                     }
-                                    follower_table.following = Set(Some(convert_set_to_string(
-                                        following_list_set.clone(),
-                                    )));
-                                    let follower_update = follower_table.update(connection).await;
-                                    let following_update = following_table.update(connection).await;
-                                    match follower_update {
-                                        Ok(_) => match following_update {
-                                            Ok(following_model) => {
-                                                Ok(schemas::users::FollowResponse {
-                                                    following_id: following_model.id.to_string(),
-                                                    is_following: following_list_set
-                                                        .contains(&follow_id),
-                                                })
-                                            }
-                                            Err(e) => Err(FieldError::new(
-                                                e.to_string(),
-                                                juniper::Value::Null,
+                                    None => {
+                                        entity::follow_requests::ActiveModel {
+                                            requester_id: Set(follower.id),
+                                            target_id: Set(following.id),
+                                            created_at: Set(chrono::DateTime::from(
+                                                chrono::Utc::now(),
                                             )),
-                                        },
-                                        Err(e) => Err(FieldError::new(
-                                            e.to_string(),
-                                            juniper::Value::Null,
-                                        )),
+                                            ..Default::default()
+                                        }
+                                        .insert(txn)
+                                        .await?;
+                                        notify(
+                                            txn,
+                                            NotificationKind::FollowRequest,
+                                            &[following.id],
+                                            follower.id,
+                                            None,
+                                        )
+                                        .await?;
                                     }
                                 }
 
-                                None => Err(FieldError::new(
-                                    "Following user not found",
-                                    juniper::Value::Null,
-                                )),
-                            },
-                            Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
-                        },
-                        None => Err(FieldError::new(
-                            "Follower user not found",
-                            juniper::Value::Null,
-                        )),
-                    },
-                    Err(e) => Err(FieldError::new(e.to_string(), juniper::Value::Null)),
+                                return Ok(schemas::users::FollowResponse {
+                                    following_id: ID::from(following.id.to_string()),
+                                    is_following: false,
+                                    is_requested,
+                                });
+                            }
+
+                            follow(txn, follower.id, following.id).await?;
+                            true
+                        };
+
+                        if is_following {
+                            notify(
+                                txn,
+                                NotificationKind::Follow,
+                                &[following.id],
+                                follower_id,
+                                None,
+                            )
+                            .await?;
+                        } else {
+                            retract(
+                                txn,
+                                NotificationKind::Follow,
+                                following.id,
+                                follower_id,
+                                None,
+                            )
+                            .await?;
+                        }
+
+                        Ok(schemas::users::FollowResponse {
+                            following_id: ID::from(following.id.to_string()),
+                            is_following,
+                            is_requested: false,
+                        })
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "accept or reject a follow request sent to the user")]
+    async fn respond_to_follow_request(
+        jwt: String,
+        request_id: ID,
+        accept: bool,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        context
+            .transaction(parse_id(&request_id)?, move |txn, request_id| {
+                Box::pin(async move {
+                    let follow_request = entity::follow_requests::Entity::find_by_id(request_id)
+                        .one(txn)
+                        .await?
+                        .filter(|follow_request| follow_request.target_id == authenticated.user_id)
+                        .ok_or(AppError::NotFound("Follow request"))?;
+
+                    if accept {
+                        follow(txn, follow_request.requester_id, follow_request.target_id).await?;
+                    }
+
+                    retract(
+                        txn,
+                        NotificationKind::FollowRequest,
+                        follow_request.target_id,
+                        follow_request.requester_id,
+                        None,
+                    )
+                    .await?;
+                    follow_request.delete(txn).await?;
+
+                    Ok(accept)
+                })
+            })
+            .await
+    }
+
+    #[graphql(
+        description = "make the account private or public, going public accepts every pending follow request"
+    )]
+    async fn set_account_private(
+        jwt: String,
+        is_private: bool,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+        context
+            .transaction(is_private, move |txn, is_private| {
+                Box::pin(async move {
+                    let user = entity::users::Entity::find_by_id(authenticated.user_id)
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("User"))?;
+
+                    let mut user_table: entity::users::ActiveModel = user.into();
+                    user_table.is_private = Set(is_private);
+                    let user = user_table.update(txn).await?;
+
+                    if !is_private {
+                        let follow_requests = entity::follow_requests::Entity::find()
+                            .filter(entity::follow_requests::Column::TargetId.eq(user.id))
+                            .order_by(entity::follow_requests::Column::CreatedAt, Order::Asc)
+                            .all(txn)
+                            .await?;
+
+                        for follow_request in follow_requests {
+                            follow(txn, follow_request.requester_id, user.id).await?;
+                        }
+
+                        entity::follow_requests::Entity::delete_many()
+                            .filter(entity::follow_requests::Column::TargetId.eq(user.id))
+                            .exec(txn)
+                            .await?;
+                        entity::notifications::Entity::delete_many()
+                            .filter(entity::notifications::Column::UserId.eq(user.id))
+                            .filter(
+                                entity::notifications::Column::Kind
+                                    .eq(NotificationKind::FollowRequest.key()),
+                            )
+                            .exec(txn)
+                            .await?;
+                    }
+
+                    Ok(user.is_private)
+                })
+            })
+            .await
+    }
+
+    #[graphql(description = "block or unblock a user, blocking ends follows both ways")]
+    async fn block_user(
+        jwt: String,
+        user_id: ID,
+        context: &Context,
+    ) -> AppResult<schemas::users::BlockResponse> {
+        let authenticated = context.require_auth(jwt).await?;
+        let user_id = parse_id(&user_id)?;
+
+        if user_id == authenticated.user_id {
+            return Err(AppError::BadRequest("Cant block yourself".to_string()));
+        }
+
+        context
+            .transaction(user_id, move |txn, user_id| {
+                Box::pin(async move {
+                    let blocker = entity::users::Entity::find_by_id(authenticated.user_id)
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("User"))?;
+                    let blocked = entity::users::Entity::find_by_id(user_id)
+                        .one(txn)
+                        .await?
+                        .ok_or(AppError::NotFound("Blocked user"))?;
+
+                    let existing = entity::blocks::Entity::find()
+                        .filter(entity::blocks::Column::BlockerId.eq(blocker.id))
+                        .filter(entity::blocks::Column::BlockedId.eq(blocked.id))
+                        .one(txn)
+                        .await?;
+                    let is_blocked = existing.is_none();
+
+                    match existing {
+                        Some(block) => {
+                            block.delete(txn).await?;
+                        }
+                        None => {
+                            entity::blocks::ActiveModel {
+                                blocker_id: Set(blocker.id),
+                                blocked_id: Set(blocked.id),
+                                created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+                                ..Default::default()
+                            }
+                            .insert(txn)
+                            .await?;
+
+                            // Pending follow requests between the two, either way.
+                            let pair = [blocker.id, blocked.id];
+                            entity::follow_requests::Entity::delete_many()
+                                .filter(entity::follow_requests::Column::RequesterId.is_in(pair))
+                                .filter(entity::follow_requests::Column::TargetId.is_in(pair))
+                                .exec(txn)
+                                .await?;
+
+                            remove_follows_between(txn, blocker.id, blocked.id).await?;
+                        }
+                    }
+
+                    Ok(schemas::users::BlockResponse {
+                        user_id: ID::from(user_id.to_string()),
+                        is_blocked,
+                    })
+                })
+            })
+            .await
+    }
+
+    #[graphql(description = "mute or unmute a user, the muted user is not told")]
+    async fn mute_user(
+        jwt: String,
+        user_id: ID,
+        context: &Context,
+    ) -> AppResult<schemas::users::MuteResponse> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+        let user_id = parse_id(&user_id)?;
+
+        if user_id == authenticated.user_id {
+            return Err(AppError::BadRequest("Cant mute yourself".to_string()));
+        }
+
+        entity::users::Entity::find_by_id(user_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Muted user"))?;
+
+        let is_muted = context
+            .transaction(user_id, move |txn, user_id| {
+                Box::pin(async move {
+                    let existing = entity::mutes::Entity::find()
+                        .filter(entity::mutes::Column::MuterId.eq(authenticated.user_id))
+                        .filter(entity::mutes::Column::MutedId.eq(user_id))
+                        .one(txn)
+                        .await?;
+                    let is_muted = existing.is_none();
+
+                    match existing {
+                        Some(mute) => {
+                            mute.delete(txn).await?;
+                        }
+                        None => {
+                            entity::mutes::ActiveModel {
+                                muter_id: Set(authenticated.user_id),
+                                muted_id: Set(user_id),
+                                created_at: Set(chrono::DateTime::from(chrono::Utc::now())),
+                                ..Default::default()
+                            }
+                            .insert(txn)
+                            .await?;
+                        }
+                    }
+
+                    Ok(is_muted)
+                })
+            })
+            .await?;
+
+        Ok(schemas::users::MuteResponse {
+            user_id: ID::from(user_id.to_string()),
+            is_muted,
+        })
+    }
+
+    #[graphql(description = "choose who can start direct conversations with the user")]
+    async fn set_dm_policy(
+        jwt: String,
+        policy: schemas::messages::DmPolicy,
+        context: &Context,
+    ) -> AppResult<schemas::messages::DmPolicy> {
+        let authenticated = context.require_auth(jwt).await?;
+
+        let user = entity::users::Entity::find_by_id(authenticated.user_id)
+            .one(&context.connection)
+            .await?
+            .ok_or(AppError::NotFound("User"))?;
+
+        let mut user_table: entity::users::ActiveModel = user.into();
+        user_table.dm_policy = Set(policy.key().to_string());
+        user_table.update(&context.connection).await?;
+
+        Ok(policy)
+    }
+
+    #[graphql(
+        description = "start a direct conversation, an existing one to one conversation is reused"
+    )]
+    async fn start_conversation(
+        jwt: String,
+        member_ids: Vec<ID>,
+        title: Option<String>,
+        context: &Context,
+    ) -> AppResult<schemas::messages::ConversationDetails> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+
+        let mut others = Vec::new();
+        for member_id in &member_ids {
+            let member_id = parse_id(member_id)?;
+            if member_id != authenticated.user_id && !others.contains(&member_id) {
+                others.push(member_id);
+            }
+        }
+
+        if others.is_empty() {
+            return Err(AppError::BadRequest(
+                "A conversation needs at least one other member".to_string(),
+            ));
+        }
+        let max_members: usize = env_or("DM_GROUP_MAX_MEMBERS", 8);
+        if others.len() + 1 > max_members {
+            return Err(AppError::BadRequest(format!(
+                "A conversation can have at most {} members",
+                max_members
+            )));
+        }
+
+        for other in &others {
+            schemas::messages::check_can_message(connection, authenticated.user_id, *other).await?;
+        }
+
+        let is_group = others.len() > 1;
+        if !is_group {
+            let mine = entity::conversations::Entity::find()
+                .inner_join(entity::conversation_members::Entity)
+                .filter(entity::conversation_members::Column::UserId.eq(authenticated.user_id))
+                .filter(entity::conversations::Column::IsGroup.eq(false))
+                .all(connection)
+                .await?;
+            let shared = entity::conversation_members::Entity::find()
+                .filter(entity::conversation_members::Column::UserId.eq(others[0]))
+                .filter(
+                    entity::conversation_members::Column::ConversationId.is_in(
+                        mine.iter()
+                            .map(|conversation| conversation.id)
+                            .collect::<Vec<i64>>(),
+                    ),
+                )
+                .one(connection)
+                .await?;
+
+            if let Some(shared) = shared {
+                let existing = mine
+                    .into_iter()
+                    .filter(|conversation| conversation.id == shared.conversation_id)
+                    .collect();
+                return schemas::messages::conversation_details(
+                    connection,
+                    authenticated.user_id,
+                    existing,
+                )
+                .await?
+                .pop()
+                .ok_or(AppError::NotFound("Conversation"));
+            }
+        }
+
+        let now: chrono::DateTime<chrono::FixedOffset> = chrono::DateTime::from(chrono::Utc::now());
+        let conversation = context
+            .transaction((title, others), move |txn, (title, others)| {
+                Box::pin(async move {
+                    let conversation = entity::conversations::ActiveModel {
+                        created_by: Set(Some(authenticated.user_id)),
+                        title: Set(title),
+                        is_group: Set(is_group),
+                        created_at: Set(now),
+                        ..Default::default()
+                    }
+                    .insert(txn)
+                    .await?;
+
+                    entity::conversation_members::Entity::insert_many(
+                        std::iter::once(authenticated.user_id)
+                            .chain(others)
+                            .map(|user_id| entity::conversation_members::ActiveModel {
+                                conversation_id: Set(conversation.id),
+                                user_id: Set(user_id),
+                                joined_at: Set(now),
+                                ..Default::default()
+                            }),
+                    )
+                    .exec(txn)
+                    .await?;
+
+                    Ok(conversation)
+                })
+            })
+            .await?;
+
+        schemas::messages::conversation_details(
+            connection,
+            authenticated.user_id,
+            vec![conversation],
+        )
+        .await?
+        .pop()
+        .ok_or(AppError::NotFound("Conversation"))
+    }
+
+    #[graphql(description = "send a message to a direct conversation")]
+    async fn send_message(
+        jwt: String,
+        conversation_id: ID,
+        body: String,
+        context: &Context,
+    ) -> AppResult<schemas::messages::MessageDetails> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+        let conversation_id = parse_id(&conversation_id)?;
+
+        if body.trim().is_empty() {
+            return Err(AppError::BadRequest("Message is empty".to_string()));
+        }
+
+        let member =
+            schemas::messages::require_member(connection, conversation_id, authenticated.user_id)
+                .await?;
+
+        let conversation = entity::conversations::Entity::find_by_id(conversation_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Conversation"))?;
+
+        if !conversation.is_group {
+            let member_ids =
+                schemas::messages::load_member_ids(connection, vec![conversation.id]).await?;
+            for other in member_ids.get(&conversation.id).into_iter().flatten() {
+                if *other != authenticated.user_id {
+                    schemas::messages::check_can_message(connection, authenticated.user_id, *other)
+                        .await?;
                 }
             }
+        }
 
-            Unauthenticated => Err(FieldError::new(
-                "Authentication Failed",
-                juniper::Value::Null,
-            )),
-        };
+        let now: chrono::DateTime<chrono::FixedOffset> = chrono::DateTime::from(chrono::Utc::now());
+        context
+            .transaction(
+                (body, conversation, member),
+                move |txn, (body, conversation, member)| {
+                    Box::pin(async move {
+                        let message = entity::messages::ActiveModel {
+                            conversation_id: Set(conversation.id),
+                            sender_id: Set(authenticated.user_id),
+                            body: Set(body),
+                            created_at: Set(now),
+                            ..Default::default()
+                        }
+                        .insert(txn)
+                        .await?;
+
+                        let mut conversation_table: entity::conversations::ActiveModel =
+                            conversation.into();
+                        conversation_table.last_message_at = Set(Some(now));
+                        conversation_table.update(txn).await?;
+
+                        let mut member_table: entity::conversation_members::ActiveModel =
+                            member.into();
+                        member_table.last_read_at = Set(Some(now));
+                        member_table.update(txn).await?;
+
+                        Ok(schemas::messages::MessageDetails::from(message))
+                    })
+                },
+            )
+            .await
+    }
+
+    #[graphql(description = "delete a message the user sent")]
+    async fn delete_message(jwt: String, message_id: ID, context: &Context) -> AppResult<bool> {
+        let connection = &context.connection;
+        let authenticated = context.require_auth(jwt).await?;
+        let message_id = parse_id(&message_id)?;
+
+        let message = entity::messages::Entity::find_by_id(message_id)
+            .one(connection)
+            .await?
+            .ok_or(AppError::NotFound("Message"))?;
+
+        if message.sender_id != authenticated.user_id {
+            return Err(AppError::Forbidden(
+                "Cant delete messages sent by other users",
+            ));
+        }
+
+        entity::messages::Entity::delete_by_id(message_id)
+            .exec(connection)
+            .await?;
+
+        Ok(true)
+    }
+
+    #[graphql(description = "mark every message of a direct conversation as read")]
+    async fn mark_conversation_read(
+        jwt: String,
+        conversation_id: ID,
+        context: &Context,
+    ) -> AppResult<bool> {
+        let authenticated = context.require_auth(jwt).await?;
+
+        let member = schemas::messages::require_member(
+            &context.connection,
+            parse_id(&conversation_id)?,
+            authenticated.user_id,
+        )
+        .await?;
+
+        let mut member_table: entity::conversation_members::ActiveModel = member.into();
+        member_table.last_read_at = Set(Some(chrono::DateTime::from(chrono::Utc::now())));
+        member_table.update(&context.connection).await?;
+
+        Ok(true)
     }
 }
 
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use juniper::ID;
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection, DbBackend,
+    FromQueryResult, Statement, Value,
+};
+
+use crate::lib::{
+    errors::{check_page, parse_id, AppError, AppResult},
+    privacy::not_locked_sql,
+};
+use crate::schemas::buzz::{AllBuzzResult, BuzzResult};
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum SearchOrder {
+    Relevance,
+    Recency,
+}
+
+#[derive(GraphQLInputObject, Default)]
+#[graphql(description = "Narrow down a search")]
+pub struct SearchFilters {
+    pub author_id: Option<ID>,
+    pub created_after: Option<DateTimeWithTimeZone>,
+    pub created_before: Option<DateTimeWithTimeZone>,
+    pub hashtag: Option<String>,
+    #[graphql(description = "also match buzzes through the text of their replies")]
+    pub include_replies: Option<bool>,
+    pub order_by: Option<SearchOrder>,
+}
+
+#[derive(FromQueryResult)]
+struct SearchHit {
+    id: i64,
+}
+
+#[derive(FromQueryResult)]
+struct SearchCount {
+    total: i64,
+}
+
+/// `query` uses the web search syntax, `"quoted words"` is a phrase,
+/// `or` between words matches either and `-word` excludes a word. Buzzes
+/// and replies of `hidden` users and of private accounts `viewer_id` may not
+/// see never match.
+pub async fn search_buzzes(
+    connection: &DatabaseConnection,
+    query: String,
+    filters: Option<SearchFilters>,
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllBuzzResult> {
+    check_page(page_size, page_number)?;
+
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("Search query is empty".to_string()));
+    }
+
+    let filters = filters.unwrap_or_default();
+
+    // $2 is the viewer, the hidden users follow it.
+    let mut values: Vec<Value> = vec![query.into(), viewer_id.into()];
+    values.extend(hidden.iter().map(|user_id| (*user_id).into()));
+    let visible = |author_column: &str| {
+        let mut visible = not_locked_sql(author_column, "$2");
+        if !hidden.is_empty() {
+            visible.push_str(&format!(
+                " AND {} NOT IN ({})",
+                author_column,
+                (3..=values.len())
+                    .map(|i| format!("${}", i))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        visible
+    };
+
+    let mut conditions: Vec<String> = vec![
+        "buzz.deleted_at IS NULL".to_string(),
+        "buzz.hidden_at IS NULL".to_string(),
+        visible("buzz.user_id"),
+    ];
+
+    let include_replies = filters.include_replies.unwrap_or(false);
+    let (matches, rank) = if include_replies {
+        let replies = format!(
+            "FROM reply WHERE reply.buzz_id = buzz.id AND reply.hidden_at IS NULL
+            AND reply.search_vector @@ q.query AND {}",
+            visible("reply.user_id")
+        );
+        (
+            format!(
+                "(buzz.search_vector @@ q.query OR EXISTS (SELECT 1 {}))",
+                replies
+            ),
+            format!(
+                "GREATEST(ts_rank(buzz.search_vector, q.query), COALESCE((
+                    SELECT MAX(ts_rank(reply.search_vector, q.query)) {}
+                ), 0))",
+                replies
+            ),
+        )
+    } else {
+        (
+            "buzz.search_vector @@ q.query".to_string(),
+            "ts_rank(buzz.search_vector, q.query)".to_string(),
+        )
+    };
+    conditions.push(matches);
+
+    if let Some(author_id) = &filters.author_id {
+        values.push(parse_id(author_id)?.into());
+        conditions.push(format!("buzz.user_id = ${}", values.len()));
+    }
+    if let Some(created_after) = filters.created_after {
+        values.push(created_after.into());
+        conditions.push(format!("buzz.created_at >= ${}", values.len()));
+    }
+    if let Some(created_before) = filters.created_before {
+        values.push(created_before.into());
+        conditions.push(format!("buzz.created_at < ${}", values.len()));
+    }
+    if let Some(hashtag) = &filters.hashtag {
+        values.push(hashtag.trim_start_matches('#').to_lowercase().into());
+        conditions.push(format!(
+            "EXISTS (
+                SELECT 1 FROM buzz_hashtags JOIN hashtags ON hashtags.id = buzz_hashtags.hashtag_id
+                WHERE buzz_hashtags.buzz_id = buzz.id AND hashtags.tag = ${}
+            )",
+            values.len()
+        ));
+    }
+
+    let from = format!(
+        "FROM buzz, websearch_to_tsquery('english', $1) AS q(query) WHERE {}",
+        conditions.join(" AND ")
+    );
+
+    let total = SearchCount::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!("SELECT COUNT(*) AS total {}", from),
+        values.clone(),
+    ))
+    .one(connection)
+    .await?
+    .map(|count| count.total)
+    .unwrap_or(0);
+
+    let order = match filters.order_by.unwrap_or(SearchOrder::Relevance) {
+        SearchOrder::Relevance => format!("{} DESC, buzz.created_at DESC", rank),
+        SearchOrder::Recency => "buzz.created_at DESC".to_string(),
+    };
+    let hits = SearchHit::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            "SELECT buzz.id {} ORDER BY {}, buzz.id DESC LIMIT {} OFFSET {}",
+            from,
+            order,
+            page_size,
+            (page_number as i64 - 1) * page_size as i64
+        ),
+        values,
+    ))
+    .all(connection)
+    .await?;
+
+    let mut buzzes: HashMap<i64, entity::buzz::Model> = entity::buzz::Entity::find()
+        .filter(entity::buzz::Column::Id.is_in(hits.iter().map(|hit| hit.id).collect::<Vec<i64>>()))
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|buzz| (buzz.id, buzz))
+        .collect();
+
+    Ok(AllBuzzResult {
+        buzzes: hits
+            .iter()
+            .filter_map(|hit| buzzes.remove(&hit.id))
+            .map(BuzzResult::from)
+            .collect(),
+        total_buzzes: total as i32,
+        total_pages: ((total + page_size as i64 - 1) / page_size as i64) as i32,
+        page_number,
+        page_size,
+    })
+}
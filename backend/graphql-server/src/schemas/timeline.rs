@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use sea_orm::{
+    entity::*, prelude::DateTimeWithTimeZone, query::*, DatabaseConnection, DbBackend,
+    FromQueryResult, Statement,
+};
+
+use crate::lib::{
+    errors::{check_page, AppResult},
+    privacy::not_locked_sql,
+};
+use crate::schemas::buzz::{AllBuzzResult, BuzzResult};
+
+#[derive(FromQueryResult)]
+struct TimelineEntry {
+    buzz_id: i64,
+    rebuzzed_by: Option<i64>,
+    activity_at: DateTimeWithTimeZone,
+}
+
+#[derive(FromQueryResult)]
+struct TimelineCount {
+    total: i64,
+}
+
+/// Buzzes written or rebuzzed by any of `user_ids`, newest first. A rebuzz
+/// is placed at the time it was rebuzzed. Buzzes written by `hidden` users
+/// or by private accounts `viewer_id` may not see are left out, rebuzzed or
+/// not.
+pub async fn load_timeline(
+    connection: &DatabaseConnection,
+    user_ids: &[i64],
+    hidden: &[i64],
+    viewer_id: Option<i64>,
+    page_size: i32,
+    page_number: i32,
+) -> AppResult<AllBuzzResult> {
+    check_page(page_size, page_number)?;
+
+    if user_ids.is_empty() {
+        return Ok(AllBuzzResult {
+            buzzes: Vec::new(),
+            total_buzzes: 0,
+            total_pages: 0,
+            page_number,
+            page_size,
+        });
+    }
+
+    let placeholders = (1..=user_ids.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut not_hidden = format!(
+        " AND {}",
+        not_locked_sql(
+            "buzz.user_id",
+            &format!("${}", user_ids.len() + hidden.len() + 1)
+        )
+    );
+    if !hidden.is_empty() {
+        not_hidden.push_str(&format!(
+            " AND buzz.user_id NOT IN ({})",
+            (user_ids.len() + 1..=user_ids.len() + hidden.len())
+                .map(|i| format!("${}", i))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    let timeline = format!(
+        r#"SELECT buzz.id AS buzz_id, NULL::BIGINT AS rebuzzed_by, buzz.created_at AS activity_at
+            FROM buzz WHERE buzz.deleted_at IS NULL AND buzz.hidden_at IS NULL
+            AND buzz.user_id IN ({0}){1}
+        UNION ALL
+        SELECT rebuzz.buzz_id, rebuzz.user_id, rebuzz.created_at
+            FROM rebuzz JOIN buzz ON buzz.id = rebuzz.buzz_id
+            WHERE buzz.deleted_at IS NULL AND buzz.hidden_at IS NULL
+            AND rebuzz.user_id IN ({0}){1}"#,
+        placeholders, not_hidden
+    );
+    let values = || {
+        user_ids
+            .iter()
+            .chain(hidden)
+            .map(|id| (*id).into())
+            .chain([viewer_id.into()])
+    };
+
+    let total = TimelineCount::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!("SELECT COUNT(*) AS total FROM ({}) AS timeline", timeline),
+        values(),
+    ))
+    .one(connection)
+    .await?
+    .map(|count| count.total)
+    .unwrap_or(0);
+
+    let entries = TimelineEntry::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"SELECT * FROM ({}) AS timeline
+            ORDER BY timeline.activity_at DESC, timeline.buzz_id DESC
+            LIMIT {} OFFSET {}"#,
+            timeline,
+            page_size,
+            (page_number as i64 - 1) * page_size as i64
+        ),
+        values(),
+    ))
+    .all(connection)
+    .await?;
+
+    let buzzes: HashMap<i64, entity::buzz::Model> = entity::buzz::Entity::find()
+        .filter(
+            entity::buzz::Column::Id.is_in(
+                entries
+                    .iter()
+                    .map(|entry| entry.buzz_id)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|buzz| (buzz.id, buzz))
+        .collect();
+
+    let buzzes = entries
+        .into_iter()
+        .filter_map(|entry| {
+            // The same buzz can be on a page twice, as a buzz and a rebuzz.
+            let mut buzz = BuzzResult::from(buzzes.get(&entry.buzz_id)?.clone());
+            if entry.rebuzzed_by.is_some() {
+                buzz.rebuzzed_by = entry.rebuzzed_by;
+                buzz.rebuzzed_at = Some(entry.activity_at);
+            }
+            Some(buzz)
+        })
+        .collect();
+
+    Ok(AllBuzzResult {
+        buzzes,
+        total_buzzes: total as i32,
+        total_pages: ((total + page_size as i64 - 1) / page_size as i64) as i32,
+        page_number,
+        page_size,
+    })
+}
@@ -0,0 +1,55 @@
+use juniper::ID;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+#[derive(GraphQLEnum, Clone, Copy, Debug)]
+pub enum TrendingWindow {
+    Hour,
+    Day,
+    Week,
+}
+
+impl TrendingWindow {
+    pub const ALL: [TrendingWindow; 3] = [
+        TrendingWindow::Hour,
+        TrendingWindow::Day,
+        TrendingWindow::Week,
+    ];
+
+    /// Stored in `trending.time_window`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            TrendingWindow::Hour => "1h",
+            TrendingWindow::Day => "24h",
+            TrendingWindow::Week => "7d",
+        }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            TrendingWindow::Hour => chrono::Duration::hours(1),
+            TrendingWindow::Day => chrono::Duration::days(1),
+            TrendingWindow::Week => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+#[derive(GraphQLObject)]
+pub struct TrendingDetails {
+    pub hashtag_id: ID,
+    pub hashtag: String,
+    pub buzz_count: i32,
+    pub rank: i32,
+    pub computed_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<(entity::trending::Model, entity::hashtags::Model)> for TrendingDetails {
+    fn from((trending, hashtag): (entity::trending::Model, entity::hashtags::Model)) -> Self {
+        TrendingDetails {
+            hashtag_id: ID::from(hashtag.id.to_string()),
+            hashtag: hashtag.tag,
+            buzz_count: trending.buzz_count.unwrap_or(0) as i32,
+            rank: trending.rank.unwrap_or(0),
+            computed_at: trending.computed_at,
+        }
+    }
+}
@@ -1,3 +1,11 @@
+use juniper::ID;
+
+use crate::lib::{
+    common::Tagged,
+    errors::{AppError, AppResult},
+};
+use crate::schemas::{messages::DmPolicy, root::Context};
+
 #[derive(GraphQLInputObject)]
 pub struct UserModify {
     pub full_name: String,
@@ -8,18 +16,78 @@ pub struct UserModify {
 
 #[derive(GraphQLObject)]
 pub struct UserDetails {
-    pub id: i32,
-    pub auth_id: Option<i32>,
+    pub id: ID,
+    pub auth_id: Option<ID>,
     pub full_name: String,
     pub description: Option<String>,
     pub profile_picture: Option<String>,
     pub location_or_region: Option<String>,
+    pub dm_policy: DmPolicy,
+    pub is_private: bool,
+}
+
+impl From<entity::users::Model> for UserDetails {
+    fn from(user: entity::users::Model) -> Self {
+        UserDetails {
+            id: ID::from(user.id.to_string()),
+            auth_id: Some(ID::from(user.auth_id.to_string())),
+            full_name: user.full_name,
+            description: user.description,
+            profile_picture: user.profile_picture,
+            location_or_region: user.location_or_region,
+            dm_policy: DmPolicy::from_key(&user.dm_policy),
+            is_private: user.is_private,
+        }
+    }
+}
+
+pub async fn load_user_details(context: &Context, user_id: i64) -> AppResult<UserDetails> {
+    match context.loaders.users.load(user_id).await? {
+        Some(user) => Ok(UserDetails::from(user)),
+        None => Err(AppError::NotFound("User")),
+    }
+}
+
+/// `user_ids` in the given order, deleted users are left out.
+pub async fn load_users(context: &Context, user_ids: Vec<i64>) -> AppResult<Vec<UserDetails>> {
+    let mut users = context.loaders.users.load_many(user_ids.clone()).await;
+
+    let mut found = Vec::new();
+    for user_id in user_ids {
+        if let Some(user) = users.remove(&user_id).transpose()?.flatten() {
+            found.push(UserDetails::from(user));
+        }
+    }
+    Ok(found)
+}
+
+/// Users mentioned in a buzz or reply, deleted users are left out.
+pub async fn load_mentioned_users(
+    context: &Context,
+    tagged: Tagged,
+) -> AppResult<Vec<UserDetails>> {
+    let user_ids = context.loaders.mentions.load(tagged).await?;
+    load_users(context, user_ids).await
 }
 
 #[derive(GraphQLObject)]
 pub struct FollowResponse {
-    pub following_id: String,
+    pub following_id: ID,
     pub is_following: bool,
+    #[graphql(description = "a follow request is waiting for the private account to accept it")]
+    pub is_requested: bool,
+}
+
+#[derive(GraphQLObject)]
+pub struct BlockResponse {
+    pub user_id: ID,
+    pub is_blocked: bool,
+}
+
+#[derive(GraphQLObject)]
+pub struct MuteResponse {
+    pub user_id: ID,
+    pub is_muted: bool,
 }
 
 #[derive(GraphQLObject)]
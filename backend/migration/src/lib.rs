@@ -1,12 +1,58 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20220901_000001_create_buzz_revisions;
+mod m20220902_000001_add_reply_parent;
+mod m20220903_000001_create_rebuzz;
+mod m20220904_000001_create_bookmarks;
+mod m20220905_000001_add_search_vectors;
+mod m20220906_000001_create_hashtags;
+mod m20220907_000001_create_mentions;
+mod m20220908_000001_create_notifications;
+mod m20220909_000001_create_direct_messages;
+mod m20220910_000001_create_blocks_and_mutes;
+mod m20220911_000001_add_private_accounts;
+mod m20220912_000001_create_view_events;
+mod m20220913_000001_create_follows;
+mod m20220914_000001_create_upvotes;
+mod m20220915_000001_add_buzz_tombstones;
+mod m20220916_000001_add_account_deletion;
+mod m20220917_000001_create_security_events;
+mod m20220918_000001_create_data_exports;
+mod m20220919_000001_add_moderation;
+mod m20220920_000001_create_rate_limit_buckets;
+mod m20220921_000001_create_polls;
+mod m20220922_000001_add_reply_tombstones;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20220901_000001_create_buzz_revisions::Migration),
+            Box::new(m20220902_000001_add_reply_parent::Migration),
+            Box::new(m20220903_000001_create_rebuzz::Migration),
+            Box::new(m20220904_000001_create_bookmarks::Migration),
+            Box::new(m20220905_000001_add_search_vectors::Migration),
+            Box::new(m20220906_000001_create_hashtags::Migration),
+            Box::new(m20220907_000001_create_mentions::Migration),
+            Box::new(m20220908_000001_create_notifications::Migration),
+            Box::new(m20220909_000001_create_direct_messages::Migration),
+            Box::new(m20220910_000001_create_blocks_and_mutes::Migration),
+            Box::new(m20220911_000001_add_private_accounts::Migration),
+            Box::new(m20220912_000001_create_view_events::Migration),
+            Box::new(m20220913_000001_create_follows::Migration),
+            Box::new(m20220914_000001_create_upvotes::Migration),
+            Box::new(m20220915_000001_add_buzz_tombstones::Migration),
+            Box::new(m20220916_000001_add_account_deletion::Migration),
+            Box::new(m20220917_000001_create_security_events::Migration),
+            Box::new(m20220918_000001_create_data_exports::Migration),
+            Box::new(m20220919_000001_add_moderation::Migration),
+            Box::new(m20220920_000001_create_rate_limit_buckets::Migration),
+            Box::new(m20220921_000001_create_polls::Migration),
+            Box::new(m20220922_000001_add_reply_tombstones::Migration),
+        ]
     }
 }
@@ -78,9 +78,9 @@ impl MigrationTrait for Migration {
                     .col(ColumnDef::new(users::Column::ProfilePicture).text())
                     .col(ColumnDef::new(users::Column::Description).text())
                     .col(ColumnDef::new(users::Column::LocationOrRegion).text())
-                    .col(ColumnDef::new(users::Column::Following).text())
-                    .col(ColumnDef::new(users::Column::Followers).text())
-                    .col(ColumnDef::new(users::Column::Upvoted).text())
+                    .col(ColumnDef::new(Alias::new("following")).text())
+                    .col(ColumnDef::new(Alias::new("followers")).text())
+                    .col(ColumnDef::new(Alias::new("upvoted")).text())
                     .col(
                         ColumnDef::new(users::Column::CreatedAt)
                             .timestamp_with_time_zone()
@@ -112,7 +112,7 @@ impl MigrationTrait for Migration {
                             .big_integer()
                             .default(0_i64),
                     )
-                    .col(ColumnDef::new(ratings::Column::UpvotedBy).text())
+                    .col(ColumnDef::new(Alias::new("upvoted_by")).text())
                     .to_owned(),
             )
             .await?;
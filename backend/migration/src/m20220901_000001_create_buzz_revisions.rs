@@ -0,0 +1,144 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220901_000001_create_buzz_revisions"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .add_column(ColumnDef::new(buzz::Column::EditedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .add_column(ColumnDef::new(reply::Column::EditedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(buzz_revisions::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(buzz_revisions::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(buzz_revisions::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(buzz_revisions::Entity, buzz_revisions::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(buzz_revisions::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(buzz_revisions::Entity, buzz_revisions::Column::ReplyId)
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(buzz_revisions::Column::Content)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(buzz_revisions::Column::ImageLink).text())
+                    .col(ColumnDef::new(buzz_revisions::Column::VideoLink).text())
+                    .col(ColumnDef::new(buzz_revisions::Column::BuzzWords).text())
+                    .col(ColumnDef::new(buzz_revisions::Column::MentionedUsers).text())
+                    .col(
+                        ColumnDef::new(buzz_revisions::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(buzz_revisions::Column::RevisedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A revision belongs to exactly one buzz or one reply.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "ALTER TABLE buzz_revisions ADD CONSTRAINT buzz_revisions_buzz_or_reply
+                    CHECK ((buzz_id IS NULL) <> (reply_id IS NULL))"
+                    .to_string(),
+            ))
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_buzz_revisions_buzz_id")
+                    .table(buzz_revisions::Entity)
+                    .col(buzz_revisions::Column::BuzzId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_buzz_revisions_reply_id")
+                    .table(buzz_revisions::Entity)
+                    .col(buzz_revisions::Column::ReplyId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(buzz_revisions::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .drop_column(reply::Column::EditedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .drop_column(buzz::Column::EditedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220902_000001_add_reply_parent"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .add_column(ColumnDef::new(reply::Column::ParentReplyId).big_integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_reply_parent_reply_id")
+                            .from_tbl(reply::Entity)
+                            .from_col(reply::Column::ParentReplyId)
+                            .to_tbl(reply::Entity)
+                            .to_col(reply::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_reply_parent_reply_id")
+                    .table(reply::Entity)
+                    .col(reply::Column::ParentReplyId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .drop_column(reply::Column::ParentReplyId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220903_000001_create_rebuzz"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(ratings::Entity)
+                    .add_column(
+                        ColumnDef::new(ratings::Column::Rebuzzes)
+                            .big_integer()
+                            .default(0_i64),
+                    )
+                    .add_column(
+                        ColumnDef::new(ratings::Column::Quotes)
+                            .big_integer()
+                            .default(0_i64),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .add_column(ColumnDef::new(buzz::Column::QuotedBuzzId).big_integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_buzz_quoted_buzz_id")
+                            .from_tbl(buzz::Entity)
+                            .from_col(buzz::Column::QuotedBuzzId)
+                            .to_tbl(buzz::Entity)
+                            .to_col(buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(rebuzz::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(rebuzz::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(rebuzz::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(rebuzz::Entity, rebuzz::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(rebuzz::Column::BuzzId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(rebuzz::Entity, rebuzz::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(rebuzz::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_rebuzz_user_id_buzz_id")
+                            .col(rebuzz::Column::UserId)
+                            .col(rebuzz::Column::BuzzId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(rebuzz::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .drop_column(buzz::Column::QuotedBuzzId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(ratings::Entity)
+                    .drop_column(ratings::Column::Rebuzzes)
+                    .drop_column(ratings::Column::Quotes)
+                    .to_owned(),
+            )
+            .await
+    }
+}
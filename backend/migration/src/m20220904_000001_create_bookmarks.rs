@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220904_000001_create_bookmarks"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(bookmarks::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(bookmarks::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(bookmarks::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(bookmarks::Entity, bookmarks::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(bookmarks::Column::BuzzId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(bookmarks::Entity, bookmarks::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(bookmarks::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_bookmarks_user_id_buzz_id")
+                            .col(bookmarks::Column::UserId)
+                            .col(bookmarks::Column::BuzzId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(bookmarks::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220905_000001_add_search_vectors"
+    }
+}
+
+/// The vectors are generated columns, Postgres keeps them in sync with the
+/// text on every insert and update.
+const UP: [&str; 4] = [
+    "ALTER TABLE buzz ADD COLUMN IF NOT EXISTS search_vector tsvector
+        GENERATED ALWAYS AS (to_tsvector('english', description)) STORED",
+    "CREATE INDEX IF NOT EXISTS idx_buzz_search_vector ON buzz USING GIN (search_vector)",
+    "ALTER TABLE reply ADD COLUMN IF NOT EXISTS search_vector tsvector
+        GENERATED ALWAYS AS (to_tsvector('english', reply_content)) STORED",
+    "CREATE INDEX IF NOT EXISTS idx_reply_search_vector ON reply USING GIN (search_vector)",
+];
+
+const DOWN: [&str; 4] = [
+    "DROP INDEX IF EXISTS idx_reply_search_vector",
+    "ALTER TABLE reply DROP COLUMN IF EXISTS search_vector",
+    "DROP INDEX IF EXISTS idx_buzz_search_vector",
+    "ALTER TABLE buzz DROP COLUMN IF EXISTS search_vector",
+];
+
+async fn execute_all(manager: &SchemaManager<'_>, statements: &[&str]) -> Result<(), DbErr> {
+    let connection = manager.get_connection();
+    for sql in statements {
+        connection
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                sql.to_string(),
+            ))
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        execute_all(manager, &UP).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        execute_all(manager, &DOWN).await
+    }
+}
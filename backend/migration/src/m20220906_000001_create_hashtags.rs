@@ -0,0 +1,215 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220906_000001_create_hashtags"
+    }
+}
+
+/// Existing `buzz_words` were free-form client strings, only the entries
+/// that are valid tags are carried over.
+const BACKFILL: [&str; 3] = [
+    r#"INSERT INTO hashtags (tag, created_at)
+        SELECT DISTINCT words.tag, now() FROM (
+            SELECT lower(trim(leading '#' from trim(word))) AS tag
+                FROM buzz CROSS JOIN LATERAL unnest(string_to_array(buzz.buzz_words, ',')) AS word
+            UNION
+            SELECT lower(trim(leading '#' from trim(word))) AS tag
+                FROM reply CROSS JOIN LATERAL unnest(string_to_array(reply.buzz_words, ',')) AS word
+        ) AS words
+        WHERE words.tag ~ '^[[:alnum:]_]{1,64}$'
+        ON CONFLICT (tag) DO NOTHING"#,
+    r#"INSERT INTO buzz_hashtags (hashtag_id, buzz_id, created_at)
+        SELECT DISTINCT hashtags.id, buzz.id, buzz.created_at
+            FROM buzz CROSS JOIN LATERAL unnest(string_to_array(buzz.buzz_words, ',')) AS word
+            JOIN hashtags ON hashtags.tag = lower(trim(leading '#' from trim(word)))"#,
+    r#"INSERT INTO buzz_hashtags (hashtag_id, reply_id, created_at)
+        SELECT DISTINCT hashtags.id, reply.id, reply.created_at
+            FROM reply CROSS JOIN LATERAL unnest(string_to_array(reply.buzz_words, ',')) AS word
+            JOIN hashtags ON hashtags.tag = lower(trim(leading '#' from trim(word)))"#,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(hashtags::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(hashtags::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(hashtags::Column::Tag)
+                            .text()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(hashtags::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(buzz_hashtags::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(buzz_hashtags::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(buzz_hashtags::Column::HashtagId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(buzz_hashtags::Entity, buzz_hashtags::Column::HashtagId)
+                            .to(hashtags::Entity, hashtags::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(buzz_hashtags::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(buzz_hashtags::Entity, buzz_hashtags::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(buzz_hashtags::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(buzz_hashtags::Entity, buzz_hashtags::Column::ReplyId)
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(buzz_hashtags::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_buzz_hashtags_hashtag_id_created_at")
+                    .table(buzz_hashtags::Entity)
+                    .col(buzz_hashtags::Column::HashtagId)
+                    .col(buzz_hashtags::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_buzz_hashtags_buzz_id")
+                    .table(buzz_hashtags::Entity)
+                    .col(buzz_hashtags::Column::BuzzId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_buzz_hashtags_reply_id")
+                    .table(buzz_hashtags::Entity)
+                    .col(buzz_hashtags::Column::ReplyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(trending::Entity)
+                    .add_column(ColumnDef::new(trending::Column::HashtagId).big_integer())
+                    .add_column(ColumnDef::new(trending::Column::TimeWindow).text())
+                    .add_column(ColumnDef::new(trending::Column::BuzzCount).big_integer())
+                    .add_column(ColumnDef::new(trending::Column::Rank).integer())
+                    .add_column(
+                        ColumnDef::new(trending::Column::ComputedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk_trending_hashtag_id")
+                    .from(trending::Entity, trending::Column::HashtagId)
+                    .to(hashtags::Entity, hashtags::Column::Id)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        for sql in BACKFILL {
+            connection
+                .execute(Statement::from_string(
+                    manager.get_database_backend(),
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(trending::Entity)
+                    .drop_column(trending::Column::HashtagId)
+                    .drop_column(trending::Column::TimeWindow)
+                    .drop_column(trending::Column::BuzzCount)
+                    .drop_column(trending::Column::Rank)
+                    .drop_column(trending::Column::ComputedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(buzz_hashtags::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(hashtags::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
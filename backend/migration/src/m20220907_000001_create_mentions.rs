@@ -0,0 +1,150 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220907_000001_create_mentions"
+    }
+}
+
+/// Existing `mentioned_users` were free-form client strings, only the
+/// entries naming an existing user are carried over.
+const BACKFILL: [&str; 2] = [
+    r#"INSERT INTO mentions (user_id, buzz_id, created_at)
+        SELECT DISTINCT users.id, buzz.id, buzz.created_at
+            FROM buzz CROSS JOIN LATERAL unnest(string_to_array(buzz.mentioned_users, ',')) AS word
+            JOIN auth ON lower(auth.username) = lower(trim(leading '@' from trim(word)))
+            JOIN users ON users.auth_id = auth.id"#,
+    r#"INSERT INTO mentions (user_id, reply_id, created_at)
+        SELECT DISTINCT users.id, reply.id, reply.created_at
+            FROM reply CROSS JOIN LATERAL unnest(string_to_array(reply.mentioned_users, ',')) AS word
+            JOIN auth ON lower(auth.username) = lower(trim(leading '@' from trim(word)))
+            JOIN users ON users.auth_id = auth.id"#,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(mentions::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(mentions::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(mentions::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(mentions::Entity, mentions::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(mentions::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(mentions::Entity, mentions::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(mentions::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(mentions::Entity, mentions::Column::ReplyId)
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(mentions::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_mentions_user_id_created_at")
+                    .table(mentions::Entity)
+                    .col(mentions::Column::UserId)
+                    .col(mentions::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_mentions_buzz_id")
+                    .table(mentions::Entity)
+                    .col(mentions::Column::BuzzId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_mentions_reply_id")
+                    .table(mentions::Entity)
+                    .col(mentions::Column::ReplyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+
+        // Mentions are resolved by username, whatever its case.
+        connection
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "CREATE INDEX IF NOT EXISTS idx_auth_lower_username ON auth (lower(username))"
+                    .to_string(),
+            ))
+            .await?;
+
+        for sql in BACKFILL {
+            connection
+                .execute(Statement::from_string(
+                    manager.get_database_backend(),
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "DROP INDEX IF EXISTS idx_auth_lower_username".to_string(),
+            ))
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(mentions::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
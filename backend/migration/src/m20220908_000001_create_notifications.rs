@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220908_000001_create_notifications"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(notifications::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(notifications::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(notifications::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(notifications::Entity, notifications::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(notifications::Column::ActorId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(notifications::Entity, notifications::Column::ActorId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(notifications::Column::Kind)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(notifications::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(notifications::Entity, notifications::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(notifications::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(notifications::Entity, notifications::Column::ReplyId)
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(notifications::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(notifications::Column::ReadAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_notifications_user_id_created_at")
+                    .table(notifications::Entity)
+                    .col(notifications::Column::UserId)
+                    .col(notifications::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(notifications::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,235 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220909_000001_create_direct_messages"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .add_column(
+                        ColumnDef::new(users::Column::DmPolicy)
+                            .text()
+                            .not_null()
+                            .default("everyone"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(conversations::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(conversations::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(conversations::Column::CreatedBy)
+                            .big_integer()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(conversations::Entity, conversations::Column::CreatedBy)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(conversations::Column::Title).text())
+                    .col(
+                        ColumnDef::new(conversations::Column::IsGroup)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(conversations::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(conversations::Column::LastMessageAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(conversation_members::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(conversation_members::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(conversation_members::Column::ConversationId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                conversation_members::Entity,
+                                conversation_members::Column::ConversationId,
+                            )
+                            .to(conversations::Entity, conversations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(conversation_members::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                conversation_members::Entity,
+                                conversation_members::Column::UserId,
+                            )
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(conversation_members::Column::JoinedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(conversation_members::Column::LastReadAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_conversation_members_conversation_id_user_id")
+                            .col(conversation_members::Column::ConversationId)
+                            .col(conversation_members::Column::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_conversation_members_user_id")
+                    .table(conversation_members::Entity)
+                    .col(conversation_members::Column::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(messages::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(messages::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(messages::Column::ConversationId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(messages::Entity, messages::Column::ConversationId)
+                            .to(conversations::Entity, conversations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(messages::Column::SenderId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(messages::Entity, messages::Column::SenderId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(messages::Column::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(messages::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_messages_conversation_id_created_at")
+                    .table(messages::Entity)
+                    .col(messages::Column::ConversationId)
+                    .col(messages::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(messages::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(conversation_members::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(conversations::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .drop_column(users::Column::DmPolicy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220912_000001_create_view_events"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(view_events::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(view_events::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(view_events::Column::RatingsId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(view_events::Entity, view_events::Column::RatingsId)
+                            .to(ratings::Entity, ratings::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(view_events::Column::ViewerKey)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(view_events::Column::WindowStart)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(view_events::Column::Counted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_view_events_ratings_id_viewer_key_window_start")
+                            .col(view_events::Column::RatingsId)
+                            .col(view_events::Column::ViewerKey)
+                            .col(view_events::Column::WindowStart)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The aggregation job only ever looks at views it has not counted.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "CREATE INDEX IF NOT EXISTS idx_view_events_uncounted
+                    ON view_events (ratings_id) WHERE NOT counted"
+                    .to_string(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(view_events::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,143 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220913_000001_create_follows"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(follows::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(follows::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(follows::Column::FollowerId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(follows::Entity, follows::Column::FollowerId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(follows::Column::FolloweeId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(follows::Entity, follows::Column::FolloweeId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(follows::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_follows_follower_id_followee_id")
+                            .col(follows::Column::FollowerId)
+                            .col(follows::Column::FolloweeId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_follows_followee_id")
+                    .table(follows::Entity)
+                    .col(follows::Column::FolloweeId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // `change_follow_user` used to add the followed user to their own
+        // `followers`, so only `following` is trusted. Ids of users that are
+        // gone and anything that is not an id are dropped.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                r#"INSERT INTO follows (follower_id, followee_id, created_at)
+                SELECT users.id, followee.id, NOW()
+                FROM users
+                CROSS JOIN LATERAL unnest(string_to_array(users.following, ',')) AS entry(value)
+                JOIN users AS followee ON followee.id::TEXT = trim(entry.value)
+                WHERE followee.id <> users.id
+                ON CONFLICT (follower_id, followee_id) DO NOTHING"#
+                    .to_string(),
+            ))
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .drop_column(Alias::new("following"))
+                    .drop_column(Alias::new("followers"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .add_column(ColumnDef::new(Alias::new("following")).text())
+                    .add_column(ColumnDef::new(Alias::new("followers")).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                r#"UPDATE users SET
+                following = (
+                    SELECT string_agg(follows.followee_id::TEXT, ', ')
+                    FROM follows WHERE follows.follower_id = users.id
+                ),
+                followers = (
+                    SELECT string_agg(follows.follower_id::TEXT, ', ')
+                    FROM follows WHERE follows.followee_id = users.id
+                )"#
+                .to_string(),
+            ))
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(follows::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,168 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220914_000001_create_upvotes"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(upvotes::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(upvotes::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(upvotes::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(upvotes::Entity, upvotes::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(upvotes::Column::RatingsId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(upvotes::Entity, upvotes::Column::RatingsId)
+                            .to(ratings::Entity, ratings::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(upvotes::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .index(
+                        sea_query::Index::create()
+                            .name("idx_upvotes_user_id_ratings_id")
+                            .col(upvotes::Column::UserId)
+                            .col(upvotes::Column::RatingsId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_upvotes_ratings_id")
+                    .table(upvotes::Entity)
+                    .col(upvotes::Column::RatingsId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Lost updates left `ratings.upvoted_by` and `users.upvoted` out of
+        // step, an upvote found in either of them is kept. The counters are
+        // recounted from the result.
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        for sql in [
+            r#"INSERT INTO upvotes (user_id, ratings_id, created_at)
+            SELECT users.id, ratings.id, NOW()
+            FROM ratings
+            CROSS JOIN LATERAL unnest(string_to_array(ratings.upvoted_by, ',')) AS entry(value)
+            JOIN users ON users.id::TEXT = trim(entry.value)
+            ON CONFLICT (user_id, ratings_id) DO NOTHING"#,
+            r#"INSERT INTO upvotes (user_id, ratings_id, created_at)
+            SELECT users.id, ratings.id, NOW()
+            FROM users
+            CROSS JOIN LATERAL unnest(string_to_array(users.upvoted, ',')) AS entry(value)
+            JOIN ratings ON ratings.id::TEXT = trim(entry.value)
+            ON CONFLICT (user_id, ratings_id) DO NOTHING"#,
+            r#"UPDATE ratings SET upvotes = (
+                SELECT COUNT(*) FROM upvotes WHERE upvotes.ratings_id = ratings.id
+            )"#,
+        ] {
+            connection
+                .execute(Statement::from_string(backend, sql.to_string()))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(ratings::Entity)
+                    .drop_column(Alias::new("upvoted_by"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .drop_column(Alias::new("upvoted"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(ratings::Entity)
+                    .add_column(ColumnDef::new(Alias::new("upvoted_by")).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .add_column(ColumnDef::new(Alias::new("upvoted")).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        for sql in [
+            r#"UPDATE ratings SET upvoted_by = (
+                SELECT string_agg(upvotes.user_id::TEXT, ', ')
+                FROM upvotes WHERE upvotes.ratings_id = ratings.id
+            )"#,
+            r#"UPDATE users SET upvoted = (
+                SELECT string_agg(upvotes.ratings_id::TEXT, ', ')
+                FROM upvotes WHERE upvotes.user_id = users.id
+            )"#,
+        ] {
+            connection
+                .execute(Statement::from_string(backend, sql.to_string()))
+                .await?;
+        }
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(upvotes::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
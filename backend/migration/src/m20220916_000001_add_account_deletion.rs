@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220916_000001_add_account_deletion"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .add_column(
+                        ColumnDef::new(users::Column::DeletionRequestedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .add_column(ColumnDef::new(users::Column::DeletedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_users_deletion_requested_at")
+                    .table(users::Entity)
+                    .col(users::Column::DeletionRequestedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .drop_column(users::Column::DeletionRequestedAt)
+                    .drop_column(users::Column::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
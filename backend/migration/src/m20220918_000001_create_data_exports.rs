@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220918_000001_create_data_exports"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(data_exports::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(data_exports::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(data_exports::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(data_exports::Entity, data_exports::Column::UserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(data_exports::Column::Token)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(data_exports::Column::Status)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(data_exports::Column::Archive).binary())
+                    .col(
+                        ColumnDef::new(data_exports::Column::RequestedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(data_exports::Column::CompletedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(data_exports::Column::ExpiresAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx_data_exports_user_id")
+                    .table(data_exports::Entity)
+                    .col(data_exports::Column::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(data_exports::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
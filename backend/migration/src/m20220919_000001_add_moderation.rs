@@ -0,0 +1,267 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220919_000001_add_moderation"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .add_column(
+                        ColumnDef::new(users::Column::IsModerator)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(users::Column::SuspendedAt).timestamp_with_time_zone(),
+                    )
+                    .add_column(
+                        ColumnDef::new(users::Column::SuspendedUntil).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .add_column(ColumnDef::new(buzz::Column::HiddenAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .add_column(ColumnDef::new(reply::Column::HiddenAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(reports::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(reports::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(reports::Column::ReporterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(reports::Entity, reports::Column::ReporterId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(reports::Column::ReportedUserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(reports::Entity, reports::Column::ReportedUserId)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(reports::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(reports::Entity, reports::Column::BuzzId)
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(reports::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(reports::Entity, reports::Column::ReplyId)
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(reports::Column::Reason).text().not_null())
+                    .col(ColumnDef::new(reports::Column::Details).text())
+                    .col(ColumnDef::new(reports::Column::ContentSnapshot).text())
+                    .col(ColumnDef::new(reports::Column::Status).text().not_null())
+                    .col(
+                        ColumnDef::new(reports::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(reports::Column::ResolvedBy).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(reports::Entity, reports::Column::ResolvedBy)
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(reports::Column::ResolvedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The queue only ever looks at open reports, oldest first.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "CREATE INDEX IF NOT EXISTS idx_reports_open
+                    ON reports (created_at) WHERE status = 'open'"
+                    .to_string(),
+            ))
+            .await?;
+
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(moderation_actions::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(moderation_actions::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(moderation_actions::Column::ModeratorId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                moderation_actions::Entity,
+                                moderation_actions::Column::ModeratorId,
+                            )
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(
+                        ColumnDef::new(moderation_actions::Column::Action)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(moderation_actions::Column::UserId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                moderation_actions::Entity,
+                                moderation_actions::Column::UserId,
+                            )
+                            .to(users::Entity, users::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(moderation_actions::Column::BuzzId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                moderation_actions::Entity,
+                                moderation_actions::Column::BuzzId,
+                            )
+                            .to(buzz::Entity, buzz::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(moderation_actions::Column::ReplyId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                moderation_actions::Entity,
+                                moderation_actions::Column::ReplyId,
+                            )
+                            .to(reply::Entity, reply::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(moderation_actions::Column::ReportId).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                moderation_actions::Entity,
+                                moderation_actions::Column::ReportId,
+                            )
+                            .to(reports::Entity, reports::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(
+                        ColumnDef::new(moderation_actions::Column::Reason)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(moderation_actions::Column::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(moderation_actions::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(reports::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(reply::Entity)
+                    .drop_column(reply::Column::HiddenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(buzz::Entity)
+                    .drop_column(buzz::Column::HiddenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(users::Entity)
+                    .drop_column(users::Column::IsModerator)
+                    .drop_column(users::Column::SuspendedAt)
+                    .drop_column(users::Column::SuspendedUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use entity::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220920_000001_create_rate_limit_buckets"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(rate_limit_buckets::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(rate_limit_buckets::Column::BucketKey)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(rate_limit_buckets::Column::Tokens)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(rate_limit_buckets::Column::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(rate_limit_buckets::Entity)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -12,6 +12,8 @@ CREATE TABLE IF NOT EXISTS auth (
     UNIQUE (contact_number)
 );
 
+CREATE INDEX IF NOT EXISTS idx_auth_lower_username ON auth (lower(username));
+
 CREATE TABLE IF NOT EXISTS users (
     id BIGSERIAL PRIMARY KEY,
     auth_id BIGINT NOT NULL REFERENCES auth(id),
@@ -19,17 +21,24 @@ CREATE TABLE IF NOT EXISTS users (
     profile_picture TEXT,
     description TEXT,
     location_or_region TEXT,
-    following TEXT,
-    followers TEXT,
-    upvoted TEXT,
-    created_at TIMESTAMP NOT NULL
+    created_at TIMESTAMP NOT NULL,
+    dm_policy TEXT NOT NULL DEFAULT 'everyone',
+    is_private BOOLEAN NOT NULL DEFAULT FALSE,
+    deletion_requested_at TIMESTAMP,
+    deleted_at TIMESTAMP,
+    is_moderator BOOLEAN NOT NULL DEFAULT FALSE,
+    suspended_at TIMESTAMP,
+    suspended_until TIMESTAMP
 );
 
+CREATE INDEX IF NOT EXISTS idx_users_deletion_requested_at ON users (deletion_requested_at);
+
 CREATE TABLE IF NOT EXISTS ratings (
     id BIGSERIAL PRIMARY KEY,
     upvotes BIGINT DEFAULT 0,
     views BIGINT DEFAULT 0,
-    upvoted_by TEXT
+    rebuzzes BIGINT DEFAULT 0,
+    quotes BIGINT DEFAULT 0
 );
 
 CREATE TABLE IF NOT EXISTS buzz (
@@ -41,23 +50,291 @@ CREATE TABLE IF NOT EXISTS buzz (
     buzz_words TEXT,
     mentioned_users TEXT,
     ratings_id BIGINT REFERENCES ratings(id),
-    created_at TIMESTAMP NOT NULL
+    quoted_buzz_id BIGINT REFERENCES buzz(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL,
+    edited_at TIMESTAMP,
+    deleted_at TIMESTAMP,
+    hidden_at TIMESTAMP,
+    search_vector tsvector GENERATED ALWAYS AS (to_tsvector('english', description)) STORED
+);
+
+CREATE INDEX IF NOT EXISTS idx_buzz_search_vector ON buzz USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_buzz_deleted_at ON buzz (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS rebuzz (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buzz_id BIGINT NOT NULL REFERENCES buzz(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (user_id, buzz_id)
+);
+
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buzz_id BIGINT NOT NULL REFERENCES buzz(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (user_id, buzz_id)
 );
 
 CREATE TABLE IF NOT EXISTS reply(
     id BIGSERIAL PRIMARY KEY,
     user_id BIGINT NOT NULL REFERENCES users(id),
     buzz_id BIGINT NOT NULL REFERENCES buzz(id),
+    parent_reply_id BIGINT REFERENCES reply(id) ON DELETE CASCADE,
     reply_content TEXT NOT NULL,
     buzz_words TEXT,
     mentioned_users TEXT,
     ratings_id BIGINT REFERENCES ratings(id),
+    created_at TIMESTAMP NOT NULL,
+    edited_at TIMESTAMP,
+    hidden_at TIMESTAMP,
+    deleted_at TIMESTAMP,
+    search_vector tsvector GENERATED ALWAYS AS (to_tsvector('english', reply_content)) STORED
+);
+
+CREATE INDEX IF NOT EXISTS idx_reply_search_vector ON reply USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_reply_deleted_at ON reply (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS buzz_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    image_link TEXT,
+    video_link TEXT,
+    buzz_words TEXT,
+    mentioned_users TEXT,
+    created_at TIMESTAMP NOT NULL,
+    revised_at TIMESTAMP NOT NULL,
+    CONSTRAINT buzz_revisions_buzz_or_reply CHECK ((buzz_id IS NULL) <> (reply_id IS NULL))
+);
+
+CREATE TABLE IF NOT EXISTS hashtags (
+    id BIGSERIAL PRIMARY KEY,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (tag)
+);
+
+CREATE TABLE IF NOT EXISTS buzz_hashtags (
+    id BIGSERIAL PRIMARY KEY,
+    hashtag_id BIGINT NOT NULL REFERENCES hashtags(id) ON DELETE CASCADE,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_buzz_hashtags_hashtag_id_created_at ON buzz_hashtags (hashtag_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_buzz_hashtags_buzz_id ON buzz_hashtags (buzz_id);
+CREATE INDEX IF NOT EXISTS idx_buzz_hashtags_reply_id ON buzz_hashtags (reply_id);
+
+CREATE TABLE IF NOT EXISTS mentions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_mentions_user_id_created_at ON mentions (user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_mentions_buzz_id ON mentions (buzz_id);
+CREATE INDEX IF NOT EXISTS idx_mentions_reply_id ON mentions (reply_id);
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    actor_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    read_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id_created_at ON notifications (user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS conversations (
+    id BIGSERIAL PRIMARY KEY,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    title TEXT,
+    is_group BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    last_message_at TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS conversation_members (
+    id BIGSERIAL PRIMARY KEY,
+    conversation_id BIGINT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    joined_at TIMESTAMP NOT NULL,
+    last_read_at TIMESTAMP,
+    UNIQUE (conversation_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversation_members_user_id ON conversation_members (user_id);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id BIGSERIAL PRIMARY KEY,
+    conversation_id BIGINT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    sender_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_messages_conversation_id_created_at ON messages (conversation_id, created_at);
+
+CREATE TABLE IF NOT EXISTS blocks (
+    id BIGSERIAL PRIMARY KEY,
+    blocker_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (blocker_id, blocked_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_blocked_id ON blocks (blocked_id);
+
+CREATE TABLE IF NOT EXISTS mutes (
+    id BIGSERIAL PRIMARY KEY,
+    muter_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    muted_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (muter_id, muted_id)
+);
+
+CREATE TABLE IF NOT EXISTS follow_requests (
+    id BIGSERIAL PRIMARY KEY,
+    requester_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (requester_id, target_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_follow_requests_target_id ON follow_requests (target_id);
+
+CREATE TABLE IF NOT EXISTS follows (
+    id BIGSERIAL PRIMARY KEY,
+    follower_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (follower_id, followee_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_follows_followee_id ON follows (followee_id);
+
+CREATE TABLE IF NOT EXISTS upvotes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ratings_id BIGINT NOT NULL REFERENCES ratings(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (user_id, ratings_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_upvotes_ratings_id ON upvotes (ratings_id);
+
+CREATE TABLE IF NOT EXISTS view_events (
+    id BIGSERIAL PRIMARY KEY,
+    ratings_id BIGINT NOT NULL REFERENCES ratings(id) ON DELETE CASCADE,
+    viewer_key TEXT NOT NULL,
+    window_start TIMESTAMP NOT NULL,
+    counted BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (ratings_id, viewer_key, window_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_view_events_uncounted ON view_events (ratings_id) WHERE NOT counted;
+
+CREATE TABLE IF NOT EXISTS security_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    ip_address TEXT,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_security_events_user_id ON security_events (user_id);
+
+CREATE TABLE IF NOT EXISTS data_exports (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL,
+    archive BYTEA,
+    requested_at TIMESTAMP NOT NULL,
+    completed_at TIMESTAMP,
+    expires_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user_id ON data_exports (user_id);
+
+CREATE TABLE IF NOT EXISTS reports (
+    id BIGSERIAL PRIMARY KEY,
+    reporter_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reported_user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE SET NULL,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    details TEXT,
+    content_snapshot TEXT,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    resolved_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_reports_open ON reports (created_at) WHERE status = 'open';
+
+CREATE TABLE IF NOT EXISTS moderation_actions (
+    id BIGSERIAL PRIMARY KEY,
+    moderator_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    action TEXT NOT NULL,
+    user_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    buzz_id BIGINT REFERENCES buzz(id) ON DELETE SET NULL,
+    reply_id BIGINT REFERENCES reply(id) ON DELETE SET NULL,
+    report_id BIGINT REFERENCES reports(id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
     created_at TIMESTAMP NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    bucket_key TEXT PRIMARY KEY,
+    tokens DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS polls (
+    id BIGSERIAL PRIMARY KEY,
+    buzz_id BIGINT NOT NULL UNIQUE REFERENCES buzz(id) ON DELETE CASCADE,
+    multiple_choice BOOLEAN NOT NULL DEFAULT FALSE,
+    hide_results BOOLEAN NOT NULL DEFAULT FALSE,
+    closes_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS poll_options (
+    id BIGSERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    UNIQUE (poll_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS poll_votes (
+    id BIGSERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    option_id BIGINT NOT NULL REFERENCES poll_options(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (poll_id, user_id, option_id)
+);
+
 CREATE TABLE IF NOT EXISTS trending (
     id BIGSERIAL PRIMARY KEY,
     trending_id BIGINT,
     description TEXT,
-    buzz_words TEXT
+    buzz_words TEXT,
+    time_window TEXT,
+    buzz_count BIGINT,
+    rank INTEGER,
+    computed_at TIMESTAMP,
+    hashtag_id BIGINT REFERENCES hashtags(id) ON DELETE CASCADE
 );
@@ -1,5 +1,29 @@
 DROP TABLE trending;
+DROP TABLE poll_votes;
+DROP TABLE poll_options;
+DROP TABLE polls;
+DROP TABLE rate_limit_buckets;
+DROP TABLE moderation_actions;
+DROP TABLE reports;
+DROP TABLE data_exports;
+DROP TABLE security_events;
+DROP TABLE mentions;
+DROP TABLE notifications;
+DROP TABLE messages;
+DROP TABLE conversation_members;
+DROP TABLE conversations;
+DROP TABLE blocks;
+DROP TABLE mutes;
+DROP TABLE follow_requests;
+DROP TABLE follows;
+DROP TABLE upvotes;
+DROP TABLE view_events;
+DROP TABLE buzz_hashtags;
+DROP TABLE hashtags;
+DROP TABLE buzz_revisions;
 DROP TABLE reply;
+DROP TABLE rebuzz;
+DROP TABLE bookmarks;
 DROP TABLE buzz;
 DROP TABLE ratings;
 DROP TABLE users;